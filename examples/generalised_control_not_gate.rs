@@ -53,15 +53,15 @@ fn main() -> Result<(), QuantrError> {
 fn multicnot<const NUM_CONTROL: usize>(input_state: ProductState) -> Option<SuperPosition> {
     let mut copy_state = input_state;
     if copy_state.get_qubits() == [Qubit::One; NUM_CONTROL] {
-        copy_state.get_mut_qubits()[NUM_CONTROL - 1] = Qubit::Zero;
-        return Some(copy_state.into());
+        copy_state.set(NUM_CONTROL - 1, Qubit::Zero).ok()?;
+        Some(copy_state.into())
     } else if copy_state.get_qubits() == {
         let mut temp = [Qubit::One; NUM_CONTROL];
         temp[NUM_CONTROL - 1] = Qubit::Zero;
         temp
     } {
-        copy_state.get_mut_qubits()[NUM_CONTROL - 1] = Qubit::One;
-        return Some(copy_state.into());
+        copy_state.set(NUM_CONTROL - 1, Qubit::One).ok()?;
+        Some(copy_state.into())
     } else {
         None
     }
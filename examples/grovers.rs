@@ -45,7 +45,7 @@ fn main() -> Result<(), QuantrError> {
 
     // Simulates the circuit
     let simulated_circuit = circuit.simulate();
-    println!("");
+    println!();
 
     // Displays bin count of the resulting 500 repeat measurements of
     // superpositions. bin_count is a HashMap<ProductState, usize>.
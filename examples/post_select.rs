@@ -18,7 +18,7 @@ fn main() -> Result<(), QuantrError> {
     let mut qc = Circuit::new(3)?;
 
     qc.add_repeating_gate(Gate::H, &[0, 1, 2])?
-        .add_gate(Gate::Custom(post_select, vec![], "P".to_string()), 1)?;
+        .add_gate(Gate::custom_single(post_select, "P"), 1)?;
 
     let mut printer = Printer::new(&qc);
     printer.print_diagram();
@@ -8,15 +8,21 @@
 * Author: Andrew Rowan Barlow <a.barlow.dev@gmail.com>
 */
 
-use super::circuit::gate::GateInfo;
+use super::circuit::gate::{is_unitary, GateCategory, GateInfo, GateKind, ParamId};
 use crate::error::QuantrError;
-use crate::states::SuperPosition;
-use crate::{Gate, SimulatedCircuit};
+use crate::states::{ProductState, Qubit, SuperPosition};
+use crate::{Float, Gate, SimulatedCircuit};
+use core::iter::zip;
+use num_complex::Complex64;
 use std::collections::HashMap;
+use std::f64::consts::FRAC_PI_2;
+use std::fmt;
+use std::sync::OnceLock;
 
 pub mod gate;
 pub mod measurement;
 pub mod printer;
+mod qasm;
 mod simulation;
 mod standard_gate_ops;
 pub mod states;
@@ -24,20 +30,38 @@ pub mod states;
 pub(crate) type QResult<T> = Result<T, QuantrError>;
 
 /// A quantum circuit where gates can be appended and then simulated to produce a [SimulatedCircuit] struct.
-pub struct Circuit {
+///
+/// The amplitudes of the register are simulated with the precision of `T`, which is `f64` by
+/// default. See [Circuit32] and [Circuit64].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Circuit<T: Float = f64> {
     pub(crate) circuit_gates: Vec<Gate>,
     pub(crate) num_qubits: usize,
-    pub(crate) register: Option<SuperPosition>,
+    pub(crate) register: Option<SuperPosition<T>>,
     pub(crate) config_progress: bool,
+    pub(crate) enforce_unique_custom_names: bool,
+    pub(crate) suppress_warnings: bool,
+    pub(crate) seed: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) categorised_gates: OnceLock<Vec<GateCategory>>,
 }
 
-// The tolerance for declaring non-zero amplitudes.
+/// A circuit that simulates the amplitudes of its register with `f32` precision.
+pub type Circuit32 = Circuit<f32>;
+
+/// A circuit that simulates the amplitudes of its register with `f64` precision, which is the
+/// same as [Circuit].
+pub type Circuit64 = Circuit<f64>;
+
 impl Circuit {
     /// Initialises a new circuit.
     ///
     /// The lifetime is due to the slices of control qubits for [Gate::Custom]. That is, the slice
     /// argument must outlive the circuit.
     ///
+    /// The amplitudes of the register are simulated with `f64` precision. To choose the precision,
+    /// see [Circuit::new_with_precision].
+    ///
     /// # Example
     /// ```
     /// use quantr::Circuit;
@@ -46,8 +70,26 @@ impl Circuit {
     /// let quantum_circuit: Circuit = Circuit::new(3).unwrap();
     /// ```
     pub fn new(num_qubits: usize) -> QResult<Circuit> {
+        Self::new_with_precision(num_qubits)
+    }
+}
+
+impl<T: Float> Circuit<T> {
+    /// Initialises a new circuit, where the amplitudes of the register are simulated with the
+    /// precision of `T`.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit32, Gate};
+    ///
+    /// let mut quantum_circuit: Circuit32 = Circuit32::new_with_precision(1).unwrap();
+    /// quantum_circuit.add_gate(Gate::X, 0).unwrap();
+    ///
+    /// assert_eq!(quantum_circuit.simulate().probabilities(), vec![0f64, 1f64]);
+    /// ```
+    pub fn new_with_precision(num_qubits: usize) -> QResult<Circuit<T>> {
         if num_qubits == 0 {
-            return Err(QuantrError {
+            return Err(QuantrError::Other {
                 message: String::from("The initialised circuit must have at least one wire."),
             });
         }
@@ -58,6 +100,10 @@ impl Circuit {
             num_qubits,
             register: None,
             config_progress: false,
+            enforce_unique_custom_names: false,
+            suppress_warnings: false,
+            seed: None,
+            categorised_gates: OnceLock::new(),
         })
     }
 
@@ -76,6 +122,9 @@ impl Circuit {
 
     /// Sets whether the simulation progress of the circuit shall be printed to the terminal.
     ///
+    /// By default, each gate is printed as it is applied. With the `indicatif` feature, an
+    /// indicatif progress bar over the number of gates is drawn instead.
+    ///
     /// # Example
     /// ```
     /// use quantr::{Circuit};
@@ -87,6 +136,89 @@ impl Circuit {
         self.config_progress = progress;
     }
 
+    /// Toggles whether custom gates must have unique names.
+    ///
+    /// When enabled, adding a [Gate::Custom] returns an error if its name is already used by a
+    /// custom gate in the circuit that implements a different function. This avoids ambiguous
+    /// circuit diagrams. By default, this is disabled.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{ProductState, SuperPosition};
+    ///
+    /// fn flip(mut prod: ProductState) -> Option<SuperPosition> {
+    ///     Some(SuperPosition::from(prod.invert_digit(0).unwrap().clone()))
+    /// }
+    ///
+    /// fn identity(prod: ProductState) -> Option<SuperPosition> {
+    ///     Some(SuperPosition::from(prod))
+    /// }
+    ///
+    /// let mut quantum_circuit = Circuit::new(1).unwrap();
+    /// quantum_circuit.set_enforce_unique_custom_names(true);
+    /// quantum_circuit.add_gate(Gate::custom_single(flip, "U"), 0).unwrap();
+    ///
+    /// assert!(quantum_circuit.add_gate(Gate::custom_single(identity, "U"), 0).is_err());
+    /// ```
+    pub fn set_enforce_unique_custom_names(&mut self, enforce: bool) {
+        self.enforce_unique_custom_names = enforce;
+    }
+
+    /// Toggles whether warnings are printed to stderr.
+    ///
+    /// This is inherited by the [SimulatedCircuit] and [crate::Printer] of the circuit, so that
+    /// no warnings are printed when quantr is embedded in another application. By default,
+    /// warnings are printed.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, Printer};
+    ///
+    /// let mut quantum_circuit = Circuit::new(1).unwrap();
+    /// quantum_circuit.set_suppress_warnings(true);
+    /// for _ in 0..15 {
+    ///     quantum_circuit.add_gate(Gate::H, 0).unwrap();
+    /// }
+    ///
+    /// // No warning is printed for the wide diagram.
+    /// Printer::new(&quantum_circuit).print_diagram();
+    /// ```
+    pub fn set_suppress_warnings(&mut self, suppress: bool) {
+        self.suppress_warnings = suppress;
+    }
+
+    /// Sets the seed of the random number generator that is used to simulate the circuit.
+    ///
+    /// This decides the outcomes of the [Gate::MeasureReset] gates, so that a circuit with
+    /// mid-circuit measurements simulates to the same register each time. The seed is inherited
+    /// by the [SimulatedCircuit], see [SimulatedCircuit::set_seed]. By default no seed is set.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap()
+    ///     .add_gate(Gate::MeasureReset(0), 0).unwrap();
+    /// circuit.set_seed(42);
+    ///
+    /// let first = circuit.clone_and_simulate();
+    /// let second = circuit.clone_and_simulate();
+    /// assert_eq!(first.get_measurements(), second.get_measurements());
+    /// ```
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    // A generator from the seed of the circuit, or from the global generator if no seed is set.
+    pub(crate) fn rng(&self) -> fastrand::Rng {
+        match self.seed {
+            Some(seed) => fastrand::Rng::with_seed(seed),
+            None => fastrand::Rng::new(),
+        }
+    }
+
     /// Returns the slice of gates that have been added to the circuit.
     ///
     /// It is a flattened vector which is buffered with identity gates.
@@ -104,6 +236,226 @@ impl Circuit {
         self.circuit_gates.as_slice()
     }
 
+    /// Checks that the mapping of each [Gate::Custom] and [Gate::CustomArg] in the circuit is
+    /// unitary.
+    ///
+    /// The mapping is evaluated on every product state of the qubits that the gate acts on, its
+    /// control nodes and position, from which its matrix is assembled and checked to be unitary.
+    /// An error is returned for the first custom gate that is not unitary, or that maps to a
+    /// superposition with the wrong number of qubits. Non-unitary gates lead to wrong results
+    /// from [SimulatedCircuit::measure_all], and so this can be used to catch them before the
+    /// circuit is simulated.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{ProductState, SuperPosition};
+    ///
+    /// // Maps every state to |0>, which is not unitary.
+    /// fn reset(_prod: ProductState) -> Option<SuperPosition> {
+    ///     Some(SuperPosition::new(1).unwrap())
+    /// }
+    ///
+    /// let mut quantum_circuit: Circuit = Circuit::new(2).unwrap();
+    /// quantum_circuit.add_gate(Gate::custom_single(reset, "R"), 1).unwrap();
+    ///
+    /// assert!(quantum_circuit.check_custom_unitarity().is_err());
+    /// ```
+    pub fn check_custom_unitarity(&self) -> QResult<()> {
+        for (index, gate) in self.circuit_gates.iter().enumerate() {
+            if !gate.is_custom_gate() {
+                continue;
+            }
+
+            let matrix: Vec<Vec<Complex64>> = gate.matrix_from_images()?;
+            if !is_unitary(matrix.len(), |row, column| matrix[row][column]) {
+                return Err(QuantrError::Other {
+                    message: format!(
+                        "The custom gate, {}, on wire {} of column {}, does not have a unitary mapping.",
+                        gate.get_name(),
+                        index % self.num_qubits,
+                        index / self.num_qubits
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the depth of the circuit, which is the number of columns.
+    ///
+    /// Gates with control nodes are placed in their own column when added to the circuit, and so
+    /// each contributes one to the depth.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut quantum_circuit: Circuit = Circuit::new(3).unwrap();
+    /// quantum_circuit.add_gates(&[Gate::H, Gate::CNot(0), Gate::X]).unwrap();
+    ///
+    /// // The CNot is pushed into its own column.
+    /// assert_eq!(quantum_circuit.depth(), 2);
+    /// ```
+    pub fn depth(&self) -> usize {
+        self.circuit_gates.len() / self.num_qubits
+    }
+
+    /// Returns the number of each gate in the circuit, labelled by the name of the gate that is
+    /// displayed in the circuit diagram. The identity gates and barriers are not counted.
+    ///
+    /// Controlled gates are labelled by the name of the gate acting on the target, for example
+    /// [Gate::CNot] is counted as "X".
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut quantum_circuit: Circuit = Circuit::new(2).unwrap();
+    /// quantum_circuit.add_repeating_gate(Gate::H, &[0, 1]).unwrap();
+    ///
+    /// assert_eq!(quantum_circuit.gate_counts().get("H"), Some(&2));
+    /// ```
+    pub fn gate_counts(&self) -> HashMap<String, usize> {
+        let mut counts: HashMap<String, usize> = Default::default();
+        for gate in self
+            .circuit_gates
+            .iter()
+            .filter(|gate| !matches!(gate, Gate::Id | Gate::Barrier))
+        {
+            *counts.entry(gate.get_name()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the duration of the circuit, where each column lasts as long as its longest gate.
+    ///
+    /// The duration of each gate is given by `gate_duration`, with the exception of
+    /// [Gate::Delay] which lasts for its given duration, and [Gate::Id] and [Gate::Barrier] which
+    /// have no duration.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut quantum_circuit: Circuit = Circuit::new(2).unwrap();
+    /// quantum_circuit
+    ///     .add_gates(&[Gate::H, Gate::Delay(3f64)]).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// // The CNot takes two units of time, and all other gates take one.
+    /// let duration: f64 = quantum_circuit.weighted_depth(|gate| match gate {
+    ///     Gate::CNot(_) => 2f64,
+    ///     _ => 1f64,
+    /// });
+    ///
+    /// assert_eq!(duration, 5f64);
+    /// ```
+    pub fn weighted_depth<F: Fn(&Gate) -> f64>(&self, gate_duration: F) -> f64 {
+        self.circuit_gates
+            .chunks(self.num_qubits)
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|gate| match gate {
+                        Gate::Id | Gate::Barrier => 0f64,
+                        Gate::Delay(duration) => *duration,
+                        _ => gate_duration(gate),
+                    })
+                    .fold(0f64, f64::max)
+            })
+            .sum()
+    }
+
+    /// Returns the as-soon-as-possible schedule of the circuit, where each gate starts as soon as
+    /// all of the wires it acts on are free.
+    ///
+    /// Each entry is the column and wire of a gate, followed by its start and end times. The
+    /// duration of each gate is given by `gate_duration`, with the same exceptions as
+    /// [Circuit::weighted_depth]. A column made only of [Gate::Barrier], as added by
+    /// [Circuit::add_barrier], synchronises all wires. Otherwise, a barrier takes no time like
+    /// [Gate::Id], and neither are included in the schedule.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut quantum_circuit: Circuit = Circuit::new(2).unwrap();
+    /// quantum_circuit
+    ///     .add_gates(&[Gate::H, Gate::Delay(3f64)]).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// let schedule = quantum_circuit.schedule(|_| 1f64);
+    ///
+    /// assert_eq!(schedule, vec![(0, 0, 0f64, 1f64), (0, 1, 0f64, 3f64), (1, 1, 3f64, 4f64)]);
+    /// ```
+    pub fn schedule<F: Fn(&Gate) -> f64>(&self, gate_duration: F) -> Vec<(usize, usize, f64, f64)> {
+        let mut wire_free_at: Vec<f64> = vec![0f64; self.num_qubits];
+        let mut schedule: Vec<(usize, usize, f64, f64)> = Vec::new();
+        for (column_num, column) in self.circuit_gates.chunks(self.num_qubits).enumerate() {
+            if column.iter().all(|gate| *gate == Gate::Barrier) {
+                let latest: f64 = wire_free_at.iter().copied().fold(0f64, f64::max);
+                wire_free_at.fill(latest);
+                continue;
+            }
+
+            for (wire, gate) in column.iter().enumerate() {
+                if matches!(gate, Gate::Id | Gate::Barrier) {
+                    continue;
+                }
+
+                let mut wires: Vec<usize> = gate.get_nodes().unwrap_or_default();
+                wires.push(wire);
+                let start: f64 = wires.iter().map(|w| wire_free_at[*w]).fold(0f64, f64::max);
+                let end: f64 = start
+                    + match gate {
+                        Gate::Delay(duration) => *duration,
+                        _ => gate_duration(gate),
+                    };
+                for w in wires {
+                    wire_free_at[w] = end;
+                }
+                schedule.push((column_num, wire, start, end));
+            }
+        }
+        schedule
+    }
+
+    /// Removes the columns of the circuit that only contain [Gate::Id].
+    ///
+    /// This does not change the result of simulating the circuit, but reduces its depth and the
+    /// width of its diagram. Columns of [Gate::Delay] are kept, as they carry timing information.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut quantum_circuit: Circuit = Circuit::new(2).unwrap();
+    /// quantum_circuit
+    ///     .add_gate(Gate::H, 0).unwrap()
+    ///     .add_gates(&[Gate::Id, Gate::Id]).unwrap()
+    ///     .add_gate(Gate::X, 1).unwrap();
+    ///
+    /// quantum_circuit.compact();
+    /// assert_eq!(quantum_circuit.depth(), 2);
+    /// ```
+    pub fn compact(&mut self) -> &mut Circuit<T> {
+        let circuit_gates: Vec<Gate> = self
+            .circuit_gates
+            .chunks(self.num_qubits)
+            .filter(|column| column.iter().any(|gate| *gate != Gate::Id))
+            .flatten()
+            .cloned()
+            .collect();
+
+        if circuit_gates.len() != self.circuit_gates.len() {
+            self.circuit_gates = circuit_gates;
+            self.categorised_gates.take();
+        }
+        self
+    }
+
     /// Adds a single gate to the circuit.
     ///
     /// If wanting to add multiple gates, or a single gate repeatedly across multiple wires, see
@@ -121,10 +473,85 @@ impl Circuit {
     /// // -------
     /// // -------
     /// ```
-    pub fn add_gate(&mut self, gate: Gate, position: usize) -> QResult<&mut Circuit> {
+    pub fn add_gate(&mut self, gate: Gate, position: usize) -> QResult<&mut Circuit<T>> {
         Self::add_gates_with_positions(self, HashMap::from([(position, gate)]))
     }
 
+    /// Checks whether a gate can be added to the circuit at the given position, without adding it.
+    ///
+    /// The position, and any control nodes of the gate, must be within the number of qubits of
+    /// the circuit and not overlap. The name of a custom gate must also be in ASCII, and not be
+    /// used by a custom gate of a different function if unique names are enforced. This gives the
+    /// same error that [Circuit::add_gate] would, which is useful for validating gates before they
+    /// are committed to the circuit.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let quantum_circuit: Circuit = Circuit::new(2).unwrap();
+    ///
+    /// assert!(quantum_circuit.can_add(&Gate::CNot(0), 1).is_ok());
+    /// assert!(quantum_circuit.can_add(&Gate::Toffoli(0, 1), 2).is_err());
+    /// ```
+    pub fn can_add(&self, gate: &Gate, position: usize) -> QResult<()> {
+        if position >= self.num_qubits {
+            return Err(QuantrError::QubitOutOfBounds {
+                index: position,
+                num_qubits: self.num_qubits,
+                message: format!(
+                    "The position, {}, is out of bounds for the circuit with {} qubits.",
+                    position, self.num_qubits
+                ),
+            });
+        }
+
+        let mut column: Vec<Gate> = vec![Gate::Id; self.num_qubits];
+        column[position] = gate.clone();
+        self.check_column(&column)
+    }
+
+    /// Adds a single gate into the last column of the circuit if its wire is free, otherwise the
+    /// gate is added in a new column like [Circuit::add_gate].
+    ///
+    /// Returns `true` if the gate was packed into the last column. Gates with control nodes are
+    /// always added in a new column, as they are isolated in their own column. Similarly, a
+    /// single gate cannot be packed into a column that contains a gate with control nodes.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut quantum_circuit: Circuit = Circuit::new(2).unwrap();
+    /// quantum_circuit.add_gate(Gate::X, 0).unwrap();
+    ///
+    /// assert!(quantum_circuit.try_pack_gate(Gate::H, 1).unwrap());
+    /// assert!(!quantum_circuit.try_pack_gate(Gate::Y, 1).unwrap());
+    ///
+    /// // Produces the circuit:
+    /// // -- X ---------
+    /// // -- H ---- Y --
+    /// ```
+    pub fn try_pack_gate(&mut self, gate: Gate, position: usize) -> QResult<bool> {
+        self.can_add(&gate, position)?;
+        let last_column_start: Option<usize> =
+            self.circuit_gates.len().checked_sub(self.num_qubits);
+        if let Some(start) = last_column_start {
+            let last_column: &mut [Gate] = &mut self.circuit_gates[start..];
+            if gate.is_single_gate()
+                && last_column[position] == Gate::Id
+                && last_column.iter().all(|g| g.is_single_gate())
+            {
+                last_column[position] = gate;
+                self.categorised_gates.take();
+                return Ok(true);
+            }
+        }
+
+        self.add_gate(gate, position)?;
+        Ok(false)
+    }
+
     /// Add a column of gates specifying the position for each gate.
     ///
     /// A `HashMap<usize, Gate>` is used to place gates onto their desired position.
@@ -152,12 +579,14 @@ impl Circuit {
     pub fn add_gates_with_positions(
         &mut self,
         gates_with_positions: HashMap<usize, Gate>,
-    ) -> QResult<&mut Circuit> {
+    ) -> QResult<&mut Circuit<T>> {
         // If any keys are out of bounds, return an error.
         if let Some(out_of_bounds_key) =
             gates_with_positions.keys().find(|k| *k >= &self.num_qubits)
         {
-            return Err(QuantrError {
+            return Err(QuantrError::QubitOutOfBounds {
+                index: *out_of_bounds_key,
+                num_qubits: self.num_qubits,
                 message: format!(
                     "The position, {}, is out of bounds for the circuit with {} qubits.",
                     out_of_bounds_key, self.num_qubits
@@ -177,16 +606,54 @@ impl Circuit {
             );
         }
 
-        // No overlapping gates
-        Self::has_overlapping_controls_and_target(&gates_to_add, self.num_qubits)?;
+        // No overlapping gates, and valid custom gate names
+        self.check_column(&gates_to_add)?;
 
         // Push any multi-controlled gates to isolated columns
-        Self::push_multi_gates(&mut gates_to_add)?;
+        Self::push_multi_gates(&mut gates_to_add);
 
         self.circuit_gates.extend(gates_to_add);
+        self.categorised_gates.take();
         Ok(self)
     }
 
+    /// Add a column of gates from a slice of wire and gate pairs.
+    ///
+    /// This is the same as [Circuit::add_gates_with_positions], however avoids constructing a
+    /// `HashMap` for small columns. An error is returned if a wire is listed more than once.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut quantum_circuit: Circuit = Circuit::new(3).unwrap();
+    /// // Adds gates on wires 0 and 2, implicitly leaving wire 1 bare.
+    /// quantum_circuit.add_sparse_column(&[(0, Gate::X), (2, Gate::H)]).unwrap();
+    ///
+    /// // Produces the circuit:
+    /// // -- X --
+    /// // -------
+    /// // -- H --
+    /// ```
+    pub fn add_sparse_column(&mut self, entries: &[(usize, Gate)]) -> QResult<&mut Circuit<T>> {
+        let mut gates_with_positions: HashMap<usize, Gate> = HashMap::with_capacity(entries.len());
+        for (position, gate) in entries {
+            if gates_with_positions
+                .insert(*position, gate.clone())
+                .is_some()
+            {
+                return Err(QuantrError::Other {
+                    message: format!(
+                        "The wire, {}, has been assigned more than one gate in the column.",
+                        position
+                    ),
+                });
+            }
+        }
+
+        self.add_gates_with_positions(gates_with_positions)
+    }
+
     /// Add a column of gates.
     ///
     /// Expects the input vector to specify the gate that is added to *each* wire. That is, the
@@ -207,27 +674,98 @@ impl Circuit {
     /// // -- X --
     /// // -- Y --
     /// ```
-    pub fn add_gates(&mut self, gates: &[Gate]) -> QResult<&mut Circuit> {
+    pub fn add_gates(&mut self, gates: &[Gate]) -> QResult<&mut Circuit<T>> {
         // Ensured we have a gate for every wire.
         if gates.len() != self.num_qubits {
-            return Err(QuantrError {
-                message: format!("The number of gates, {}, does not match the number of wires, {}. All wires must have gates added.", gates.len(), self.num_qubits)
+            return Err(QuantrError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: gates.len(),
+                message: format!("The number of gates, {}, does not match the number of wires, {}. All wires must have gates added.", gates.len(), self.num_qubits),
             });
         }
 
         // Make sure there are no control nodes that overlap with it's other nodes.
-        Self::has_overlapping_controls_and_target(gates, self.num_qubits)?;
+        self.check_column(gates)?;
 
         // Push n-gates to another line (double, triple, etc.)
         let mut gates_vec: Vec<Gate> = gates.to_vec();
-        Self::push_multi_gates(&mut gates_vec)?;
+        Self::push_multi_gates(&mut gates_vec);
         self.circuit_gates.extend(gates_vec);
+        self.categorised_gates.take();
+        Ok(self)
+    }
+
+    /// Appends the columns of another circuit onto the end of this circuit.
+    ///
+    /// This is useful for building circuits from reusable blocks. The register of `other` is not
+    /// appended. An error is returned if the circuits have a different number of qubits.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut h_block = Circuit::new(2).unwrap();
+    /// h_block.add_repeating_gate(Gate::H, &[0, 1]).unwrap();
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap()
+    ///     .append(&h_block).unwrap();
+    ///
+    /// assert_eq!(circuit.get_gates(), &[Gate::X, Gate::Id, Gate::H, Gate::H]);
+    /// ```
+    pub fn append(&mut self, other: &Circuit<T>) -> QResult<&mut Circuit<T>> {
+        if other.num_qubits != self.num_qubits {
+            return Err(QuantrError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: other.num_qubits,
+                message: format!("The appended circuit has {} qubits, while this circuit has {} qubits. These must equal each other.", other.num_qubits, self.num_qubits),
+            });
+        }
+
+        self.check_custom_names(&other.circuit_gates)?;
+        self.circuit_gates.extend_from_slice(&other.circuit_gates);
+        self.categorised_gates.take();
         Ok(self)
     }
 
+    /// Returns a circuit made of the columns of this circuit repeated `times` times.
+    ///
+    /// This is useful for Trotterisation, or the iterations of amplitude amplification. The
+    /// columns are copied as they are, and so gates with control nodes stay in their own columns.
+    /// The resulting circuit has the default register, and the configuration of this circuit.
+    /// Repeating a circuit zero times returns a circuit without any gates.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut block = Circuit::new(2).unwrap();
+    /// block.add_gate(Gate::H, 0).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// let circuit: Circuit = block.repeat(2).unwrap();
+    ///
+    /// assert_eq!(
+    ///     circuit.get_gates(),
+    ///     &[Gate::H, Gate::Id, Gate::Id, Gate::CNot(0), Gate::H, Gate::Id, Gate::Id, Gate::CNot(0)]
+    /// );
+    /// ```
+    pub fn repeat(&self, times: usize) -> QResult<Circuit<T>> {
+        let mut circuit: Circuit<T> = Circuit::new_with_precision(self.num_qubits)?;
+        circuit.config_progress = self.config_progress;
+        circuit.enforce_unique_custom_names = self.enforce_unique_custom_names;
+        circuit.suppress_warnings = self.suppress_warnings;
+        circuit.seed = self.seed;
+
+        for _ in 0..times {
+            circuit.append(self)?;
+        }
+        Ok(circuit)
+    }
+
     // Pushes multi-controlled gates into their own column. Potentially expensive operation to
     // insert new elements at smaller positions into a long vector.
-    fn push_multi_gates(gates: &mut Vec<Gate>) -> QResult<()> {
+    fn push_multi_gates(gates: &mut Vec<Gate>) {
         let mut extended_vec: Vec<Gate> = Default::default();
         let mut multi_gate_positions: Vec<usize> = Default::default();
 
@@ -235,11 +773,6 @@ impl Circuit {
         let mut found_multi: bool = false;
         let mut found_second: bool = false;
         for gate in gates.iter() {
-            if let Gate::Custom(_, _, name) = gate {
-                if !name.is_ascii() {
-                    return Err(QuantrError { message: format!("The custom function name, {}, does not only use ASCII chars. This could lead to problems in printing the circuit diagram. This warning will be promoted to an Error in the next major release.", name) } );
-                }
-            }
             if gate != &Gate::Id {
                 if found_multi {
                     found_second = true;
@@ -265,26 +798,52 @@ impl Circuit {
             }
             gates.extend(extended_vec);
         }
+    }
+
+    // Validates a column of gates before it is added to the circuit, which is shared by all
+    // methods that add gates.
+    fn check_column(&self, gates: &[Gate]) -> QResult<()> {
+        Self::has_overlapping_controls_and_target(gates, self.num_qubits)?;
+        self.check_custom_names(gates)?;
+
+        for gate in gates.iter() {
+            if let Gate::Custom(_, _, name) | Gate::CustomArg(_, _, _, name) = gate {
+                if !name.is_ascii() {
+                    return Err(QuantrError::Other { message: format!("The custom function name, {}, does not only use ASCII chars. This could lead to problems in printing the circuit diagram. This warning will be promoted to an Error in the next major release.", name) } );
+                }
+            }
+        }
 
         Ok(())
     }
 
     fn has_overlapping_controls_and_target(gates: &[Gate], circuit_size: usize) -> QResult<()> {
         for (pos, gate) in gates.iter().enumerate() {
+            // check that controlled gates only control a single qubit gate.
+            gate.check_controlled()?;
             if let Some(nodes) = gate.get_nodes() {
+                // check the nodes are in bounds before they are used for indexing.
+                for &node in nodes.iter() {
+                    if node >= circuit_size {
+                        return Err(QuantrError::QubitOutOfBounds {
+                            index: node,
+                            num_qubits: circuit_size,
+                            message: format!("The control node at position {:?}, is greater than the umnber of qubits {}.", node, circuit_size),
+                        });
+                    }
+                }
                 // check for overlapping control nodes.
                 if Self::contains_repeating_values(circuit_size, &nodes) {
-                    return Err(QuantrError {
+                    return Err(QuantrError::OverlappingControlNodes {
+                        gate: format!("{:?}", gate),
                         message: format!("The gate, {:?}, has overlapping control nodes.", gate),
                     });
                 }
                 if nodes.contains(&pos) {
-                    return Err(QuantrError { message: format!("The gate, {:?}, has a control node that equals the gate's position {}.", gate, pos) });
-                }
-                for &node in nodes.iter() {
-                    if node >= circuit_size {
-                        return Err(QuantrError { message: format!("The control node at position {:?}, is greater than the umnber of qubits {}.", node, circuit_size) });
-                    }
+                    return Err(QuantrError::OverlappingControlNodes {
+                        gate: format!("{:?}", gate),
+                        message: format!("The gate, {:?}, has a control node that equals the gate's position {}.", gate, pos),
+                    });
                 }
             }
         }
@@ -304,6 +863,34 @@ impl Circuit {
         false
     }
 
+    // Checks that the names of the custom gates are not shared by a custom gate of a different
+    // function, if unique names are enforced. The functions are compared by their addresses.
+    fn check_custom_names(&self, gates: &[Gate]) -> QResult<()> {
+        if !self.enforce_unique_custom_names {
+            return Ok(());
+        }
+
+        let mut named_functions: HashMap<&str, *const ()> = HashMap::new();
+        for gate in self.circuit_gates.iter().chain(gates) {
+            let (func, name): (*const (), &String) = match gate {
+                Gate::Custom(func, _, name) => (*func as *const (), name),
+                Gate::CustomArg(func, _, _, name) => (*func as *const (), name),
+                _ => continue,
+            };
+            let first_func = named_functions.entry(name.as_str()).or_insert(func);
+            if *first_func != func {
+                return Err(QuantrError::Other {
+                    message: format!(
+                        "The custom gate name, {}, is already used by a custom gate of a different function.",
+                        name
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     /// Place a single gate repeatedly onto multiple wires.
     ///
     /// For adding multiple different gates, refer to [Circuit::add_gates]
@@ -321,10 +908,14 @@ impl Circuit {
     /// // -- H --
     /// // -- H --
     /// ```
-    pub fn add_repeating_gate(&mut self, gate: Gate, positions: &[usize]) -> QResult<&mut Circuit> {
+    pub fn add_repeating_gate(
+        &mut self,
+        gate: Gate,
+        positions: &[usize],
+    ) -> QResult<&mut Circuit<T>> {
         // Incase the user has attempted to place the gate twice on the same wire.
         if Self::contains_repeating_values(self.num_qubits, positions) {
-            return Err(QuantrError {
+            return Err(QuantrError::Other {
                 message: format!(
                     "Attempted to add more than one gate onto a single wire. The positions in {:?} must all differ.", positions 
                 ),
@@ -340,51 +931,222 @@ impl Circuit {
         self.add_gates(gates.as_slice())
     }
 
-    /// Attaches the register, |0...0>, to the circuit resulting in a superposition that can be measured.
+    /// Adds a [Gate::CNot] from the control wire to each of the target wires, in the order that
+    /// the targets are given.
     ///
-    /// See [SimulatedCircuit::get_state] and [SimulatedCircuit::measure_all] for details on obtaining
-    /// observables from the resulting superposition.
-    ///
-    /// If you are not wanting the circuit to be consumed, please refer to [Circuit::clone_and_simulate].
+    /// As the gates share the control node, each is added in its own column. An error is returned
+    /// if a wire is out of bounds, a target equals the control, or a target is repeated.
     ///
     /// # Example
     /// ```
     /// use quantr::{Circuit, Gate};
     ///
-    /// let mut circuit = Circuit::new(3).unwrap();
-    /// circuit.add_gate(Gate::H, 2).unwrap();
-    ///
-    /// circuit.simulate();
+    /// let mut quantum_circuit: Circuit = Circuit::new(3).unwrap();
+    /// quantum_circuit.add_fanout(0, &[1, 2]).unwrap();
     ///
-    /// // Simulates the circuit:
-    /// // |0> -------
-    /// // |0> -------
-    /// // |0> -- H --
-    /// ````
-    pub fn simulate(mut self) -> SimulatedCircuit {
-        match self.register.take() {
-            Some(mut prepared_register) => {
-                self.simulate_with_register(&mut prepared_register);
-                SimulatedCircuit {
-                    circuit_gates: self.circuit_gates,
-                    num_qubits: self.num_qubits,
-                    register: prepared_register,
-                    config_progress: self.config_progress,
-                    disable_warnings: false,
-                }
-            }
-            None => {
-                let mut zero_register = SuperPosition::new_unchecked(self.num_qubits);
-                self.simulate_with_register(&mut zero_register);
-                SimulatedCircuit {
-                    circuit_gates: self.circuit_gates,
-                    num_qubits: self.num_qubits,
-                    register: zero_register,
-                    config_progress: self.config_progress,
-                    disable_warnings: false,
-                }
+    /// // Produces the circuit:
+    /// // -- █ ---- █ --
+    /// // -- X ---- | --
+    /// // --------- X --
+    /// ```
+    pub fn add_fanout(&mut self, control: usize, targets: &[usize]) -> QResult<&mut Circuit<T>> {
+        if let Some(out_of_bounds) = targets
+            .iter()
+            .chain([&control])
+            .find(|&&wire| wire >= self.num_qubits)
+        {
+            return Err(QuantrError::QubitOutOfBounds {
+                index: *out_of_bounds,
+                num_qubits: self.num_qubits,
+                message: format!(
+                    "The position, {}, is out of bounds for the circuit with {} qubits.",
+                    out_of_bounds, self.num_qubits
+                ),
+            });
+        }
+
+        if targets.contains(&control) {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "The control node, {}, is also a target of the fanout {:?}.",
+                    control, targets
+                ),
+            });
+        }
+
+        if Self::contains_repeating_values(self.num_qubits, targets) {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "Attempted to add more than one gate onto a single wire. The targets in {:?} must all differ.",
+                    targets
+                ),
+            });
+        }
+
+        for &target in targets {
+            self.add_gate(Gate::CNot(control), target)?;
+        }
+        Ok(self)
+    }
+
+    /// Adds a network of [Gate::Swap] that moves the qubit on wire `i` to wire `perm[i]`.
+    ///
+    /// The permutation must contain each wire of the circuit exactly once. Each swap is added in
+    /// its own column, and at most `num_qubits - 1` swaps are used.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut quantum_circuit: Circuit = Circuit::new(3).unwrap();
+    /// quantum_circuit.add_permutation(&[1, 2, 0]).unwrap();
+    ///
+    /// // Produces the circuit:
+    /// // -- Sw --------
+    /// // -- |  --- Sw -
+    /// // -- Sw --- |  -
+    /// ```
+    pub fn add_permutation(&mut self, perm: &[usize]) -> QResult<&mut Circuit<T>> {
+        if perm.len() != self.num_qubits {
+            return Err(QuantrError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: perm.len(),
+                message: format!("The permutation has {} entries, while the circuit has {} qubits. These must equal each other.", perm.len(), self.num_qubits),
+            });
+        }
+
+        if let Some(&out_of_bounds) = perm.iter().find(|&&wire| wire >= self.num_qubits) {
+            return Err(QuantrError::QubitOutOfBounds {
+                index: out_of_bounds,
+                num_qubits: self.num_qubits,
+                message: format!(
+                    "The position, {}, is out of bounds for the circuit with {} qubits.",
+                    out_of_bounds, self.num_qubits
+                ),
+            });
+        }
+
+        if Self::contains_repeating_values(self.num_qubits, perm) {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "The permutation {:?} must contain each wire of the circuit exactly once.",
+                    perm
+                ),
+            });
+        }
+
+        // wires[w] tracks which of the original qubits currently sits on wire w.
+        let mut wires: Vec<usize> = (0..self.num_qubits).collect();
+        let mut destination: Vec<usize> = vec![0; self.num_qubits];
+        for (qubit, &wire) in perm.iter().enumerate() {
+            destination[wire] = qubit;
+        }
+
+        for (wire, &qubit) in destination.iter().enumerate() {
+            let current = wires
+                .iter()
+                .position(|&on_wire| on_wire == qubit)
+                .expect("Every qubit is on some wire.");
+            if current != wire {
+                self.add_gate(Gate::Swap(current), wire)?;
+                wires.swap(current, wire);
             }
         }
+        Ok(self)
+    }
+
+    /// Adds a column of [Gate::Barrier] across all wires.
+    ///
+    /// The barrier acts as the identity, and is drawn as a dashed line across the circuit
+    /// diagram, which helps to separate the stages of an algorithm. As the column is full, gates
+    /// that are added afterwards are not packed into the column before the barrier.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut quantum_circuit: Circuit = Circuit::new(2).unwrap();
+    /// quantum_circuit.add_gate(Gate::H, 0).unwrap()
+    ///     .add_barrier()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// // Produces the circuit:
+    /// // -- H -- ┆ -- █ --
+    /// // ------- ┆ -- X --
+    /// ```
+    pub fn add_barrier(&mut self) -> &mut Circuit<T> {
+        self.circuit_gates
+            .extend(vec![Gate::Barrier; self.num_qubits]);
+        self.categorised_gates.take();
+        self
+    }
+
+    /// Attaches the register, |0...0>, to the circuit resulting in a superposition that can be measured.
+    ///
+    /// See [SimulatedCircuit::get_state] and [SimulatedCircuit::measure_all] for details on obtaining
+    /// observables from the resulting superposition.
+    ///
+    /// If you are not wanting the circuit to be consumed, please refer to [Circuit::clone_and_simulate].
+    ///
+    /// # Panics
+    /// If a [Gate::Custom] maps a product state to a superposition of the wrong dimension. To
+    /// handle this as an error instead, see [Circuit::try_simulate].
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(3).unwrap();
+    /// circuit.add_gate(Gate::H, 2).unwrap();
+    ///
+    /// circuit.simulate();
+    ///
+    /// // Simulates the circuit:
+    /// // |0> -------
+    /// // |0> -------
+    /// // |0> -- H --
+    /// ````
+    pub fn simulate(self) -> SimulatedCircuit<T> {
+        self.try_simulate()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as [Circuit::simulate], however an error is returned if a [Gate::Custom] maps a
+    /// product state to a superposition that does not have the same number of qubits as the
+    /// gate acts on (the control nodes and the gate's position).
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{ProductState, Qubit, SuperPosition};
+    ///
+    /// // Incorrectly returns a single qubit, when the gate acts on two qubits.
+    /// fn mis_sized(_prod: ProductState) -> Option<SuperPosition> {
+    ///     Some(SuperPosition::from(Qubit::One))
+    /// }
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::Custom(mis_sized, vec![0], String::from("M")), 1).unwrap();
+    ///
+    /// assert!(circuit.try_simulate().is_err());
+    /// ````
+    pub fn try_simulate(mut self) -> QResult<SimulatedCircuit<T>> {
+        let mut register: SuperPosition<T> = self
+            .register
+            .take()
+            .unwrap_or_else(|| SuperPosition::new_unchecked(self.num_qubits));
+        let measurements: Vec<(usize, Qubit)> = self.simulate_with_register(&mut register)?;
+        Ok(SimulatedCircuit {
+            circuit_gates: self.circuit_gates,
+            num_qubits: self.num_qubits,
+            register,
+            config_progress: self.config_progress,
+            disable_warnings: self.suppress_warnings,
+            seed: self.seed,
+            reset_register: None,
+            measurements,
+            categorised_gates: self.categorised_gates,
+        })
     }
 
     /// Attaches the register, |0...0>, to the circuit resulting in a superposition that can be measured,
@@ -396,6 +1158,10 @@ impl Circuit {
     ///
     /// If you are wanting the circuit to be consumed, please refer to [Circuit::simulate].
     ///
+    /// # Panics
+    /// If a [Gate::Custom] maps a product state to a superposition of the wrong dimension, see
+    /// [Circuit::try_simulate].
+    ///
     /// # Example
     /// ```
     /// use quantr::{Circuit, Gate};
@@ -408,30 +1174,506 @@ impl Circuit {
     /// // Below would be impossible if Circuit::simulate was used instead
     /// let simulated_with_H_and_X = circuit.add_gate(Gate::X, 1);
     /// ````
-    pub fn clone_and_simulate(&self) -> SimulatedCircuit {
-        match self.register.clone() {
-            Some(mut prepared_register) => {
-                self.simulate_with_register(&mut prepared_register);
-                SimulatedCircuit {
-                    circuit_gates: self.circuit_gates.clone(),
-                    num_qubits: self.num_qubits,
-                    register: prepared_register,
-                    config_progress: self.config_progress,
-                    disable_warnings: false,
-                }
+    pub fn clone_and_simulate(&self) -> SimulatedCircuit<T> {
+        let mut register: SuperPosition<T> = self
+            .register
+            .clone()
+            .unwrap_or_else(|| SuperPosition::new_unchecked(self.num_qubits));
+        let measurements: Vec<(usize, Qubit)> = self
+            .simulate_with_register(&mut register)
+            .unwrap_or_else(|error| panic!("{}", error));
+        SimulatedCircuit {
+            circuit_gates: self.circuit_gates.clone(),
+            num_qubits: self.num_qubits,
+            register,
+            config_progress: self.config_progress,
+            disable_warnings: self.suppress_warnings,
+            seed: self.seed,
+            reset_register: None,
+            measurements,
+            categorised_gates: self.categorised_gates.clone(),
+        }
+    }
+
+    /// Simulates the circuit by only tracking the product states with the given Hamming weight,
+    /// that is the number of qubits in the |1> state.
+    ///
+    /// Circuits that conserve the Hamming weight, such as particle number conserving circuits in
+    /// chemistry, only reach a subspace of `n choose hamming_weight` product states, rather than
+    /// all `2^n`. The amplitudes of this subspace are stored sparsely during the simulation, and are
+    /// expanded into the register of the returned [SimulatedCircuit] at the end.
+    ///
+    /// An error is returned if the circuit contains a gate that does not conserve the Hamming
+    /// weight, which are any gates other than the diagonal gates (see [Gate::is_diagonal]),
+    /// [Gate::Swap] and [Gate::ISwap], or if the register has an amplitude outside of the subspace.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{ProductState, Qubit, SuperPosition};
+    ///
+    /// let mut circuit = Circuit::new(3).unwrap();
+    /// circuit.change_register(SuperPosition::from(
+    ///     ProductState::new(&[Qubit::One, Qubit::Zero, Qubit::Zero]).unwrap()
+    /// )).unwrap();
+    /// circuit.add_gate(Gate::Swap(0), 2).unwrap();
+    ///
+    /// let simulated_circuit = circuit.simulate_in_subspace(1).unwrap();
+    /// assert_eq!(simulated_circuit.probabilities()[0b001], 1f64);
+    /// ```
+    pub fn simulate_in_subspace(mut self, hamming_weight: usize) -> QResult<SimulatedCircuit<T>> {
+        if let Some(gate) = self.circuit_gates.iter().find(|gate| {
+            matches!(gate, Gate::Param(_, _))
+                || !(gate.is_diagonal() || matches!(gate, Gate::Swap(_) | Gate::ISwap(_)))
+        }) {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "The gate, {:?}, does not conserve the Hamming weight, or is an unbound parameter.",
+                    gate
+                ),
+            });
+        }
+
+        let initial_register: SuperPosition<T> = self
+            .register
+            .take()
+            .unwrap_or_else(|| SuperPosition::new_unchecked(self.num_qubits));
+        let mut amplitudes: HashMap<usize, Complex64> =
+            initial_register.to_precision::<f64>().to_sparse();
+        if let Some(index) = amplitudes
+            .keys()
+            .find(|index| index.count_ones() as usize != hamming_weight)
+        {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "The register has an amplitude on the product state |{}>, which does not have a Hamming weight of {}.",
+                    ProductState::binary_basis(*index, self.num_qubits),
+                    hamming_weight
+                ),
+            });
+        }
+
+        for (index, gate) in self.circuit_gates.iter().enumerate() {
+            if *gate == Gate::Id {
+                continue;
+            }
+            amplitudes = Circuit::apply_gate_in_subspace(
+                gate,
+                index % self.num_qubits,
+                self.num_qubits,
+                &amplitudes,
+            );
+        }
+
+        Ok(SimulatedCircuit {
+            register: SuperPosition::from_sparse(amplitudes, self.num_qubits)?.to_precision(),
+            circuit_gates: self.circuit_gates,
+            num_qubits: self.num_qubits,
+            config_progress: self.config_progress,
+            disable_warnings: self.suppress_warnings,
+            seed: self.seed,
+            reset_register: None,
+            measurements: Vec::new(),
+            categorised_gates: self.categorised_gates,
+        })
+    }
+
+    /// Returns the tensor product of the circuits, where the wires of `other` are placed below the
+    /// wires of this circuit.
+    ///
+    /// The resulting circuit has `self.get_num_qubits() + other.get_num_qubits()` wires, with the
+    /// gates of `other` moved down by the number of qubits of this circuit, including their
+    /// control nodes. The columns of both circuits are placed side by side, where any gates with
+    /// control nodes are pushed into their own columns. The resulting circuit has the default
+    /// register, and the configuration of this circuit.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut first = Circuit::new(1).unwrap();
+    /// first.add_gate(Gate::H, 0).unwrap();
+    ///
+    /// let mut second = Circuit::new(2).unwrap();
+    /// second.add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// let circuit: Circuit = first.tensor(&second);
+    ///
+    /// assert_eq!(
+    ///     circuit.get_gates(),
+    ///     &[Gate::H, Gate::Id, Gate::Id, Gate::Id, Gate::Id, Gate::CNot(1)]
+    /// );
+    /// ```
+    pub fn tensor(&self, other: &Circuit<T>) -> Circuit<T> {
+        let num_columns: usize = self.depth().max(other.depth());
+        let mut circuit_gates: Vec<Gate> =
+            Vec::with_capacity(num_columns * (self.num_qubits + other.num_qubits));
+        // The circuit with fewer columns is padded with columns of identity gates.
+        let self_padding: Vec<Gate> = vec![Gate::Id; self.num_qubits];
+        let other_padding: Vec<Gate> = vec![Gate::Id; other.num_qubits];
+        let self_columns = self
+            .circuit_gates
+            .chunks(self.num_qubits)
+            .chain(std::iter::repeat(self_padding.as_slice()));
+        let other_columns = other
+            .circuit_gates
+            .chunks(other.num_qubits)
+            .chain(std::iter::repeat(other_padding.as_slice()));
+
+        for (self_column, other_column) in self_columns.zip(other_columns).take(num_columns) {
+            let mut column: Vec<Gate> = Vec::with_capacity(self.num_qubits + other.num_qubits);
+            column.extend_from_slice(self_column);
+            column.extend(
+                other_column
+                    .iter()
+                    .map(|gate| gate.shift_nodes(self.num_qubits)),
+            );
+
+            Self::push_multi_gates(&mut column);
+            circuit_gates.extend(column);
+        }
+
+        Circuit {
+            circuit_gates,
+            num_qubits: self.num_qubits + other.num_qubits,
+            register: None,
+            config_progress: self.config_progress,
+            enforce_unique_custom_names: self.enforce_unique_custom_names,
+            suppress_warnings: self.suppress_warnings,
+            seed: self.seed,
+            categorised_gates: OnceLock::new(),
+        }
+    }
+
+    /// Splits the circuit at a column boundary, returning the circuit made of the columns before
+    /// `column`, and the circuit made of the remaining columns. Both circuits have the same number
+    /// of qubits as the original.
+    ///
+    /// The first circuit keeps the custom register, if one has been set. Simulating the first
+    /// circuit and setting its resulting superposition as the register of the second is equivalent
+    /// to simulating the whole circuit.
+    ///
+    /// An error is returned if `column` is greater than the number of columns in the circuit.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit
+    ///     .add_gates(&[Gate::H, Gate::X]).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// let (first, second) = circuit.split_at_column(1).unwrap();
+    ///
+    /// assert_eq!(first.get_gates(), &[Gate::H, Gate::X]);
+    /// assert_eq!(second.get_gates(), &[Gate::Id, Gate::CNot(0)]);
+    /// ```
+    pub fn split_at_column(&self, column: usize) -> QResult<(Circuit<T>, Circuit<T>)> {
+        let num_columns: usize = self.circuit_gates.len() / self.num_qubits;
+        if column > num_columns {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "The column, {}, is out of bounds for a circuit with {} columns.",
+                    column, num_columns
+                ),
+            });
+        }
+
+        let (first_gates, second_gates) = self.circuit_gates.split_at(column * self.num_qubits);
+
+        Ok((
+            Circuit {
+                circuit_gates: first_gates.to_vec(),
+                num_qubits: self.num_qubits,
+                register: self.register.clone(),
+                config_progress: self.config_progress,
+                enforce_unique_custom_names: self.enforce_unique_custom_names,
+                suppress_warnings: self.suppress_warnings,
+                seed: self.seed,
+                categorised_gates: OnceLock::new(),
+            },
+            Circuit {
+                circuit_gates: second_gates.to_vec(),
+                num_qubits: self.num_qubits,
+                register: None,
+                config_progress: self.config_progress,
+                enforce_unique_custom_names: self.enforce_unique_custom_names,
+                suppress_warnings: self.suppress_warnings,
+                seed: self.seed,
+                categorised_gates: OnceLock::new(),
+            },
+        ))
+    }
+
+    /// Returns the inverse of the circuit, where the order of the columns is reversed and each
+    /// gate is replaced by its adjoint, see [Gate::inverse]. The positions of the control nodes are
+    /// preserved.
+    ///
+    /// Simulating a circuit followed by its inverse returns the register to its initial state,
+    /// which is useful for uncomputing ancillas. The inverse circuit has the default register. An
+    /// error is returned if the circuit contains a [Gate::Custom], a [Gate::MeasureReset] or an
+    /// unbound [Gate::Param], as these have no adjoint.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit
+    ///     .add_gates(&[Gate::H, Gate::T]).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// let inverse_circuit: Circuit = circuit.inverse().unwrap();
+    ///
+    /// assert_eq!(
+    ///     inverse_circuit.get_gates(),
+    ///     &[Gate::Id, Gate::CNot(0), Gate::H, Gate::Tdag]
+    /// );
+    /// ```
+    pub fn inverse(&self) -> QResult<Circuit<T>> {
+        let mut circuit_gates: Vec<Gate> = Vec::with_capacity(self.circuit_gates.len());
+        for column in self.circuit_gates.chunks(self.num_qubits).rev() {
+            for gate in column {
+                circuit_gates.push(gate.inverse().ok_or_else(|| QuantrError::Other {
+                    message: format!(
+                        "The circuit contains the gate, {}, which cannot be inverted.",
+                        gate.get_name()
+                    ),
+                })?);
             }
-            None => {
-                let mut zero_register = SuperPosition::new_unchecked(self.num_qubits);
-                self.simulate_with_register(&mut zero_register);
-                SimulatedCircuit {
-                    circuit_gates: self.circuit_gates.clone(),
-                    num_qubits: self.num_qubits,
-                    register: zero_register,
-                    config_progress: self.config_progress,
-                    disable_warnings: false,
+        }
+
+        Ok(Circuit {
+            circuit_gates,
+            num_qubits: self.num_qubits,
+            register: None,
+            config_progress: self.config_progress,
+            enforce_unique_custom_names: self.enforce_unique_custom_names,
+            suppress_warnings: self.suppress_warnings,
+            seed: self.seed,
+            categorised_gates: OnceLock::new(),
+        })
+    }
+
+    /// Returns a copy of the circuit where each [Gate::Param] is replaced by its gate, with the
+    /// angle given by the value of its parameter.
+    ///
+    /// This allows a parameterised circuit, such as a variational ansatz, to be defined once and
+    /// bound to new angles for each simulation. The register of the circuit is copied. An error is
+    /// returned if a parameter of the circuit is missing from `values`.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, GateKind, ParamId};
+    /// use std::collections::HashMap;
+    ///
+    /// let mut ansatz = Circuit::new(1).unwrap();
+    /// ansatz.add_gate(Gate::Param(ParamId(0), GateKind::Rz), 0).unwrap();
+    ///
+    /// let circuit: Circuit = ansatz
+    ///     .bind_parameters(&HashMap::from([(ParamId(0), 0.5f64)]))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(circuit.get_gates(), &[Gate::Rz(0.5f64)]);
+    /// ```
+    pub fn bind_parameters(&self, values: &HashMap<ParamId, f64>) -> QResult<Circuit<T>> {
+        let mut circuit_gates: Vec<Gate> = Vec::with_capacity(self.circuit_gates.len());
+        for gate in &self.circuit_gates {
+            circuit_gates.push(match gate {
+                Gate::Param(id, kind) => match values.get(id) {
+                    Some(angle) => kind.bind(*angle),
+                    None => {
+                        return Err(QuantrError::Other {
+                            message: format!(
+                                "The parameter, {:?}, has not been given a value.",
+                                id
+                            ),
+                        })
+                    }
+                },
+                _ => gate.clone(),
+            });
+        }
+
+        Ok(Circuit {
+            circuit_gates,
+            num_qubits: self.num_qubits,
+            register: self.register.clone(),
+            config_progress: self.config_progress,
+            enforce_unique_custom_names: self.enforce_unique_custom_names,
+            suppress_warnings: self.suppress_warnings,
+            seed: self.seed,
+            categorised_gates: OnceLock::new(),
+        })
+    }
+
+    /// Returns the derivative of the expectation value of a diagonal observable with respect to a
+    /// parameter, using the parameter shift rule.
+    ///
+    /// The observable is given by its diagonal in the computational basis, so that the expectation
+    /// value is `E = Σ p_i * observable_diagonal[i]`. Each gate of the parameter is shifted in
+    /// turn by ±π/2, and the derivative is the sum of `(E(θ + π/2) - E(θ - π/2)) / 2` over these
+    /// gates. The values of all parameters, including `param`, are taken from `base`.
+    ///
+    /// An error is returned if the observable does not have `2^num_qubits` entries, or if a
+    /// parameter of the circuit is missing from `base`.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, GateKind, ParamId};
+    /// use std::collections::HashMap;
+    ///
+    /// let theta = ParamId(0);
+    /// let mut ansatz = Circuit::new(1).unwrap();
+    /// ansatz.add_gate(Gate::Param(theta, GateKind::Rx), 0).unwrap();
+    ///
+    /// // The expectation of Pauli-Z is cos(θ), and so the derivative is -sin(θ).
+    /// let gradient: f64 = ansatz
+    ///     .parameter_shift_gradient(theta, &[1f64, -1f64], &HashMap::from([(theta, 0.5f64)]))
+    ///     .unwrap();
+    ///
+    /// assert!((gradient + 0.5f64.sin()).abs() < 1e-10);
+    /// ```
+    pub fn parameter_shift_gradient(
+        &self,
+        param: ParamId,
+        observable_diagonal: &[f64],
+        base: &HashMap<ParamId, f64>,
+    ) -> QResult<f64> {
+        self.check_observable(observable_diagonal)?;
+
+        let mut bound_circuit: Circuit<T> = self.bind_parameters(base)?;
+        let expectation = |circuit: &Circuit<T>| -> QResult<f64> {
+            let mut register: SuperPosition<T> = circuit
+                .register
+                .clone()
+                .unwrap_or_else(|| SuperPosition::new_unchecked(circuit.num_qubits));
+            circuit.simulate_with_register(&mut register)?;
+            Ok(zip(&register.amplitudes, observable_diagonal)
+                .map(|(amp, value)| amp.norm_sqr().into_f64() * value)
+                .sum())
+        };
+
+        let mut gradient: f64 = 0f64;
+        for (index, gate) in self.circuit_gates.iter().enumerate() {
+            if let Gate::Param(id, kind) = gate {
+                if *id != param {
+                    continue;
                 }
+                let angle: f64 = base[id];
+
+                bound_circuit.circuit_gates[index] = kind.bind(angle + FRAC_PI_2);
+                bound_circuit.categorised_gates.take();
+                let forward: f64 = expectation(&bound_circuit)?;
+
+                bound_circuit.circuit_gates[index] = kind.bind(angle - FRAC_PI_2);
+                bound_circuit.categorised_gates.take();
+                let backward: f64 = expectation(&bound_circuit)?;
+
+                bound_circuit.circuit_gates[index] = kind.bind(angle);
+                gradient += (forward - backward) / 2f64;
+            }
+        }
+
+        Ok(gradient)
+    }
+
+    /// Returns the expectation value of a diagonal observable for each value of a parameter.
+    ///
+    /// The observable is given by its diagonal in the computational basis, the same as
+    /// [Circuit::parameter_shift_gradient]. The values of the other parameters are taken from
+    /// `base`, and any value of `param` in `base` is ignored. The gates are linked once, and only
+    /// the gates of `param` are relinked for each value.
+    ///
+    /// An error is returned if the observable does not have `2^num_qubits` entries, or if a
+    /// parameter of the circuit, other than `param`, is missing from `base`.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, GateKind, ParamId};
+    /// use std::collections::HashMap;
+    ///
+    /// let theta = ParamId(0);
+    /// let mut ansatz = Circuit::new(1).unwrap();
+    /// ansatz.add_gate(Gate::Param(theta, GateKind::Ry), 0).unwrap();
+    ///
+    /// // The expectation of Pauli-Z is cos(θ).
+    /// let expectations: Vec<f64> = ansatz
+    ///     .sweep(theta, &[0f64, 1f64, 2f64], &[1f64, -1f64], &HashMap::new())
+    ///     .unwrap();
+    ///
+    /// for (expectation, angle) in expectations.iter().zip([0f64, 1f64, 2f64]) {
+    ///     assert!((expectation - angle.cos()).abs() < 1e-10);
+    /// }
+    /// ```
+    pub fn sweep(
+        &self,
+        param: ParamId,
+        values: &[f64],
+        observable_diagonal: &[f64],
+        base: &HashMap<ParamId, f64>,
+    ) -> QResult<Vec<f64>> {
+        self.check_observable(observable_diagonal)?;
+
+        let mut base_values: HashMap<ParamId, f64> = base.clone();
+        base_values.insert(param, 0f64);
+        let mut bound_circuit: Circuit<T> = self.bind_parameters(&base_values)?;
+        let mut categorised_gates: Vec<GateCategory> = bound_circuit
+            .circuit_gates
+            .iter()
+            .map(Gate::linker)
+            .collect();
+        let param_gates: Vec<(usize, &GateKind)> = self
+            .circuit_gates
+            .iter()
+            .enumerate()
+            .filter_map(|(index, gate)| match gate {
+                Gate::Param(id, kind) if *id == param => Some((index, kind)),
+                _ => None,
+            })
+            .collect();
+
+        let mut expectations: Vec<f64> = Vec::with_capacity(values.len());
+        let mut rng: fastrand::Rng = self.rng();
+        for value in values {
+            for (index, kind) in param_gates.iter() {
+                bound_circuit.circuit_gates[*index] = kind.bind(*value);
+                categorised_gates[*index] = bound_circuit.circuit_gates[*index].linker();
             }
+
+            let mut register: SuperPosition<T> = bound_circuit
+                .register
+                .clone()
+                .unwrap_or_else(|| SuperPosition::new_unchecked(self.num_qubits));
+            Self::simulate_gates(
+                &bound_circuit.circuit_gates,
+                &categorised_gates,
+                self.num_qubits,
+                self.config_progress,
+                &mut rng,
+                &mut register,
+            )?;
+            expectations.push(
+                zip(&register.amplitudes, observable_diagonal)
+                    .map(|(amp, value)| amp.norm_sqr().into_f64() * value)
+                    .sum(),
+            );
+        }
+
+        Ok(expectations)
+    }
+
+    // Checks that a diagonal observable has an entry for each product state of the circuit.
+    fn check_observable(&self, observable_diagonal: &[f64]) -> QResult<()> {
+        if observable_diagonal.len() != 1 << self.num_qubits {
+            return Err(QuantrError::DimensionMismatch {
+                expected: 1usize << self.num_qubits,
+                found: observable_diagonal.len(),
+                message: format!("The observable has {} entries, which does not match the dimension of the circuit, {}.", observable_diagonal.len(), 1usize << self.num_qubits),
+            });
         }
+        Ok(())
     }
 
     /// Changes the register which is applied to the circuit when [Circuit::simulate] is called.
@@ -460,10 +1702,12 @@ impl Circuit {
     /// // |1> -------
     /// // |0> -- X --
     /// ````
-    pub fn change_register(&mut self, super_pos: SuperPosition) -> QResult<&mut Circuit> {
+    pub fn change_register(&mut self, super_pos: SuperPosition<T>) -> QResult<&mut Circuit<T>> {
         if super_pos.product_dim != self.num_qubits {
-            return Err(QuantrError {
-                message: format!("The custom register has a product state dimension of {}, while the number of qubits is {}. These must equal each other.", super_pos.product_dim, self.num_qubits)
+            return Err(QuantrError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: super_pos.product_dim,
+                message: format!("The custom register has a product state dimension of {}, while the number of qubits is {}. These must equal each other.", super_pos.product_dim, self.num_qubits),
             });
         }
 
@@ -473,14 +1717,61 @@ impl Circuit {
     }
 }
 
+impl<T: Float> fmt::Debug for Circuit<T> {
+    /// Summarises the circuit by its number of qubits and columns, followed by the non-identity
+    /// gates of each column labelled by their position.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit
+    ///     .add_gates(&[Gate::H, Gate::Id]).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// assert_eq!(
+    ///     format!("{:?}", circuit),
+    ///     "Circuit { num_qubits: 2, num_columns: 2, columns: [{0: H}, {1: X (controls [0])}] }"
+    /// );
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let columns: Vec<String> = self
+            .circuit_gates
+            .chunks(self.num_qubits)
+            .map(|column| {
+                let gates: Vec<String> = column
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, gate)| **gate != Gate::Id)
+                    .map(|(pos, gate)| match gate.get_nodes() {
+                        Some(nodes) => {
+                            format!("{}: {} (controls {:?})", pos, gate.get_name(), nodes)
+                        }
+                        None => format!("{}: {}", pos, gate.get_name()),
+                    })
+                    .collect();
+                format!("{{{}}}", gates.join(", "))
+            })
+            .collect();
+
+        f.debug_struct("Circuit")
+            .field("num_qubits", &self.num_qubits)
+            .field("num_columns", &columns.len())
+            .field("columns", &format_args!("[{}]", columns.join(", ")))
+            .finish()
+    }
+}
+
 #[rustfmt::skip]
 #[cfg(test)]
 mod tests {
-    use crate::{complex_im, complex_re, complex_re_array, Circuit, Gate};
+    use crate::{complex_im, complex_re, complex_re_array, Circuit, Circuit32, Circuit64, Gate, GateKind, ParamId};
     use num_complex::{Complex64, c64};
     use crate::states::{SuperPosition, Qubit, ProductState};
     use super::HashMap;
     use std::f64::consts::{FRAC_1_SQRT_2, PI};
+    use std::iter::zip;
     use crate::Measurement::NonObservable;
     const ERROR_MARGIN: f64 = 0.000001f64; // For comparing floats due to floating point error.
     // Needed for testing
@@ -502,7 +1793,7 @@ mod tests {
     }
 
     fn example_cnot(prod: ProductState) -> Option<SuperPosition> {
-        let input_register: [Qubit; 2] = [prod.qubits[0], prod.qubits[1]];
+        let input_register: [Qubit; 2] = [prod.get_unchecked(0), prod.get_unchecked(1)];
         Some(SuperPosition::new_with_amplitudes(match input_register {
             [Qubit::Zero, Qubit::Zero] => return None,
             [Qubit::Zero, Qubit::One]  => return None,
@@ -663,7 +1954,7 @@ mod tests {
         quantum_circuit
             .add_gates(&[Gate::Id, Gate::X]).unwrap();
 
-        assert!(vec!(Gate::Id, Gate::X).iter().all(|item| quantum_circuit.circuit_gates.contains(item)));
+        assert!([Gate::Id, Gate::X].iter().all(|item| quantum_circuit.circuit_gates.contains(item)));
     }
 
     #[test]
@@ -681,7 +1972,7 @@ mod tests {
         quantum_circuit
             .add_gates_with_positions(HashMap::from([(0, Gate::X), (2, Gate::H)])).unwrap();
         
-        assert!(vec!(Gate::X, Gate::Id, Gate::H)
+        assert!([Gate::X, Gate::Id, Gate::H]
                 .iter().all(|item| quantum_circuit.circuit_gates.contains(item)));
     }
 
@@ -989,4 +2280,948 @@ mod tests {
         circuit.add_gate(Gate::X, 1).unwrap()
             .change_register(register).unwrap();
     }
+
+    #[test]
+    fn can_add_catches_toffoli_on_two_qubits() {
+        let quantum_circuit = Circuit::new(2).unwrap();
+        assert!(quantum_circuit.can_add(&Gate::Toffoli(0, 1), 1).is_err());
+        assert!(quantum_circuit.can_add(&Gate::Toffoli(1, 2), 0).is_err());
+        assert!(quantum_circuit.can_add(&Gate::CNot(0), 0).is_err());
+        assert!(quantum_circuit.can_add(&Gate::CNot(0), 1).is_ok());
+        assert!(quantum_circuit.can_add(&Gate::H, 2).is_err());
+    }
+
+    #[test]
+    fn suppresses_warnings() {
+        use crate::error::print_warning;
+
+        assert!(print_warning("Printed warning from a test.", false));
+        assert!(!print_warning("Suppressed warning from a test.", true));
+
+        let mut quantum_circuit = Circuit::new(1).unwrap();
+        assert!(!quantum_circuit.clone_and_simulate().disable_warnings);
+
+        quantum_circuit.set_suppress_warnings(true);
+        assert!(quantum_circuit.clone_and_simulate().disable_warnings);
+        assert!(quantum_circuit.simulate().disable_warnings);
+    }
+
+    #[test]
+    fn custom_arg_phase_oracle() {
+        fn phase_oracle(prod: ProductState, angle: f64) -> Option<SuperPosition> {
+            match prod.get_qubits().as_slice() {
+                [Qubit::One, Qubit::One] => {
+                    let mut amplitudes: [Complex64; 4] = [Complex64::ZERO; 4];
+                    amplitudes[3] = Complex64::from_polar(1f64, angle);
+                    Some(SuperPosition::new_with_amplitudes(&amplitudes).unwrap())
+                }
+                _ => None,
+            }
+        }
+
+        for (angle, marked_amplitude) in [(PI, complex_re!(-0.5f64)), (PI / 2f64, complex_im!(0.5f64))] {
+            let mut quantum_circuit = Circuit::new(2).unwrap();
+            quantum_circuit.add_repeating_gate(Gate::H, &[0, 1]).unwrap()
+                .add_gate(Gate::CustomArg(phase_oracle, angle, vec![0], "Uf".to_string()), 1).unwrap();
+            let correct_register: [Complex64; 4] = [complex_re!(0.5f64), complex_re!(0.5f64), complex_re!(0.5f64), marked_amplitude];
+            compare_circuit(quantum_circuit, &correct_register);
+        }
+
+        let oracle: Gate = Gate::CustomArg(phase_oracle, PI, vec![0], "Uf".to_string());
+        assert_eq!(oracle.get_nodes(), Some(vec![0]));
+        assert_eq!(oracle.get_name(), "Uf");
+    }
+
+    #[test]
+    fn measure_reset_returns_wire_to_zero() {
+        for prepared in [Gate::Id, Gate::X] {
+            let mut circuit = Circuit::new(2).unwrap();
+            circuit.add_gate(prepared.clone(), 0).unwrap()
+                .add_gate(Gate::MeasureReset(3), 0).unwrap();
+            let simulated_circuit = circuit.simulate();
+
+            let expected: Qubit = if prepared == Gate::X { Qubit::One } else { Qubit::Zero };
+            assert_eq!(simulated_circuit.get_measurements(), &[(3, expected)]);
+            assert_eq!(simulated_circuit.get_state().take().amplitudes[0], complex_re!(1f64));
+        }
+    }
+
+    #[test]
+    fn measure_reset_collapses_bell_pair() {
+        fastrand::seed(0);
+        for _ in 0..10 {
+            let mut circuit = Circuit::new(2).unwrap();
+            circuit.add_gate(Gate::H, 0).unwrap()
+                .add_gate(Gate::CNot(0), 1).unwrap()
+                .add_gate(Gate::MeasureReset(0), 0).unwrap();
+            let simulated_circuit = circuit.simulate();
+
+            // The partner wire remains in the observed state, while the measured wire is reset.
+            let correct_index: usize = match simulated_circuit.get_measurements() {
+                [(0, Qubit::Zero)] => 0,
+                [(0, Qubit::One)] => 1,
+                measurements => panic!("Unexpected measurements {:?}", measurements),
+            };
+            let state: SuperPosition = simulated_circuit.take_state().take();
+            assert!((state.amplitudes[correct_index] - complex_re!(1f64)).norm() < ERROR_MARGIN);
+        }
+    }
+
+    fn post_select(prod: ProductState) -> Option<SuperPosition> {
+        match prod.get_qubits()[0] {
+            Qubit::Zero => Some(SuperPosition::new_with_amplitudes_unchecked(&complex_re_array!(2f64.sqrt(), 0f64))),
+            Qubit::One => Some(SuperPosition::new_with_amplitudes_unchecked(&complex_re_array!(0f64, 0f64))),
+        }
+    }
+
+    #[test]
+    fn checks_custom_unitarity() {
+        let mut quantum_circuit = Circuit::new(3).unwrap();
+        quantum_circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::Custom(example_cnot, vec![0], "X".to_string()), 2).unwrap();
+        assert!(quantum_circuit.check_custom_unitarity().is_ok());
+
+        quantum_circuit.add_gate(Gate::custom_single(post_select, "P"), 1).unwrap();
+        assert!(quantum_circuit.check_custom_unitarity().is_err());
+    }
+
+    #[test]
+    fn controlled_hadamard() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gate(Gate::X, 0).unwrap()
+            .add_gate(Gate::Controlled(Box::new(Gate::H), 0), 1).unwrap()
+            .add_gate(Gate::Controlled(Box::new(Gate::H), 2), 0).unwrap();
+
+        // The second gate is not applied, as its control node is |0>.
+        let correct_register: [Complex64; 8] = [
+            num_complex::Complex64::ZERO, num_complex::Complex64::ZERO,
+            num_complex::Complex64::ZERO, num_complex::Complex64::ZERO,
+            complex_re!(FRAC_1_SQRT_2), num_complex::Complex64::ZERO,
+            complex_re!(FRAC_1_SQRT_2), num_complex::Complex64::ZERO];
+        compare_circuit(circuit, &correct_register);
+    }
+
+    #[test]
+    fn controlled_t_gate() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::X, 1).unwrap()
+            .add_gate(Gate::Controlled(Box::new(Gate::T), 0), 1).unwrap();
+
+        let correct_register: [Complex64; 4] = [
+            num_complex::Complex64::ZERO, complex_re!(FRAC_1_SQRT_2),
+            num_complex::Complex64::ZERO, c64(0.5f64, 0.5f64)];
+        compare_circuit(circuit, &correct_register);
+    }
+
+    #[test]
+    fn catches_controlled_multi_qubit_gate() {
+        let mut circuit = Circuit::new(3).unwrap();
+        assert!(circuit.add_gate(Gate::Controlled(Box::new(Gate::CNot(2)), 0), 1).is_err());
+        assert!(circuit.add_gate(Gate::Controlled(Box::new(Gate::MeasureReset(0)), 0), 1).is_err());
+    }
+
+    #[test]
+    fn tensors_two_circuits() {
+        let mut first = Circuit::new(1).unwrap();
+        first.add_gate(Gate::H, 0).unwrap();
+        let mut second = Circuit::new(1).unwrap();
+        second.add_gate(Gate::X, 0).unwrap();
+
+        let correct_register: [Complex64; 4] = [
+            num_complex::Complex64::ZERO, complex_re!(FRAC_1_SQRT_2),
+            num_complex::Complex64::ZERO, complex_re!(FRAC_1_SQRT_2)];
+        compare_circuit(first.tensor(&second), &correct_register);
+    }
+
+    #[test]
+    fn tensor_shifts_control_nodes() {
+        // The CNot of the first circuit is pushed into its own column.
+        let mut first = Circuit::new(2).unwrap();
+        first.add_gate(Gate::CNot(0), 1).unwrap();
+        let mut second = Circuit::new(2).unwrap();
+        second.add_gate(Gate::X, 0).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap();
+
+        assert_eq!(first.tensor(&second).circuit_gates, vec![
+            Gate::Id, Gate::Id, Gate::X, Gate::Id,
+            Gate::Id, Gate::CNot(0), Gate::Id, Gate::Id,
+            Gate::Id, Gate::Id, Gate::Id, Gate::CNot(2)]);
+    }
+
+    #[test]
+    fn repeats_circuit() {
+        let mut circuit = Circuit::new(1).unwrap();
+        circuit.add_gate(Gate::X, 0).unwrap();
+
+        compare_circuit(circuit.repeat(2).unwrap(), &[complex_re!(1f64), num_complex::Complex64::ZERO]);
+        compare_circuit(circuit.repeat(3).unwrap(), &[num_complex::Complex64::ZERO, complex_re!(1f64)]);
+        assert!(circuit.repeat(0).unwrap().get_gates().is_empty());
+    }
+
+    #[test]
+    fn subspace_simulation_matches_full_simulation() {
+        let build_circuit = || {
+            let mut circuit = Circuit::new(3).unwrap();
+            circuit.change_register(SuperPosition::new_with_amplitudes(&[
+                num_complex::Complex64::ZERO, complex_re!(0.6f64), complex_im!(0.8f64), num_complex::Complex64::ZERO,
+                num_complex::Complex64::ZERO, num_complex::Complex64::ZERO, num_complex::Complex64::ZERO, num_complex::Complex64::ZERO,
+            ]).unwrap()).unwrap();
+            circuit.add_gate(Gate::Swap(2), 0).unwrap()
+                .add_gate(Gate::CZ(0), 1).unwrap()
+                .add_gate(Gate::CR(0.3f64, 2), 1).unwrap()
+                .add_gate(Gate::Swap(1), 2).unwrap()
+                .add_gate(Gate::T, 0).unwrap();
+            circuit
+        };
+
+        let full: SuperPosition = build_circuit().simulate().take_state().take();
+        let subspace: SuperPosition = build_circuit().simulate_in_subspace(1).unwrap().take_state().take();
+        compare_complex_lists_and_register(&full.amplitudes, &subspace);
+
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap();
+        assert!(circuit.simulate_in_subspace(0).is_err());
+        assert!(Circuit::new(2).unwrap().simulate_in_subspace(1).is_err());
+    }
+
+    #[test]
+    fn barrier_leaves_state_unchanged() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap()
+            .add_barrier()
+            .add_gate(Gate::CNot(0), 1).unwrap()
+            .add_barrier();
+
+        assert_eq!(circuit.depth(), 4);
+        let correct_register: [Complex64; 4] = [
+            complex_re!(FRAC_1_SQRT_2), num_complex::Complex64::ZERO,
+            num_complex::Complex64::ZERO, complex_re!(FRAC_1_SQRT_2)];
+        compare_circuit(circuit, &correct_register);
+    }
+
+    #[test]
+    fn simulates_bell_state_in_f32_precision() {
+        let mut circuit = Circuit32::new_with_precision(2).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap();
+
+        let register: SuperPosition<f32> = circuit.simulate().take_state().take();
+        for (amp, correct_amp) in zip(register.get_amplitudes(), [FRAC_1_SQRT_2, 0f64, 0f64, FRAC_1_SQRT_2]) {
+            assert!((amp.re as f64 - correct_amp).abs() < 1e-6);
+            assert!(amp.im.abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn simulates_bell_state_in_f64_precision() {
+        let mut circuit = Circuit64::new(2).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap();
+
+        let register: SuperPosition<f64> = circuit.simulate().take_state().take();
+        for (amp, correct_amp) in zip(register.get_amplitudes(), [FRAC_1_SQRT_2, 0f64, 0f64, FRAC_1_SQRT_2]) {
+            assert!((amp.re - correct_amp).abs() < 1e-15);
+            assert!(amp.im.abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn matches_error_variants() {
+        use crate::QuantrError;
+
+        let mut quantum_circuit = Circuit::new(2).unwrap();
+        assert!(matches!(
+            quantum_circuit.add_gate(Gate::X, 3),
+            Err(QuantrError::QubitOutOfBounds { index: 3, num_qubits: 2, message })
+                if message == "The position, 3, is out of bounds for the circuit with 2 qubits."
+        ));
+        assert!(matches!(
+            quantum_circuit.add_gate(Gate::CNot(4), 0),
+            Err(QuantrError::QubitOutOfBounds { index: 4, num_qubits: 2, message })
+                if message == "The control node at position 4, is greater than the umnber of qubits 2."
+        ));
+        assert!(matches!(
+            quantum_circuit.add_gate(Gate::CNot(1), 1),
+            Err(QuantrError::OverlappingControlNodes { message, .. })
+                if message == "The gate, CNot(1), has a control node that equals the gate's position 1."
+        ));
+        assert!(matches!(
+            Circuit::new(3).unwrap().add_gate(Gate::Toffoli(0, 0), 1),
+            Err(QuantrError::OverlappingControlNodes { message, .. })
+                if message == "The gate, Toffoli(0, 0), has overlapping control nodes."
+        ));
+        assert!(matches!(
+            quantum_circuit.add_gates(&[Gate::X]),
+            Err(QuantrError::DimensionMismatch { expected: 2, found: 1, message })
+                if message == "The number of gates, 1, does not match the number of wires, 2. All wires must have gates added."
+        ));
+        assert!(matches!(
+            quantum_circuit.append(&Circuit::new(3).unwrap()),
+            Err(QuantrError::DimensionMismatch { expected: 2, found: 3, message })
+                if message == "The appended circuit has 3 qubits, while this circuit has 2 qubits. These must equal each other."
+        ));
+        assert!(matches!(
+            quantum_circuit.change_register(SuperPosition::new(1).unwrap()),
+            Err(QuantrError::DimensionMismatch { expected: 2, found: 1, message })
+                if message == "The custom register has a product state dimension of 1, while the number of qubits is 2. These must equal each other."
+        ));
+        assert!(matches!(
+            SuperPosition::new_with_amplitudes(&[complex_re!(1f64), complex_re!(1f64)]),
+            Err(QuantrError::ProbabilityNotConserved { total, message })
+                if (total - 2f64).abs() < ERROR_MARGIN
+                && message == "Slice given to set amplitudes in super position does not conserve probability, the absolute square sum of the coefficents must be one."
+        ));
+        assert!(matches!(
+            Circuit::new(0),
+            Err(QuantrError::Other { message }) if message == "The initialised circuit must have at least one wire."
+        ));
+
+        // The message is given by the Display implementation.
+        assert_eq!(
+            quantum_circuit.add_gate(Gate::X, 3).unwrap_err().to_string(),
+            "\x1b[91m[Quantr Error] The position, 3, is out of bounds for the circuit with 2 qubits.\x1b[0m "
+        );
+    }
+
+    #[test]
+    fn enforces_unique_custom_names() {
+        fn example_identity(prod: ProductState) -> Option<SuperPosition> {
+            Some(SuperPosition::from(prod))
+        }
+
+        let mut quantum_circuit = Circuit::new(2).unwrap();
+        quantum_circuit.set_enforce_unique_custom_names(true);
+        quantum_circuit.add_gate(Gate::Custom(example_cnot, vec![0], "X".to_string()), 1).unwrap();
+        quantum_circuit.add_gate(Gate::Custom(example_identity, vec![], "I".to_string()), 0).unwrap();
+        quantum_circuit.add_gate(Gate::Custom(example_cnot, vec![1], "X".to_string()), 0).unwrap();
+        assert!(quantum_circuit.add_gate(Gate::Custom(example_identity, vec![], "X".to_string()), 0).is_err());
+
+        // The default allows shared names.
+        let mut quantum_circuit = Circuit::new(2).unwrap();
+        quantum_circuit.add_gate(Gate::Custom(example_cnot, vec![0], "X".to_string()), 1).unwrap();
+        assert!(quantum_circuit.add_gate(Gate::Custom(example_identity, vec![], "X".to_string()), 0).is_ok());
+    }
+
+    #[test]
+    fn mcx_gates() {
+        let mut circuit = Circuit::new(4).unwrap();
+        circuit.add_repeating_gate(Gate::X, &[0, 1, 2]).unwrap()
+            .add_gate(Gate::MCX(vec![0, 1, 2]), 3).unwrap()
+            .add_gate(Gate::X, 1).unwrap()
+            .add_gate(Gate::MCX(vec![0, 1, 2]), 3).unwrap();
+
+        let mut correct_register = [Complex64::ZERO; 16];
+        correct_register[11] = complex_re!(1f64); // |1011>
+
+        compare_circuit(circuit, &correct_register);
+        assert!(Circuit::new(3).unwrap().add_gate(Gate::MCX(vec![0, 2]), 2).is_err());
+    }
+
+    #[test]
+    fn add_fanout_to_circuit() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gate(Gate::X, 0).unwrap()
+            .add_fanout(0, &[1, 2]).unwrap();
+
+        assert_eq!(
+            circuit.circuit_gates[3..],
+            [Gate::Id, Gate::CNot(0), Gate::Id, Gate::Id, Gate::Id, Gate::CNot(0)]
+        );
+        compare_circuit(circuit, &complex_re_array!(0f64, 0f64, 0f64, 0f64, 0f64, 0f64, 0f64, 1f64));
+
+        let mut bad_circuit = Circuit::new(3).unwrap();
+        assert!(bad_circuit.add_fanout(0, &[0, 1]).is_err());
+        assert!(bad_circuit.add_fanout(0, &[1, 1]).is_err());
+        assert!(bad_circuit.add_fanout(0, &[3]).is_err());
+    }
+
+    #[test]
+    fn add_permutation_to_circuit() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gate(Gate::X, 0).unwrap()
+            .add_gate(Gate::X, 1).unwrap()
+            .add_permutation(&[2, 0, 1]).unwrap();
+
+        assert_eq!(circuit.depth(), 4);
+        compare_circuit(circuit, &complex_re_array!(0f64, 0f64, 0f64, 0f64, 0f64, 1f64, 0f64, 0f64));
+
+        let mut bad_circuit = Circuit::new(3).unwrap();
+        assert!(bad_circuit.add_permutation(&[0, 1]).is_err());
+        assert!(bad_circuit.add_permutation(&[0, 1, 1]).is_err());
+        assert!(bad_circuit.add_permutation(&[0, 1, 3]).is_err());
+    }
+
+    #[test]
+    fn add_sparse_column_to_circuit() {
+        let mut quantum_circuit = Circuit::new(3).unwrap();
+        quantum_circuit.add_sparse_column(&[(0, Gate::X), (2, Gate::H)]).unwrap();
+
+        assert_eq!(quantum_circuit.circuit_gates, vec![Gate::X, Gate::Id, Gate::H]);
+        assert!(quantum_circuit.add_sparse_column(&[(1, Gate::X), (1, Gate::H)]).is_err());
+    }
+
+    #[test]
+    fn sparse_column_matches_hash_map_column() {
+        let mut sparse_circuit = Circuit::new(3).unwrap();
+        sparse_circuit.add_sparse_column(&[(0, Gate::X), (2, Gate::H)]).unwrap();
+        let mut hash_map_circuit = Circuit::new(3).unwrap();
+        hash_map_circuit.add_gates_with_positions(HashMap::from([(0, Gate::X), (2, Gate::H)])).unwrap();
+
+        assert_eq!(sparse_circuit.circuit_gates, hash_map_circuit.circuit_gates);
+    }
+
+    #[test]
+    fn u3_gate_is_pauli_x() {
+        for (input, flipped) in [(Qubit::Zero, Qubit::One), (Qubit::One, Qubit::Zero)] {
+            let mut circuit = Circuit::new(1).unwrap();
+            circuit.add_gate(Gate::U3(PI, 0f64, PI), 0).unwrap()
+                .change_register(SuperPosition::from(input)).unwrap();
+
+            let register: SuperPosition = circuit.simulate().take_state().take();
+            let expected: SuperPosition = SuperPosition::from(flipped);
+            // Equal up to a global phase.
+            let overlap: Complex64 = zip(register.get_amplitudes(), expected.get_amplitudes())
+                .map(|(amp, exp_amp)| amp.conj() * exp_amp)
+                .sum();
+            assert!(equal_within_error(overlap.norm(), 1f64));
+        }
+    }
+
+    #[test]
+    fn u3_gate_composes_with_hadamard() {
+        let mut circuit = Circuit::new(1).unwrap();
+
+        circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::U3(PI / 2f64, 0f64, PI), 0).unwrap();
+
+        compare_circuit(circuit, &complex_re_array!(1f64, 0f64));
+    }
+
+    #[test]
+    fn catches_out_of_bounds_custom_control_node() {
+        let mut circuit = Circuit::new(3).unwrap();
+        assert!(circuit.add_gate(Gate::Custom(example_cnot, vec!(5), "X".to_string()), 1).is_err());
+    }
+
+    #[test]
+    fn catches_mis_sized_custom_image() {
+        fn mis_sized(_prod: ProductState) -> Option<SuperPosition> {
+            Some(SuperPosition::from(Qubit::One))
+        }
+
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::Custom(mis_sized, vec!(0), "M".to_string()), 2).unwrap();
+
+        assert!(circuit.try_simulate().is_err());
+    }
+
+    #[test]
+    fn debug_summarises_circuit() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gates(&[Gate::H, Gate::Id, Gate::X]).unwrap()
+            .add_gate(Gate::Toffoli(0, 2), 1).unwrap();
+
+        let debug_string: String = format!("{:?}", circuit);
+        assert!(debug_string.contains("num_qubits: 3"));
+        assert!(debug_string.contains("num_columns: 2"));
+        assert!(debug_string.contains("{0: H, 2: X}"));
+        assert!(debug_string.contains("{1: X (controls [0, 2])}"));
+    }
+
+    #[test]
+    fn delay_simulates_as_identity() {
+        let mut delayed = Circuit::new(2).unwrap();
+        delayed.add_gates(&[Gate::H, Gate::Delay(2f64)]).unwrap()
+            .add_gate(Gate::Delay(0.5f64), 0).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap();
+
+        let mut undelayed = Circuit::new(2).unwrap();
+        undelayed.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap();
+
+        assert_eq!(delayed.simulate().take_state().take(), undelayed.simulate().take_state().take());
+    }
+
+    #[test]
+    fn weighted_depth_includes_delay() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gates(&[Gate::H, Gate::Delay(2.5f64), Gate::X]).unwrap()
+            .add_gate(Gate::Delay(0.5f64), 1).unwrap()
+            .add_gate(Gate::CNot(0), 2).unwrap();
+
+        assert_eq!(circuit.weighted_depth(|_| 1f64), 4f64);
+        assert_eq!(circuit.weighted_depth(|_| 3f64), 6.5f64);
+    }
+
+    #[test]
+    fn weighted_depth_ignores_barriers() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap()
+            .add_barrier()
+            .add_gate(Gate::X, 1).unwrap();
+
+        assert_eq!(circuit.weighted_depth(|_| 2f64), 4f64);
+    }
+
+    #[test]
+    fn schedules_gates_as_soon_as_possible() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gates(&[Gate::H, Gate::Id, Gate::X]).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap()
+            .add_gate(Gate::Z, 2).unwrap()
+            .add_barrier()
+            .add_gate(Gate::Y, 2).unwrap();
+
+        assert_eq!(
+            circuit.schedule(|_| 1f64),
+            vec![(0, 0, 0f64, 1f64), (0, 2, 0f64, 1f64), (1, 1, 1f64, 2f64), (2, 2, 1f64, 2f64), (4, 2, 2f64, 3f64)]
+        );
+    }
+
+    #[test]
+    fn schedules_gates_in_column_with_barrier() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::X, 1).unwrap()
+            .add_gates(&[Gate::Barrier, Gate::H]).unwrap();
+
+        assert_eq!(circuit.schedule(|_| 1f64), vec![(0, 1, 0f64, 1f64), (1, 1, 1f64, 2f64)]);
+    }
+
+    #[test]
+    fn packs_single_gates_into_last_column() {
+        let mut circuit = Circuit::new(3).unwrap();
+        assert!(!circuit.try_pack_gate(Gate::X, 0).unwrap());
+        assert!(circuit.try_pack_gate(Gate::H, 2).unwrap());
+        assert_eq!(circuit.circuit_gates, vec![Gate::X, Gate::Id, Gate::H]);
+
+        assert!(!circuit.try_pack_gate(Gate::Y, 0).unwrap());
+        assert!(!circuit.try_pack_gate(Gate::CNot(0), 1).unwrap());
+        assert!(!circuit.try_pack_gate(Gate::Z, 2).unwrap());
+        assert_eq!(circuit.circuit_gates, vec![
+            Gate::X, Gate::Id, Gate::H,
+            Gate::Y, Gate::Id, Gate::Id,
+            Gate::Id, Gate::CNot(0), Gate::Id,
+            Gate::Id, Gate::Id, Gate::Z]);
+
+        assert!(circuit.try_pack_gate(Gate::H, 3).is_err());
+    }
+
+    #[cfg(feature = "indicatif")]
+    #[test]
+    fn progress_bar_runs_to_completion() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap()
+            .add_gate(Gate::X, 0).unwrap();
+        circuit.set_print_progress(true);
+
+        let correct_register: [Complex64; 4] = [
+            num_complex::Complex64::ZERO, complex_re!(FRAC_1_SQRT_2),
+            complex_re!(FRAC_1_SQRT_2), num_complex::Complex64::ZERO];
+        compare_circuit(circuit, &correct_register);
+    }
+
+    #[test]
+    fn split_circuits_simulate_as_whole() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::CNot(0), 2).unwrap()
+            .add_gate(Gate::Ry(0.3f64), 1).unwrap();
+
+        let (first, mut second) = circuit.split_at_column(2).unwrap();
+        second.change_register(first.simulate().take_state().take()).unwrap();
+
+        compare_complex_lists_and_register(circuit.simulate().take_state().take().get_amplitudes(),
+            second.simulate().get_state().take());
+        assert!(Circuit::new(3).unwrap().split_at_column(1).is_err());
+    }
+
+    #[test]
+    fn iswap_gate() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::X, 1).unwrap()
+            .add_gate(Gate::ISwap(0), 1).unwrap();
+
+        let correct_register = [Complex64::ZERO, Complex64::ZERO, complex_im!(1f64), Complex64::ZERO];
+
+        compare_circuit(circuit, &correct_register);
+    }
+
+    #[test]
+    fn cphase_gate_phase_on_one_one() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_repeating_gate(Gate::H, &[0, 1]).unwrap()
+            .add_gate(Gate::CPhase(PI / 3f64, 0), 1).unwrap();
+
+        // Only the |11> component gains the phase exp(i*pi/3) = 1/2 + i*sqrt(3)/2.
+        let correct_register = [
+            complex_re!(0.5f64), complex_re!(0.5f64), complex_re!(0.5f64),
+            Complex64::new(0.25f64, 0.75f64.sqrt() * 0.5f64),
+        ];
+
+        compare_circuit(circuit, &correct_register);
+    }
+
+    #[test]
+    fn margolus_gate_is_toffoli_up_to_relative_phase() {
+        for index in 0..8 {
+            let input: SuperPosition = SuperPosition::basis_state(index, 3).unwrap();
+            let mut margolus_circuit = Circuit::new(3).unwrap();
+            margolus_circuit.change_register(input.clone()).unwrap()
+                .add_gate(Gate::Margolus(0, 1), 2).unwrap();
+            let mut toffoli_circuit = Circuit::new(3).unwrap();
+            toffoli_circuit.change_register(input).unwrap()
+                .add_gate(Gate::Toffoli(0, 1), 2).unwrap();
+
+            let phase: f64 = if index == 0b101 { -1f64 } else { 1f64 };
+            let margolus_state: SuperPosition = margolus_circuit.simulate().take_state().take();
+            for (margolus_amp, toffoli_amp) in margolus_state.get_amplitudes().iter()
+                .zip(toffoli_circuit.simulate().take_state().take().get_amplitudes()) {
+                assert_eq!(*margolus_amp, toffoli_amp * phase);
+            }
+        }
+    }
+
+    #[test]
+    fn rzz_gate_phases_by_parity() {
+        let mut even_circuit = Circuit::new(2).unwrap();
+        even_circuit.add_repeating_gate(Gate::X, &[0, 1]).unwrap()
+            .add_gate(Gate::Rzz(PI, 0), 1).unwrap();
+        let mut odd_circuit = Circuit::new(2).unwrap();
+        odd_circuit.add_gate(Gate::X, 1).unwrap()
+            .add_gate(Gate::Rzz(PI, 0), 1).unwrap();
+
+        let mut even_register = [Complex64::ZERO; 4];
+        even_register[3] = complex_im!(-1f64);
+        let mut odd_register = [Complex64::ZERO; 4];
+        odd_register[1] = complex_im!(1f64);
+
+        compare_circuit(even_circuit, &even_register);
+        compare_circuit(odd_circuit, &odd_register);
+    }
+
+    #[test]
+    fn rxx_and_ryy_followed_by_inverse_is_identity() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::Rxx(0.7f64, 0), 1).unwrap()
+            .add_gate(Gate::Ryy(-1.2f64, 1), 0).unwrap();
+
+        let mut inverse_circuit: Circuit = circuit.inverse().unwrap();
+        inverse_circuit.change_register(circuit.simulate().take_state().take()).unwrap();
+
+        let mut correct_register = [Complex64::ZERO; 4];
+        correct_register[0] = complex_re!(1f64);
+
+        compare_circuit(inverse_circuit, &correct_register);
+    }
+
+    #[test]
+    fn repeated_simulations_reuse_categorised_gates() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gates(&[Gate::H, Gate::Ry(0.4f64)]).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap();
+
+        let first = circuit.clone_and_simulate();
+        // The linked gates are now cached, and so are not linked again.
+        assert!(circuit.categorised_gates.get().is_some());
+        let second = circuit.clone_and_simulate();
+        assert_eq!(first.get_state().take(), second.get_state().take());
+
+        // Adding a gate clears the cache.
+        circuit.add_gate(Gate::X, 0).unwrap();
+        assert!(circuit.categorised_gates.get().is_none());
+        let third = circuit.clone_and_simulate();
+        assert_ne!(first.get_state().take(), third.get_state().take());
+    }
+
+    #[test]
+    fn circuit_followed_by_inverse_is_identity() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gates(&[Gate::H, Gate::T, Gate::H]).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap()
+            .add_gate(Gate::Rz(0.7f64), 2).unwrap()
+            .add_gates(&[Gate::T, Gate::Rz(-1.3f64), Gate::CNot(1)]).unwrap()
+            .add_gate(Gate::ISwap(2), 0).unwrap()
+            .add_gate(Gate::CRk(3, 0), 2).unwrap()
+            .add_gate(Gate::U3(0.4f64, 1.1f64, -0.6f64), 1).unwrap()
+            .add_repeating_gate(Gate::H, &[0, 2]).unwrap();
+
+        let mut inverse_circuit: Circuit = circuit.inverse().unwrap();
+        inverse_circuit.change_register(circuit.simulate().take_state().take()).unwrap();
+
+        let mut correct_register = [Complex64::ZERO; 8];
+        correct_register[0] = complex_re!(1f64);
+
+        compare_circuit(inverse_circuit, &correct_register);
+    }
+
+    #[test]
+    fn catches_inverting_custom_gate() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::Custom(example_cnot, vec!(0), "X".to_string()), 1).unwrap();
+
+        assert!(circuit.inverse().is_err());
+    }
+
+    #[test]
+    fn append_circuit_blocks() {
+        let mut h_block = Circuit::new(2).unwrap();
+        h_block.add_repeating_gate(Gate::H, &[0, 1]).unwrap();
+        let mut x_block = Circuit::new(2).unwrap();
+        x_block.add_gate(Gate::X, 1).unwrap();
+
+        let mut appended_circuit = Circuit::new(2).unwrap();
+        appended_circuit.append(&h_block).unwrap()
+            .append(&x_block).unwrap();
+
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_repeating_gate(Gate::H, &[0, 1]).unwrap()
+            .add_gate(Gate::X, 1).unwrap();
+
+        assert_eq!(appended_circuit.circuit_gates, circuit.circuit_gates);
+        compare_complex_lists_and_register(
+            circuit.simulate().get_state().take().get_amplitudes(),
+            appended_circuit.simulate().get_state().take(),
+        );
+        assert!(Circuit::new(3).unwrap().append(&h_block).is_err());
+    }
+
+    #[test]
+    fn binds_rz_parameter() {
+        let theta = ParamId(0);
+        let mut ansatz = Circuit::new(2).unwrap();
+        ansatz.add_repeating_gate(Gate::H, &[0, 1]).unwrap()
+            .add_gate(Gate::Param(theta, GateKind::Rz), 1).unwrap();
+
+        let bound_circuit: Circuit = ansatz.bind_parameters(&HashMap::from([(theta, PI)])).unwrap();
+
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_repeating_gate(Gate::H, &[0, 1]).unwrap()
+            .add_gate(Gate::Rz(PI), 1).unwrap();
+
+        assert_eq!(bound_circuit.circuit_gates, circuit.circuit_gates);
+        compare_complex_lists_and_register(
+            circuit.simulate().get_state().take().get_amplitudes(),
+            bound_circuit.simulate().get_state().take(),
+        );
+    }
+
+    #[test]
+    fn parameter_shift_matches_finite_difference() {
+        let theta = ParamId(0);
+        let mut ansatz = Circuit::new(1).unwrap();
+        ansatz.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::Param(theta, GateKind::Rz), 0).unwrap()
+            .add_gate(Gate::H, 0).unwrap();
+        let observable: [f64; 2] = [1f64, -1f64];
+
+        let expectation = |angle: f64| -> f64 {
+            let circuit: Circuit = ansatz.bind_parameters(&HashMap::from([(theta, angle)])).unwrap();
+            zip(circuit.simulate().probabilities(), observable).map(|(p, o)| p * o).sum()
+        };
+
+        let angle: f64 = 0.7f64;
+        let step: f64 = 1e-6f64;
+        let finite_difference: f64 = (expectation(angle + step) - expectation(angle - step)) / (2f64 * step);
+        let gradient: f64 = ansatz.parameter_shift_gradient(theta, &observable, &HashMap::from([(theta, angle)])).unwrap();
+
+        assert!((gradient - finite_difference).abs() < 1e-6);
+        assert!(ansatz.parameter_shift_gradient(theta, &[1f64], &HashMap::from([(theta, angle)])).is_err());
+    }
+
+    #[test]
+    fn compact_removes_identity_columns() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gates(&[Gate::Id, Gate::Id]).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap()
+            .add_gates(&[Gate::Id, Gate::Id]).unwrap();
+
+        let mut compact_circuit = Circuit::new(2).unwrap();
+        compact_circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::CNot(0), 1).unwrap();
+
+        let register: SuperPosition = circuit.clone_and_simulate().take_state().take();
+        circuit.compact();
+
+        assert_eq!(circuit.depth(), 2);
+        assert_eq!(circuit.circuit_gates, compact_circuit.circuit_gates);
+        compare_complex_lists_and_register(register.get_amplitudes(), circuit.simulate().get_state().take());
+    }
+
+    #[test]
+    fn sweeps_rz_parameter() {
+        let theta = ParamId(0);
+        let mut ansatz = Circuit::new(2).unwrap();
+        ansatz.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::Param(theta, GateKind::Rz), 0).unwrap()
+            .add_gate(Gate::H, 0).unwrap();
+
+        // Pauli-Z on the first qubit.
+        let observable: [f64; 4] = [1f64, 1f64, -1f64, -1f64];
+        let angles: Vec<f64> = (0..8).map(|i| i as f64 * PI / 4f64).collect();
+        let expectations: Vec<f64> = ansatz.sweep(theta, &angles, &observable, &HashMap::new()).unwrap();
+
+        assert_eq!(expectations.len(), angles.len());
+        for (expectation, angle) in zip(expectations, angles) {
+            assert!((expectation - angle.cos()).abs() < 1e-10);
+        }
+        assert!(ansatz.sweep(theta, &[0f64], &[1f64, -1f64], &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn classical_circuit_matches_general_simulation() {
+        let mut circuit = Circuit::new(10).unwrap();
+        for i in 0..9 {
+            circuit.add_gate(Gate::X, i).unwrap()
+                .add_gate(Gate::CNot(i), i + 1).unwrap()
+                .add_gate(Gate::Swap(9 - i), i).unwrap();
+        }
+        circuit.add_gate(Gate::Toffoli(0, 3), 5).unwrap()
+            .add_gate(Gate::MCX(vec![1, 2, 4]), 9).unwrap();
+
+        let mut general_register: SuperPosition = SuperPosition::basis_state(0b1000100010, 10).unwrap();
+        let mut classical_register: SuperPosition = general_register.clone();
+
+        Circuit::simulate_gates(&circuit.circuit_gates, Circuit::link_gates(&circuit.categorised_gates, &circuit.circuit_gates), 10, false, &mut fastrand::Rng::new(), &mut general_register).unwrap();
+        assert!(Circuit::simulate_classical_gates(&circuit.circuit_gates, 10, &mut classical_register));
+
+        compare_complex_lists_and_register(general_register.get_amplitudes(), &classical_register);
+
+        // The fast path is not taken for a superposition.
+        let mut plus_register: SuperPosition = SuperPosition::new_with_amplitudes(&[complex_re!(FRAC_1_SQRT_2); 2]).unwrap();
+        assert!(!Circuit::simulate_classical_gates(&[Gate::X], 1, &mut plus_register));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn parallel_simulation_matches_serial_simulation() {
+        use crate::circuit::GateInfo;
+
+        let mut circuit = Circuit::new(12).unwrap();
+        circuit.add_repeating_gate(Gate::H, &(0..12).collect::<Vec<usize>>()).unwrap()
+            .add_gate(Gate::Custom(example_cnot, vec![3], "X".to_string()), 7).unwrap()
+            .add_gate(Gate::CNot(0), 5).unwrap()
+            .add_gate(Gate::S, 0).unwrap()
+            .add_gate(Gate::Rx(0.3f64), 11).unwrap();
+
+        // Both the custom gate and the dense gates take the parallel path within simulate.
+        let mut serial_register: SuperPosition = SuperPosition::new(12).unwrap();
+        let categorised_gates = Circuit::link_gates(&circuit.categorised_gates, &circuit.circuit_gates);
+        for (gate_num, cat_gate) in categorised_gates.iter().enumerate() {
+            let gate = GateInfo { cat_gate, position: gate_num % 12 };
+            Circuit::apply_gate_serially(&gate, &mut serial_register).unwrap();
+        }
+        let parallel_register: SuperPosition = circuit.simulate().take_state().take();
+        compare_complex_lists_and_register(serial_register.get_amplitudes(), &parallel_register);
+
+        // The amplitudes after the wall of Hadamards, the CNot and the S gate.
+        let mut wall = Circuit::new(12).unwrap();
+        wall.add_repeating_gate(Gate::H, &(0..12).collect::<Vec<usize>>()).unwrap()
+            .add_gate(Gate::CNot(0), 5).unwrap()
+            .add_gate(Gate::S, 0).unwrap();
+        let expected: Vec<Complex64> = (0..1 << 12)
+            .map(|i| if i >> 11 == 1 { complex_im!(1f64 / 64f64) } else { complex_re!(1f64 / 64f64) })
+            .collect();
+        compare_circuit(wall, &expected);
+    }
+
+    #[test]
+    fn dense_gates_match_mapped_gates() {
+        use crate::circuit::GateInfo;
+
+        let gates: [(Gate, usize); 14] = [
+            (Gate::H, 0), (Gate::Y, 2), (Gate::T, 1), (Gate::Rx(0.4f64), 2), (Gate::U3(0.1f64, 0.2f64, 0.3f64), 0),
+            (Gate::CNot(2), 0), (Gate::CY(0), 1), (Gate::CR(1.2f64, 1), 2), (Gate::CRk(3, 0), 2), (Gate::Swap(0), 2),
+            (Gate::ISwap(1), 0), (Gate::Toffoli(2, 0), 1), (Gate::CZ(0), 2), (Gate::X90, 1),
+        ];
+        let mut mapped_register: SuperPosition = SuperPosition::new_with_amplitudes(&[complex_re!(0.5f64); 4]).unwrap().pad_left(1);
+        mapped_register.amplitudes[5] = complex_im!(0.5f64);
+        let mut dense_register: SuperPosition = mapped_register.clone();
+
+        for (gate, position) in gates {
+            let cat_gate = gate.linker();
+            let gate_info = GateInfo { cat_gate: &cat_gate, position };
+            Circuit::apply_gate_serially(&gate_info, &mut mapped_register).unwrap();
+            Circuit::apply_dense_gate(&gate_info, &mut dense_register);
+            compare_complex_lists_and_register(mapped_register.get_amplitudes(), &dense_register);
+        }
+    }
+
+    #[test]
+    fn catches_unbound_parameter() {
+        let mut ansatz = Circuit::new(1).unwrap();
+        ansatz.add_gate(Gate::Param(ParamId(0), GateKind::Rx), 0).unwrap();
+
+        assert!(ansatz.bind_parameters(&HashMap::from([(ParamId(1), PI)])).is_err());
+        assert!(ansatz.try_simulate().is_err());
+    }
+
+    #[test]
+    fn depth_and_gate_counts() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gates(&[Gate::H, Gate::CNot(0), Gate::T]).unwrap()
+            .add_gate(Gate::Toffoli(0, 1), 2).unwrap()
+            .add_repeating_gate(Gate::H, &[1, 2]).unwrap()
+            .add_gate(Gate::Swap(2), 0).unwrap();
+
+        assert_eq!(circuit.depth(), 5);
+        assert_eq!(circuit.gate_counts(), HashMap::from([
+            (String::from("H"), 3),
+            (String::from("T"), 1),
+            (String::from("X"), 2),
+            (String::from("Sw"), 1),
+        ]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn round_trips_circuit_through_json() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gates(&[Gate::H, Gate::Rx(0.3f64), Gate::T]).unwrap()
+            .add_gate(Gate::Toffoli(0, 1), 2).unwrap()
+            .add_gate(Gate::Controlled(Box::new(Gate::Y), 2), 0).unwrap()
+            .add_gate(Gate::Param(ParamId(0), GateKind::CR(0)), 1).unwrap()
+            .change_register(SuperPosition::basis_state(5, 3).unwrap()).unwrap();
+
+        let json: String = serde_json::to_string(&circuit).unwrap();
+        let loaded: Circuit = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.get_gates(), circuit.get_gates());
+        assert_eq!(loaded.num_qubits, circuit.num_qubits);
+        assert_eq!(loaded.register, circuit.register);
+
+        // Custom gates hold a function pointer, and so cannot be serialised.
+        circuit.add_gate(Gate::Custom(example_cnot, vec![0], String::from("X")), 1).unwrap();
+        assert!(serde_json::to_string(&circuit).is_err());
+    }
+
+    #[test]
+    fn can_add_checks_custom_gate_names() {
+        let mut quantum_circuit = Circuit::new(2).unwrap();
+        quantum_circuit.set_enforce_unique_custom_names(true);
+        quantum_circuit.add_gate(Gate::custom_single(post_select, "P"), 0).unwrap();
+
+        assert!(quantum_circuit.can_add(&Gate::custom_single(post_select, "P"), 1).is_ok());
+        assert!(quantum_circuit.can_add(&Gate::Custom(example_cnot, vec![0], String::from("P")), 1).is_err());
+        assert!(quantum_circuit.can_add(&Gate::custom_single(post_select, "Ψ"), 1).is_err());
+        assert!(quantum_circuit.try_pack_gate(Gate::custom_single(post_select, "Ψ"), 1).is_err());
+    }
+
+    #[test]
+    fn seeded_circuit_reproduces_mid_circuit_measurements() {
+        let mut circuit = Circuit::new(4).unwrap();
+        circuit.add_repeating_gate(Gate::H, &[0, 1, 2, 3]).unwrap();
+        for wire in 0..4 {
+            circuit.add_gate(Gate::MeasureReset(wire), wire).unwrap();
+        }
+        circuit.set_seed(5);
+
+        let first = circuit.clone_and_simulate();
+        for _ in 0..5 {
+            assert_eq!(circuit.clone_and_simulate().get_measurements(), first.get_measurements());
+        }
+    }
 }
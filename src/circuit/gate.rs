@@ -9,13 +9,21 @@
 */
 
 use crate::circuit::standard_gate_ops;
+use crate::circuit::QResult;
+use crate::error::QuantrError;
 use crate::states::{ProductState, Qubit, SuperPosition};
+use num_complex::Complex64;
+use std::fmt;
+
+// The tolerance for checking that a matrix is unitary.
+const UNITARY_MARGIN: f64 = 1e-6;
 
 /// Gates that can be added to a [crate::Circuit] struct.
 ///
 /// Matrix representations of these gates can be found at
 /// <https://www.quantum-inspire.com/kbase/cqasm-qubit-gate-operations/>.
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Gate {
     /// Identity.
     Id,
@@ -49,10 +57,26 @@ pub enum Gate {
     MX90,
     /// Rotation of -π/2 around y-axis.
     MY90,
+    /// Universal single qubit gate, with the angles theta, phi and lambda respectively.
+    U3(f64, f64, f64),
     /// Global phase, `exp(i*theta/2) * Identity`, with angle.
     Phase(f64),
+    /// Idle period, with duration. This acts as the identity, and only carries timing
+    /// information, see [crate::Circuit::weighted_depth].
+    Delay(f64),
+    /// Barrier, which acts as the identity. A column of barriers is drawn as a dashed line across
+    /// the wires of the circuit diagram, to separate the stages of a circuit, see
+    /// [crate::Circuit::add_barrier].
+    Barrier,
     /// Controlled phase shift, with rotation and position of control node respectively.
     CR(f64, usize),
+    /// Controlled phase, `CP(θ)`, with rotation and position of control node respectively.
+    ///
+    /// This has the same matrix as [Gate::CR], which is diagonal with `exp(i*theta)` on the |11>
+    /// entry and ones elsewhere. As the matrix is symmetric in the two qubits, the control and
+    /// target nodes can be exchanged. The angle is drawn in the circuit diagram when
+    /// [crate::Printer::show_angles] is set.
+    CPhase(f64, usize),
     /// Controlled phase shift for Quantum Fourier Transforms, with rotation and position
     /// of control node respectively.
     CRk(i32, usize),
@@ -64,8 +88,48 @@ pub enum Gate {
     CNot(usize),
     /// Swap, with position of control node.
     Swap(usize),
+    /// iSwap, with position of control node. Swaps the two qubits, and adds a phase of i to the
+    /// |01> and |10> states.
+    ISwap(usize),
+    /// Two qubit XX rotation, `exp(-i*theta/2 * X⊗X)`, with angle and position of partner node
+    /// respectively.
+    Rxx(f64, usize),
+    /// Two qubit YY rotation, `exp(-i*theta/2 * Y⊗Y)`, with angle and position of partner node
+    /// respectively.
+    Ryy(f64, usize),
+    /// Two qubit ZZ rotation, `exp(-i*theta/2 * Z⊗Z)`, with angle and position of partner node
+    /// respectively. Product states of even parity gain the phase `exp(-i*theta/2)`, and those
+    /// of odd parity gain the phase `exp(i*theta/2)`.
+    Rzz(f64, usize),
     /// Toffoli, with position of control nodes.
     Toffoli(usize, usize),
+    /// Margolus gate, with position of control nodes. This is a cheaper relative-phase Toffoli,
+    /// which equals [Gate::Toffoli] up to a phase of -1 on the |101> state, where the qubits are
+    /// ordered as the first control node, the second control node, and then the target.
+    Margolus(usize, usize),
+    /// Multi-controlled Not, with position of control nodes. The target is flipped when all
+    /// control nodes are |1>.
+    MCX(Vec<usize>),
+    /// Controlled version of a single qubit gate, with the gate and position of control node
+    /// respectively. The gate is applied to the target when the control node is |1>.
+    ///
+    /// The controlled gate must be a single qubit gate without a free parameter, such as
+    /// [Gate::H] or [Gate::T], otherwise an error is returned when it is added to a circuit.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut quantum_circuit = Circuit::new(2).unwrap();
+    /// quantum_circuit.add_gate(Gate::Controlled(Box::new(Gate::H), 0), 1).unwrap();
+    ///
+    /// // A controlled Pauli-X is equivalent to the CNot gate.
+    /// assert_eq!(
+    ///     Gate::Controlled(Box::new(Gate::X), 0).as_matrix(),
+    ///     Gate::CNot(0).as_matrix()
+    /// );
+    /// ```
+    Controlled(Box<Gate>, usize),
     /// Defines a custom gate.
     ///
     /// *Note*, that the custom function isn't checked for unitarity.
@@ -74,6 +138,12 @@ pub enum Gate {
     /// will be displayed in the printed diagram respectively. The name of the custom gate
     /// should be in ASCII for it to render properly when printing the circuit diagram.
     ///
+    /// The product state passed to the function only contains the qubits of the control nodes,
+    /// in the order that they are given, followed by the qubit that the gate is placed on. So the
+    /// function must only read these qubits, and return a superposition (or `None` for the
+    /// identity) with the same number of qubits. A superposition with the wrong number of qubits
+    /// is caught by [crate::Circuit::try_simulate].
+    ///
     /// # Example
     /// ```
     /// use quantr::{Circuit, Gate};
@@ -98,14 +168,616 @@ pub enum Gate {
     /// quantum_circuit.add_gate(Gate::CNot(2), 1).unwrap();
     ///
     /// ```
+    #[cfg_attr(feature = "serde", serde(skip))]
     Custom(
         fn(ProductState) -> Option<SuperPosition>,
         Vec<usize>,
         String,
     ),
+    /// Similar to [Gate::Custom], however the mapping also takes an argument, such as an angle,
+    /// which is given as the second field of the variant.
+    ///
+    /// This allows a single function to define a family of custom gates. The control nodes and
+    /// name are given by the third and fourth fields respectively.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{SuperPosition, ProductState, Qubit};
+    /// use num_complex::Complex64;
+    ///
+    /// // Defines a controlled phase rotation.
+    /// fn controlled_phase(prod: ProductState, angle: f64) -> Option<SuperPosition> {
+    ///     match prod.get_qubits().as_slice() {
+    ///         [Qubit::One, Qubit::One] => {
+    ///             let mut amplitudes = [Complex64::ZERO; 4];
+    ///             amplitudes[3] = Complex64::from_polar(1f64, angle);
+    ///             Some(SuperPosition::new_with_amplitudes(&amplitudes).unwrap())
+    ///         }
+    ///         _ => None,
+    ///     }
+    /// }
+    ///
+    /// let mut quantum_circuit = Circuit::new(2).unwrap();
+    /// quantum_circuit
+    ///     .add_gate(Gate::CustomArg(controlled_phase, 0.5f64, vec![0], String::from("P")), 1)
+    ///     .unwrap();
+    ///
+    /// // This is equivalent to
+    /// quantum_circuit.add_gate(Gate::CR(0.5f64, 0), 1).unwrap();
+    /// ```
+    #[cfg_attr(feature = "serde", serde(skip))]
+    CustomArg(
+        fn(ProductState, f64) -> Option<SuperPosition>,
+        f64,
+        Vec<usize>,
+        String,
+    ),
+    /// A two-qubit gate defined by its 4x4 unitary matrix, see [Gate::from_matrix4].
+    ///
+    /// The matrix is in the basis |partner, position>, where the partner is given by the second
+    /// field, and the name by the third field.
+    Matrix4(Box<[[Complex64; 4]; 4]>, usize, String),
+    /// Measures the qubit in the computational basis and resets it to |0>, recording the observed
+    /// qubit against the classical bit given by the field.
+    ///
+    /// This is not a unitary gate. The outcomes are recorded in the order that the gates are
+    /// applied, see [crate::SimulatedCircuit::get_measurements].
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::Qubit;
+    ///
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap()
+    ///     .add_gate(Gate::MeasureReset(0), 0).unwrap();
+    ///
+    /// let simulated_circuit = circuit.simulate();
+    /// assert_eq!(simulated_circuit.get_measurements(), &[(0, Qubit::One)]);
+    /// ```
+    MeasureReset(usize),
+    /// A rotation whose angle is a free parameter, which is bound to a value with
+    /// [crate::Circuit::bind_parameters] before the circuit is simulated.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, GateKind, ParamId};
+    /// use std::collections::HashMap;
+    ///
+    /// let theta = ParamId(0);
+    ///
+    /// let mut ansatz = Circuit::new(2).unwrap();
+    /// ansatz.add_gate(Gate::Param(theta, GateKind::Ry), 0).unwrap()
+    ///     .add_gate(Gate::Param(theta, GateKind::CR(0)), 1).unwrap();
+    ///
+    /// for angle in [0.1f64, 0.2f64] {
+    ///     let circuit: Circuit = ansatz.bind_parameters(&HashMap::from([(theta, angle)])).unwrap();
+    ///     circuit.simulate();
+    /// }
+    /// ```
+    Param(ParamId, GateKind),
+}
+
+/// Labels a free parameter of a [Gate::Param].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParamId(pub usize);
+
+/// The gates with an angle that can be parameterised by [Gate::Param].
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GateKind {
+    /// Rotation around x-axis, see [Gate::Rx].
+    Rx,
+    /// Rotation around y-axis, see [Gate::Ry].
+    Ry,
+    /// Rotation around z-axis, see [Gate::Rz].
+    Rz,
+    /// Global phase, see [Gate::Phase].
+    Phase,
+    /// Controlled phase shift, with position of control node, see [Gate::CR].
+    CR(usize),
+}
+
+/// Broad families of gates, see [Gate::family].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GateFamily {
+    /// Gates that act as the identity, [Gate::Id], [Gate::Delay] and [Gate::Barrier].
+    Identity,
+    /// The Pauli gates, [Gate::X], [Gate::Y] and [Gate::Z].
+    Pauli,
+    /// The non-Pauli gates of the Clifford group, such as [Gate::H], [Gate::S] and [Gate::CNot].
+    Clifford,
+    /// Single qubit gates that are not Clifford gates, and take no angle, that is [Gate::T] and
+    /// [Gate::Tdag].
+    NonClifford,
+    /// Rotations with an arbitrary angle, such as [Gate::Rx], [Gate::U3] and [Gate::Rzz].
+    Rotation,
+    /// Controlled gates that are not Clifford gates, such as [Gate::Toffoli] and [Gate::CR].
+    Controlled,
+    /// Gates defined by the user, [Gate::Custom].
+    Custom,
+    /// Gates that measure a qubit, [Gate::MeasureReset].
+    Measurement,
+}
+
+impl GateKind {
+    /// Returns the gate with the given angle.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Gate, GateKind};
+    ///
+    /// assert_eq!(GateKind::Rz.bind(0.5f64), Gate::Rz(0.5f64));
+    /// ```
+    pub fn bind(&self, angle: f64) -> Gate {
+        match self {
+            GateKind::Rx => Gate::Rx(angle),
+            GateKind::Ry => Gate::Ry(angle),
+            GateKind::Rz => Gate::Rz(angle),
+            GateKind::Phase => Gate::Phase(angle),
+            GateKind::CR(c) => Gate::CR(angle, *c),
+        }
+    }
 }
 
 impl Gate {
+    /// Returns the oracle for the Bernstein–Vazirani algorithm, that maps `|x>|y> -> |x>|y ⊕ x·s>`
+    /// where `s` is the `secret` product state, and `x·s` is the bitwise dot product modulo 2.
+    ///
+    /// The qubits of the secret label the wires `0..secret.num_qubits()` of the circuit, and the
+    /// returned gate should be added onto the wire of the ancilla `|y>`. The oracle is a
+    /// [Gate::Custom], whose control nodes are the wires for which the secret has a [Qubit::One].
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{ProductState, Qubit};
+    ///
+    /// let secret = ProductState::new(&[Qubit::One, Qubit::Zero, Qubit::One]).unwrap();
+    ///
+    /// let mut circuit = Circuit::new(4).unwrap();
+    /// circuit
+    ///     .add_gate(Gate::X, 3).unwrap()
+    ///     .add_repeating_gate(Gate::H, &[0, 1, 2, 3]).unwrap()
+    ///     .add_gate(Gate::parity_oracle(&secret), 3).unwrap()
+    ///     .add_repeating_gate(Gate::H, &[0, 1, 2]).unwrap();
+    ///
+    /// // The first three wires are measured in the secret state |101>.
+    /// ```
+    pub fn parity_oracle(secret: &ProductState) -> Gate {
+        let nodes: Vec<usize> = secret
+            .get_qubits()
+            .iter()
+            .enumerate()
+            .filter(|(_, qubit)| **qubit == Qubit::One)
+            .map(|(pos, _)| pos)
+            .collect();
+        Gate::Custom(Self::parity_flip, nodes, String::from("Uf"))
+    }
+
+    // Flips the last qubit if the parity of the other qubits is odd.
+    fn parity_flip(mut prod: ProductState) -> Option<SuperPosition> {
+        let qubits: Vec<Qubit> = prod.get_qubits();
+        let (_, controls) = qubits.split_last()?;
+        if controls.iter().filter(|q| **q == Qubit::One).count() % 2 == 1 {
+            let target: usize = prod.num_qubits() - 1;
+            prod.invert_digit(target).ok()?;
+            Some(prod.into())
+        } else {
+            None
+        }
+    }
+
+    /// Returns a [Gate::Custom] without control nodes, which acts only on the single wire that it
+    /// is added to.
+    ///
+    /// The product state passed to `func` is the single qubit of that wire, and so `func` must
+    /// return a superposition of a single qubit. This is the same as constructing
+    /// `Gate::Custom(func, vec![], name.to_string())`.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{ProductState, Qubit, SuperPosition};
+    ///
+    /// // Maps |0> to |1>, and annihilates |1>.
+    /// fn raise(prod: ProductState) -> Option<SuperPosition> {
+    ///     match prod.get_qubits()[0] {
+    ///         Qubit::Zero => Some(SuperPosition::from(Qubit::One)),
+    ///         Qubit::One => Some(SuperPosition::new_with_amplitudes_unchecked(
+    ///             &[num_complex::Complex64::ZERO; 2],
+    ///         )),
+    ///     }
+    /// }
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::custom_single(raise, "R"), 1).unwrap();
+    /// ```
+    pub fn custom_single(func: fn(ProductState) -> Option<SuperPosition>, name: &str) -> Gate {
+        Gate::Custom(func, vec![], name.to_string())
+    }
+
+    /// Returns a [Gate::Matrix4] from a 4x4 unitary matrix, which acts on the wire that it is
+    /// added to and the partner wire.
+    ///
+    /// The matrix is in the basis |partner, position>, in the same way that the matrix of
+    /// [Gate::CNot] is in the basis |control, target>, see [Gate::as_matrix]. An error is returned
+    /// if the matrix is not unitary.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::complex_re_array;
+    ///
+    /// let swap = [
+    ///     complex_re_array!(1f64, 0f64, 0f64, 0f64),
+    ///     complex_re_array!(0f64, 0f64, 1f64, 0f64),
+    ///     complex_re_array!(0f64, 1f64, 0f64, 0f64),
+    ///     complex_re_array!(0f64, 0f64, 0f64, 1f64),
+    /// ];
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::from_matrix4(swap, 0, String::from("U")).unwrap(), 1).unwrap();
+    /// ```
+    pub fn from_matrix4(
+        matrix: [[Complex64; 4]; 4],
+        partner: usize,
+        name: String,
+    ) -> QResult<Gate> {
+        if !is_unitary(4, |row, column| matrix[row][column]) {
+            return Err(QuantrError::Other {
+                message: format!("The matrix of the gate, {}, is not unitary.", name),
+            });
+        }
+
+        Ok(Gate::Matrix4(Box::new(matrix), partner, name))
+    }
+
+    /// Returns the adjoint of the gate, which keeps the same control nodes.
+    ///
+    /// `None` is returned for [Gate::Custom], as its mapping cannot be inverted automatically,
+    /// for [Gate::MeasureReset] and for [Gate::Param]. The adjoint of [Gate::CRk] is returned as
+    /// a [Gate::CR] with the opposite rotation, and the adjoint of [Gate::ISwap] as a
+    /// [Gate::Matrix4] named "iSw*".
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::Gate;
+    ///
+    /// assert_eq!(Gate::T.inverse(), Some(Gate::Tdag));
+    /// assert_eq!(Gate::Rx(0.5f64).inverse(), Some(Gate::Rx(-0.5f64)));
+    /// ```
+    pub fn inverse(&self) -> Option<Gate> {
+        Some(match self {
+            Gate::S => Gate::Sdag,
+            Gate::Sdag => Gate::S,
+            Gate::T => Gate::Tdag,
+            Gate::Tdag => Gate::T,
+            Gate::Rx(angle) => Gate::Rx(-angle),
+            Gate::Ry(angle) => Gate::Ry(-angle),
+            Gate::Rz(angle) => Gate::Rz(-angle),
+            Gate::U3(theta, phi, lambda) => Gate::U3(-theta, -lambda, -phi),
+            Gate::X90 => Gate::MX90,
+            Gate::Y90 => Gate::MY90,
+            Gate::MX90 => Gate::X90,
+            Gate::MY90 => Gate::Y90,
+            Gate::Phase(angle) => Gate::Phase(-angle),
+            Gate::CR(angle, c) => Gate::CR(-angle, *c),
+            Gate::CPhase(angle, c) => Gate::CPhase(-angle, *c),
+            Gate::CRk(k, c) => Gate::CR(-2f64 * std::f64::consts::PI / 2f64.powi(*k), *c),
+            Gate::Rxx(angle, c) => Gate::Rxx(-angle, *c),
+            Gate::Ryy(angle, c) => Gate::Ryy(-angle, *c),
+            Gate::Rzz(angle, c) => Gate::Rzz(-angle, *c),
+            Gate::Id
+            | Gate::H
+            | Gate::X
+            | Gate::Y
+            | Gate::Z
+            | Gate::Delay(_)
+            | Gate::Barrier
+            | Gate::CZ(_)
+            | Gate::CY(_)
+            | Gate::CNot(_)
+            | Gate::Swap(_)
+            | Gate::Toffoli(_, _)
+            | Gate::Margolus(_, _)
+            | Gate::MCX(_) => self.clone(),
+            Gate::Controlled(gate, c) => Gate::Controlled(Box::new(gate.inverse()?), *c),
+            Gate::Matrix4(matrix, partner, name) => {
+                let adjoint: [[Complex64; 4]; 4] =
+                    std::array::from_fn(|i| std::array::from_fn(|j| matrix[j][i].conj()));
+                Gate::Matrix4(Box::new(adjoint), *partner, name.clone())
+            }
+            Gate::ISwap(c) => {
+                let (zero, one, minus_i) = (Complex64::ZERO, Complex64::ONE, -Complex64::I);
+                let adjoint: [[Complex64; 4]; 4] = [
+                    [one, zero, zero, zero],
+                    [zero, zero, minus_i, zero],
+                    [zero, minus_i, zero, zero],
+                    [zero, zero, zero, one],
+                ];
+                Gate::Matrix4(Box::new(adjoint), *c, String::from("iSw*"))
+            }
+            Gate::Custom(_, _, _)
+            | Gate::CustomArg(_, _, _, _)
+            | Gate::MeasureReset(_)
+            | Gate::Param(_, _) => return None,
+        })
+    }
+
+    /// Returns the dense matrix of the gate, where each column is the image of a state in the
+    /// computational basis.
+    ///
+    /// The basis is labelled by the control nodes, in the order that they are given in the
+    /// variant, followed by the wire that the gate is placed on. For example, the 4x4 matrix of
+    /// [Gate::CNot] is in the basis |control, target>. `None` is returned for [Gate::Custom],
+    /// [Gate::MeasureReset], for an unbound [Gate::Param], and for a [Gate::Controlled] that does
+    /// not control a single qubit gate.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::Gate;
+    /// use quantr::complex_re_array;
+    ///
+    /// assert_eq!(
+    ///     Gate::X.as_matrix(),
+    ///     Some(vec![complex_re_array!(0f64, 1f64).to_vec(), complex_re_array!(1f64, 0f64).to_vec()])
+    /// );
+    /// ```
+    pub fn as_matrix(&self) -> Option<Vec<Vec<Complex64>>> {
+        if self.is_custom_gate()
+            || matches!(self, Gate::MeasureReset(_) | Gate::Param(_, _))
+            || self.check_controlled().is_err()
+        {
+            return None;
+        }
+
+        self.matrix_from_images().ok()
+    }
+
+    // Assembles the matrix of the gate from the images of the product states that it acts on,
+    // where the mapping of a custom gate is evaluated on each product state. An error is
+    // returned if an image has the wrong number of qubits.
+    pub(crate) fn matrix_from_images(&self) -> QResult<Vec<Vec<Complex64>>> {
+        let num_qubits: usize = self.get_nodes().map_or(0, |nodes| nodes.len()) + 1;
+        let dim: usize = 1 << num_qubits;
+        let category: GateCategory = self.linker();
+        let columns: Vec<SuperPosition> = (0..dim)
+            .map(|j| {
+                let basis: ProductState = ProductState::binary_basis(j, num_qubits);
+                let qubits: Vec<Qubit> = basis.get_qubits();
+                Ok(match category {
+                    GateCategory::Identity => SuperPosition::from(basis.clone()),
+                    GateCategory::Single(func) => func(qubits[0]),
+                    GateCategory::SingleArg(arg, func) => func(qubits[0], arg),
+                    GateCategory::SingleArgThree(theta, phi, lambda, func) => {
+                        func(qubits[0], theta, phi, lambda)
+                    }
+                    GateCategory::Double(_, func) => func(qubits[0], qubits[1]),
+                    GateCategory::DoubleArg(arg, _, func) => func(qubits[0], qubits[1], arg),
+                    GateCategory::DoubleArgInt(arg, _, func) => func(qubits[0], qubits[1], arg),
+                    GateCategory::Triple(_, _, func) => func(qubits[0], qubits[1], qubits[2]),
+                    GateCategory::Custom(func, _) => {
+                        func(basis.clone()).unwrap_or_else(|| SuperPosition::from(basis.clone()))
+                    }
+                    GateCategory::CustomArg(func, arg, _) => func(basis.clone(), arg)
+                        .unwrap_or_else(|| SuperPosition::from(basis.clone())),
+                    GateCategory::Matrix4(ref matrix, _) => matrix4_column(matrix, j),
+                    GateCategory::MeasureReset(_) => {
+                        return Err(QuantrError::Other {
+                            message: format!(
+                                "The gate, {:?}, is a measurement, and so has no matrix.",
+                                self
+                            ),
+                        })
+                    }
+                })
+            })
+            .collect::<QResult<Vec<SuperPosition>>>()?;
+
+        if let Some(column) = columns
+            .iter()
+            .find(|column| column.product_dim != num_qubits)
+        {
+            return Err(QuantrError::DimensionMismatch {
+                expected: num_qubits,
+                found: column.product_dim,
+                message: format!("The mapping of the gate, {}, returned a superposition of {} qubits, when the gate acts on {} qubits.", self.get_name(), column.product_dim, num_qubits),
+            });
+        }
+
+        Ok((0..dim)
+            .map(|i| columns.iter().map(|column| column.amplitudes[i]).collect())
+            .collect())
+    }
+
+    /// Returns the family that the gate belongs to.
+    ///
+    /// Gates with an arbitrary angle are always regarded as a [GateFamily::Rotation], or
+    /// [GateFamily::Controlled] if they have a control node, even if the angle makes them a
+    /// Clifford gate. A [Gate::Param] belongs to the family of the gate that it is bound to.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Gate, GateFamily};
+    ///
+    /// assert_eq!(Gate::H.family(), GateFamily::Clifford);
+    /// assert_eq!(Gate::T.family(), GateFamily::NonClifford);
+    /// assert_eq!(Gate::Toffoli(0, 1).family(), GateFamily::Controlled);
+    /// ```
+    pub fn family(&self) -> GateFamily {
+        match self {
+            Gate::Id | Gate::Delay(_) | Gate::Barrier => GateFamily::Identity,
+            Gate::X | Gate::Y | Gate::Z => GateFamily::Pauli,
+            Gate::H
+            | Gate::S
+            | Gate::Sdag
+            | Gate::X90
+            | Gate::Y90
+            | Gate::MX90
+            | Gate::MY90
+            | Gate::CNot(_)
+            | Gate::CZ(_)
+            | Gate::CY(_)
+            | Gate::Swap(_)
+            | Gate::ISwap(_) => GateFamily::Clifford,
+            Gate::T | Gate::Tdag => GateFamily::NonClifford,
+            Gate::Rx(_)
+            | Gate::Ry(_)
+            | Gate::Rz(_)
+            | Gate::U3(_, _, _)
+            | Gate::Phase(_)
+            | Gate::Rxx(_, _)
+            | Gate::Ryy(_, _)
+            | Gate::Rzz(_, _) => GateFamily::Rotation,
+            Gate::CR(_, _)
+            | Gate::CPhase(_, _)
+            | Gate::CRk(_, _)
+            | Gate::Toffoli(_, _)
+            | Gate::Margolus(_, _)
+            | Gate::MCX(_) => GateFamily::Controlled,
+            Gate::Custom(_, _, _) | Gate::CustomArg(_, _, _, _) | Gate::Matrix4(_, _, _) => {
+                GateFamily::Custom
+            }
+            Gate::Controlled(gate, _) => match gate.family() {
+                GateFamily::Identity => GateFamily::Identity,
+                GateFamily::Pauli => GateFamily::Clifford,
+                _ => GateFamily::Controlled,
+            },
+            Gate::MeasureReset(_) => GateFamily::Measurement,
+            Gate::Param(_, kind) => kind.bind(0f64).family(),
+        }
+    }
+
+    /// Returns if the gate is diagonal in the computational basis.
+    ///
+    /// Diagonal gates only change the phase of each product state, and so commute with each
+    /// other. [Gate::Custom] is always regarded as non-diagonal, as its mapping is not inspected.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::Gate;
+    ///
+    /// assert!(Gate::Rz(0.5f64).is_diagonal());
+    /// assert!(!Gate::H.is_diagonal());
+    /// ```
+    pub fn is_diagonal(&self) -> bool {
+        match self {
+            Gate::Id
+            | Gate::Z
+            | Gate::S
+            | Gate::Sdag
+            | Gate::T
+            | Gate::Tdag
+            | Gate::Rz(_)
+            | Gate::Phase(_)
+            | Gate::Delay(_)
+            | Gate::Barrier
+            | Gate::CZ(_)
+            | Gate::CR(_, _)
+            | Gate::CPhase(_, _)
+            | Gate::CRk(_, _)
+            | Gate::Rzz(_, _) => true,
+            Gate::H
+            | Gate::X
+            | Gate::Y
+            | Gate::Rx(_)
+            | Gate::Ry(_)
+            | Gate::U3(_, _, _)
+            | Gate::X90
+            | Gate::Y90
+            | Gate::MX90
+            | Gate::MY90
+            | Gate::CNot(_)
+            | Gate::CY(_)
+            | Gate::Swap(_)
+            | Gate::ISwap(_)
+            | Gate::Rxx(_, _)
+            | Gate::Ryy(_, _)
+            | Gate::Toffoli(_, _)
+            | Gate::Margolus(_, _)
+            | Gate::MCX(_)
+            | Gate::Custom(_, _, _)
+            | Gate::CustomArg(_, _, _, _)
+            | Gate::MeasureReset(_) => false,
+            Gate::Controlled(gate, _) => gate.is_diagonal(),
+            Gate::Matrix4(matrix, _, _) => {
+                (0..4).all(|i| (0..4).all(|j| i == j || matrix[i][j].norm() < UNITARY_MARGIN))
+            }
+            Gate::Param(_, kind) => kind.bind(0f64).is_diagonal(),
+        }
+    }
+
+    /// Returns if two gates commute, given the wires that they are placed on.
+    ///
+    /// Gates on disjoint wires, diagonal gates, and rotations around the same axis of a single
+    /// wire always commute. The CNot gate also commutes with diagonal gates on its control node,
+    /// and with rotations around the x-axis on its target. This is conservative, and so `false`
+    /// is returned for any other pair of gates, even if they do commute.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::Gate;
+    ///
+    /// assert!(Gate::Z.commutes_with(&Gate::T, 0, 0));
+    /// assert!(Gate::X.commutes_with(&Gate::Z, 0, 1));
+    /// assert!(!Gate::X.commutes_with(&Gate::Z, 0, 0));
+    /// ```
+    pub fn commutes_with(&self, other: &Gate, self_pos: usize, other_pos: usize) -> bool {
+        let mut self_wires: Vec<usize> = self.get_nodes().unwrap_or_default();
+        self_wires.push(self_pos);
+        let mut other_wires: Vec<usize> = other.get_nodes().unwrap_or_default();
+        other_wires.push(other_pos);
+
+        if self.is_identity_up_to_phase()
+            || other.is_identity_up_to_phase()
+            || !self_wires.iter().any(|wire| other_wires.contains(wire))
+            || (self.is_diagonal() && other.is_diagonal())
+            || (self == other && self_pos == other_pos)
+        {
+            return true;
+        }
+
+        match (self, other) {
+            (Gate::CNot(c), single) if single.is_single_gate() => {
+                Self::cnot_commutes_with_single(*c, self_pos, single, other_pos)
+            }
+            (single, Gate::CNot(c)) if single.is_single_gate() => {
+                Self::cnot_commutes_with_single(*c, other_pos, single, self_pos)
+            }
+            (a, b) if a.is_single_gate() && b.is_single_gate() => {
+                (a.is_x_rotation() && b.is_x_rotation()) || (a.is_y_rotation() && b.is_y_rotation())
+            }
+            _ => false,
+        }
+    }
+
+    // A single gate commutes with a CNot if it is diagonal on the control, or an x-rotation on the
+    // target.
+    fn cnot_commutes_with_single(
+        control: usize,
+        target: usize,
+        single: &Gate,
+        position: usize,
+    ) -> bool {
+        (position == control && single.is_diagonal())
+            || (position == target && single.is_x_rotation())
+    }
+
+    fn is_identity_up_to_phase(&self) -> bool {
+        matches!(self, Gate::Id | Gate::Delay(_) | Gate::Phase(_))
+    }
+
+    fn is_x_rotation(&self) -> bool {
+        matches!(self, Gate::X | Gate::Rx(_) | Gate::X90 | Gate::MX90)
+    }
+
+    fn is_y_rotation(&self) -> bool {
+        matches!(self, Gate::Y | Gate::Ry(_) | Gate::Y90 | Gate::MY90)
+    }
+
     // Retrieves the list of nodes within a gate.
     pub(super) fn get_nodes(&self) -> Option<Vec<usize>> {
         match self {
@@ -121,25 +793,76 @@ impl Gate {
             | Gate::Rx(_)
             | Gate::Ry(_)
             | Gate::Rz(_)
+            | Gate::U3(_, _, _)
             | Gate::Phase(_)
+            | Gate::Delay(_)
+            | Gate::Barrier
             | Gate::X90
             | Gate::Y90
             | Gate::MX90
-            | Gate::MY90 => None,
+            | Gate::MY90
+            | Gate::MeasureReset(_) => None,
             Gate::CNot(c)
             | Gate::Swap(c)
+            | Gate::ISwap(c)
             | Gate::CZ(c)
             | Gate::CY(c)
             | Gate::CR(_, c)
-            | Gate::CRk(_, c) => Some(vec![*c]),
-            Gate::Toffoli(c1, c2) => Some(vec![*c1, *c2]),
-            Gate::Custom(_, nodes, _) => Some(nodes.to_vec()),
+            | Gate::CPhase(_, c)
+            | Gate::CRk(_, c)
+            | Gate::Rxx(_, c)
+            | Gate::Ryy(_, c)
+            | Gate::Rzz(_, c)
+            | Gate::Controlled(_, c) => Some(vec![*c]),
+            Gate::Toffoli(c1, c2) | Gate::Margolus(c1, c2) => Some(vec![*c1, *c2]),
+            Gate::Matrix4(_, partner, _) => Some(vec![*partner]),
+            Gate::MCX(controls) => Some(controls.to_vec()),
+            Gate::Custom(_, nodes, _) | Gate::CustomArg(_, _, nodes, _) => Some(nodes.to_vec()),
+            Gate::Param(_, kind) => kind.bind(0f64).get_nodes(),
+        }
+    }
+
+    // Returns the gate with the positions of its nodes increased by the offset, for when the gate
+    // is moved to wires further down a circuit.
+    pub(crate) fn shift_nodes(&self, offset: usize) -> Gate {
+        match self {
+            Gate::CR(angle, c) => Gate::CR(*angle, c + offset),
+            Gate::CPhase(angle, c) => Gate::CPhase(*angle, c + offset),
+            Gate::CRk(k, c) => Gate::CRk(*k, c + offset),
+            Gate::CZ(c) => Gate::CZ(c + offset),
+            Gate::CY(c) => Gate::CY(c + offset),
+            Gate::CNot(c) => Gate::CNot(c + offset),
+            Gate::Swap(c) => Gate::Swap(c + offset),
+            Gate::ISwap(c) => Gate::ISwap(c + offset),
+            Gate::Rxx(angle, c) => Gate::Rxx(*angle, c + offset),
+            Gate::Ryy(angle, c) => Gate::Ryy(*angle, c + offset),
+            Gate::Rzz(angle, c) => Gate::Rzz(*angle, c + offset),
+            Gate::Toffoli(c1, c2) => Gate::Toffoli(c1 + offset, c2 + offset),
+            Gate::Margolus(c1, c2) => Gate::Margolus(c1 + offset, c2 + offset),
+            Gate::MCX(controls) => Gate::MCX(controls.iter().map(|c| c + offset).collect()),
+            Gate::Controlled(gate, c) => Gate::Controlled(gate.clone(), c + offset),
+            Gate::Matrix4(matrix, partner, name) => {
+                Gate::Matrix4(matrix.clone(), partner + offset, name.clone())
+            }
+            Gate::Custom(func, nodes, name) => Gate::Custom(
+                *func,
+                nodes.iter().map(|c| c + offset).collect(),
+                name.clone(),
+            ),
+            Gate::CustomArg(func, arg, nodes, name) => Gate::CustomArg(
+                *func,
+                *arg,
+                nodes.iter().map(|c| c + offset).collect(),
+                name.clone(),
+            ),
+            Gate::Param(id, GateKind::CR(c)) => Gate::Param(*id, GateKind::CR(c + offset)),
+            _ => self.clone(),
         }
     }
 
     pub(crate) fn linker(&self) -> GateCategory {
         match self {
-            Gate::Id => GateCategory::Identity,
+            Gate::Id | Gate::Delay(_) | Gate::Barrier => GateCategory::Identity,
             Gate::H => GateCategory::Single(standard_gate_ops::hadamard),
             Gate::S => GateCategory::Single(standard_gate_ops::phase),
             Gate::Sdag => GateCategory::Single(standard_gate_ops::phasedag),
@@ -155,15 +878,38 @@ impl Gate {
             Gate::Rx(arg) => GateCategory::SingleArg(*arg, standard_gate_ops::rx),
             Gate::Ry(arg) => GateCategory::SingleArg(*arg, standard_gate_ops::ry),
             Gate::Rz(arg) => GateCategory::SingleArg(*arg, standard_gate_ops::rz),
+            Gate::U3(theta, phi, lambda) => {
+                GateCategory::SingleArgThree(*theta, *phi, *lambda, standard_gate_ops::u3)
+            }
             Gate::Phase(arg) => GateCategory::SingleArg(*arg, standard_gate_ops::global_phase),
             Gate::CNot(c) => GateCategory::Double(*c, standard_gate_ops::cnot),
             Gate::Swap(c) => GateCategory::Double(*c, standard_gate_ops::swap),
+            Gate::ISwap(c) => GateCategory::Double(*c, standard_gate_ops::iswap),
             Gate::CZ(c) => GateCategory::Double(*c, standard_gate_ops::cz),
             Gate::CY(c) => GateCategory::Double(*c, standard_gate_ops::cy),
-            Gate::CR(arg, c) => GateCategory::DoubleArg(*arg, *c, standard_gate_ops::cr),
+            Gate::CR(arg, c) | Gate::CPhase(arg, c) => {
+                GateCategory::DoubleArg(*arg, *c, standard_gate_ops::cr)
+            }
             Gate::CRk(arg, c) => GateCategory::DoubleArgInt(*arg, *c, standard_gate_ops::crk),
+            Gate::Rxx(arg, c) => GateCategory::DoubleArg(*arg, *c, standard_gate_ops::rxx),
+            Gate::Ryy(arg, c) => GateCategory::DoubleArg(*arg, *c, standard_gate_ops::ryy),
+            Gate::Rzz(arg, c) => GateCategory::DoubleArg(*arg, *c, standard_gate_ops::rzz),
             Gate::Toffoli(c1, c2) => GateCategory::Triple(*c1, *c2, standard_gate_ops::toffoli),
-            Gate::Custom(func, controls, _) => GateCategory::Custom(*func, controls),
+            Gate::Margolus(c1, c2) => GateCategory::Triple(*c1, *c2, standard_gate_ops::margolus),
+            Gate::Matrix4(matrix, partner, _) => GateCategory::Matrix4(matrix.clone(), *partner),
+            Gate::Controlled(gate, c) => match controlled_matrix(gate) {
+                Some(matrix) => GateCategory::Matrix4(Box::new(matrix), *c),
+                // Invalid controlled gates are caught before the circuit is simulated.
+                None => GateCategory::Identity,
+            },
+            Gate::MCX(controls) => GateCategory::Custom(standard_gate_ops::mcx, controls.clone()),
+            Gate::Custom(func, controls, _) => GateCategory::Custom(*func, controls.clone()),
+            Gate::CustomArg(func, arg, controls, _) => {
+                GateCategory::CustomArg(*func, *arg, controls.clone())
+            }
+            Gate::MeasureReset(bit) => GateCategory::MeasureReset(*bit),
+            // Unbound parameters are caught before the circuit is simulated.
+            Gate::Param(_, _) => GateCategory::Identity,
         }
     }
 
@@ -184,29 +930,62 @@ impl Gate {
             | Gate::Rx(_)
             | Gate::Ry(_)
             | Gate::Rz(_)
+            | Gate::U3(_, _, _)
             | Gate::Phase(_)
+            | Gate::Delay(_)
+            | Gate::Barrier
             | Gate::X90
             | Gate::Y90
             | Gate::MX90
-            | Gate::MY90 => true,
+            | Gate::MY90
+            | Gate::MeasureReset(_) => true,
             Gate::CNot(_)
             | Gate::Swap(_)
+            | Gate::ISwap(_)
             | Gate::CZ(_)
             | Gate::CY(_)
             | Gate::CR(_, _)
+            | Gate::CPhase(_, _)
             | Gate::CRk(_, _)
+            | Gate::Rxx(_, _)
+            | Gate::Ryy(_, _)
+            | Gate::Rzz(_, _)
             | Gate::Toffoli(_, _)
-            | Gate::Custom(_, _, _) => false,
+            | Gate::Margolus(_, _)
+            | Gate::MCX(_)
+            | Gate::Custom(_, _, _)
+            | Gate::CustomArg(_, _, _, _)
+            | Gate::Matrix4(_, _, _)
+            | Gate::Controlled(_, _) => false,
+            Gate::Param(_, kind) => kind.bind(0f64).is_single_gate(),
         }
     }
 
-    pub(crate) fn is_custom_gate(&self) -> bool {
+    // Returns if the gate can be controlled by Gate::Controlled, that is a single qubit gate
+    // without a free parameter, which excludes measurements.
+    pub(crate) fn is_controllable(&self) -> bool {
+        self.is_single_gate() && !matches!(self, Gate::MeasureReset(_) | Gate::Param(_, _))
+    }
+
+    // Checks that a controlled gate controls a gate that can be controlled.
+    pub(crate) fn check_controlled(&self) -> QResult<()> {
         match self {
-            Gate::Custom(_, _, _) => true,
-            _ => false,
+            Gate::Controlled(inner, _) if !inner.is_controllable() => {
+                Err(QuantrError::Other {
+                    message: format!(
+                        "The controlled gate, {:?}, must be a single qubit gate without a free parameter.",
+                        inner
+                    ),
+                })
+            }
+            _ => Ok(()),
         }
     }
 
+    pub(crate) fn is_custom_gate(&self) -> bool {
+        matches!(self, Gate::Custom(_, _, _) | Gate::CustomArg(_, _, _, _))
+    }
+
     pub(crate) fn get_name(&self) -> String {
         match self {
             Gate::Id => "".to_string(),
@@ -221,39 +1000,535 @@ impl Gate {
             Gate::Rx(_) => "Rx".to_string(),
             Gate::Ry(_) => "Ry".to_string(),
             Gate::Rz(_) => "Rz".to_string(),
+            Gate::U3(_, _, _) => "U3".to_string(),
             Gate::Phase(_) => "P".to_string(),
+            Gate::Delay(_) => "D".to_string(),
+            Gate::Barrier => "".to_string(),
             Gate::X90 => "X90".to_string(),
             Gate::Y90 => "Y90".to_string(),
             Gate::MX90 => "X90*".to_string(),
             Gate::MY90 => "Y90*".to_string(),
             Gate::CR(_, _) => "CR".to_string(),
+            Gate::CPhase(_, _) => "CP".to_string(),
             Gate::CRk(_, _) => "CRk".to_string(),
             Gate::Swap(_) => "Sw".to_string(),
+            Gate::ISwap(_) => "iSw".to_string(),
+            Gate::Rxx(_, _) => "Rxx".to_string(),
+            Gate::Ryy(_, _) => "Ryy".to_string(),
+            Gate::Rzz(_, _) => "Rzz".to_string(),
             Gate::CZ(_) => "Z".to_string(),
             Gate::CY(_) => "Y".to_string(),
             Gate::CNot(_) => "X".to_string(),
             Gate::Toffoli(_, _) => "X".to_string(),
-            Gate::Custom(_, _, name) => name.to_string(),
+            Gate::Margolus(_, _) => "X~".to_string(),
+            Gate::MCX(_) => "X".to_string(),
+            Gate::MeasureReset(_) => "M".to_string(),
+            Gate::Controlled(gate, _) => gate.get_name(),
+            Gate::Custom(_, _, name)
+            | Gate::CustomArg(_, _, _, name)
+            | Gate::Matrix4(_, _, name) => name.to_string(),
+            Gate::Param(id, kind) => format!("{}(p{})", kind.bind(0f64).get_name(), id.0),
+        }
+    }
+}
+
+// Custom gates are compared by the addresses of their functions with std::ptr::fn_addr_eq, as
+// with the derived implementation, along with their other fields.
+impl PartialEq for Gate {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Gate::Custom(func, nodes, name),
+                Gate::Custom(other_func, other_nodes, other_name),
+            ) => {
+                std::ptr::fn_addr_eq(*func, *other_func)
+                    && nodes == other_nodes
+                    && name == other_name
+            }
+            (
+                Gate::CustomArg(func, arg, nodes, name),
+                Gate::CustomArg(other_func, other_arg, other_nodes, other_name),
+            ) => {
+                std::ptr::fn_addr_eq(*func, *other_func)
+                    && arg == other_arg
+                    && nodes == other_nodes
+                    && name == other_name
+            }
+            (Gate::Rx(angle), Gate::Rx(other_angle))
+            | (Gate::Ry(angle), Gate::Ry(other_angle))
+            | (Gate::Rz(angle), Gate::Rz(other_angle))
+            | (Gate::Phase(angle), Gate::Phase(other_angle))
+            | (Gate::Delay(angle), Gate::Delay(other_angle)) => angle == other_angle,
+            (Gate::U3(theta, phi, lambda), Gate::U3(other_theta, other_phi, other_lambda)) => {
+                theta == other_theta && phi == other_phi && lambda == other_lambda
+            }
+            (Gate::CR(angle, control), Gate::CR(other_angle, other_control))
+            | (Gate::CPhase(angle, control), Gate::CPhase(other_angle, other_control))
+            | (Gate::Rxx(angle, control), Gate::Rxx(other_angle, other_control))
+            | (Gate::Ryy(angle, control), Gate::Ryy(other_angle, other_control))
+            | (Gate::Rzz(angle, control), Gate::Rzz(other_angle, other_control)) => {
+                angle == other_angle && control == other_control
+            }
+            (Gate::CRk(k, control), Gate::CRk(other_k, other_control)) => {
+                k == other_k && control == other_control
+            }
+            (Gate::CZ(control), Gate::CZ(other_control))
+            | (Gate::CY(control), Gate::CY(other_control))
+            | (Gate::CNot(control), Gate::CNot(other_control))
+            | (Gate::Swap(control), Gate::Swap(other_control))
+            | (Gate::ISwap(control), Gate::ISwap(other_control))
+            | (Gate::MeasureReset(control), Gate::MeasureReset(other_control)) => {
+                control == other_control
+            }
+            (Gate::Toffoli(first, second), Gate::Toffoli(other_first, other_second))
+            | (Gate::Margolus(first, second), Gate::Margolus(other_first, other_second)) => {
+                first == other_first && second == other_second
+            }
+            (Gate::MCX(controls), Gate::MCX(other_controls)) => controls == other_controls,
+            (Gate::Controlled(gate, control), Gate::Controlled(other_gate, other_control)) => {
+                gate == other_gate && control == other_control
+            }
+            (
+                Gate::Matrix4(matrix, control, name),
+                Gate::Matrix4(other_matrix, other_control, other_name),
+            ) => matrix == other_matrix && control == other_control && name == other_name,
+            (Gate::Param(id, kind), Gate::Param(other_id, other_kind)) => {
+                id == other_id && kind == other_kind
+            }
+            // The remaining variants have no fields.
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl fmt::Display for Gate {
+    /// Returns a description of the gate, including its angles and control nodes. Angles are
+    /// given to three decimal places.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::Gate;
+    ///
+    /// assert_eq!(Gate::CNot(0).to_string(), "CNOT (control 0)");
+    /// assert_eq!(Gate::Rx(1.5708f64).to_string(), "Rx(θ=1.571)");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let controls = |nodes: &[usize]| {
+            nodes
+                .iter()
+                .map(|node| node.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        match self {
+            Gate::Id => write!(f, "Id"),
+            Gate::H => write!(f, "H"),
+            Gate::X => write!(f, "X"),
+            Gate::Y => write!(f, "Y"),
+            Gate::Z => write!(f, "Z"),
+            Gate::S => write!(f, "S"),
+            Gate::Sdag => write!(f, "Sdag"),
+            Gate::T => write!(f, "T"),
+            Gate::Tdag => write!(f, "Tdag"),
+            Gate::Rx(angle) => write!(f, "Rx(θ={:.3})", angle),
+            Gate::Ry(angle) => write!(f, "Ry(θ={:.3})", angle),
+            Gate::Rz(angle) => write!(f, "Rz(θ={:.3})", angle),
+            Gate::X90 => write!(f, "X90"),
+            Gate::Y90 => write!(f, "Y90"),
+            Gate::MX90 => write!(f, "MX90"),
+            Gate::MY90 => write!(f, "MY90"),
+            Gate::U3(theta, phi, lambda) => {
+                write!(f, "U3(θ={:.3}, φ={:.3}, λ={:.3})", theta, phi, lambda)
+            }
+            Gate::Phase(angle) => write!(f, "Phase(θ={:.3})", angle),
+            Gate::Delay(duration) => write!(f, "Delay(duration={:.3})", duration),
+            Gate::Barrier => write!(f, "Barrier"),
+            Gate::CR(angle, c) => write!(f, "CR(θ={:.3}) (control {})", angle, c),
+            Gate::CPhase(angle, c) => write!(f, "CPhase(θ={:.3}) (control {})", angle, c),
+            Gate::CRk(k, c) => write!(f, "CRk(k={}) (control {})", k, c),
+            Gate::CZ(c) => write!(f, "CZ (control {})", c),
+            Gate::CY(c) => write!(f, "CY (control {})", c),
+            Gate::CNot(c) => write!(f, "CNOT (control {})", c),
+            Gate::Swap(c) => write!(f, "Swap (with {})", c),
+            Gate::ISwap(c) => write!(f, "iSwap (with {})", c),
+            Gate::Rxx(angle, c) => write!(f, "Rxx(θ={:.3}) (with {})", angle, c),
+            Gate::Ryy(angle, c) => write!(f, "Ryy(θ={:.3}) (with {})", angle, c),
+            Gate::Rzz(angle, c) => write!(f, "Rzz(θ={:.3}) (with {})", angle, c),
+            Gate::Toffoli(c1, c2) => write!(f, "Toffoli (controls {})", controls(&[*c1, *c2])),
+            Gate::Margolus(c1, c2) => write!(f, "Margolus (controls {})", controls(&[*c1, *c2])),
+            Gate::Matrix4(_, partner, name) => write!(f, "Matrix4({}) (with {})", name, partner),
+            Gate::MCX(nodes) => write!(f, "MCX (controls {})", controls(nodes)),
+            Gate::Controlled(gate, c) => write!(f, "Controlled({}) (control {})", gate, c),
+            Gate::Custom(_, nodes, name) if nodes.is_empty() => write!(f, "Custom({})", name),
+            Gate::Custom(_, nodes, name) => {
+                write!(f, "Custom({}) (controls {})", name, controls(nodes))
+            }
+            Gate::CustomArg(_, arg, nodes, name) if nodes.is_empty() => {
+                write!(f, "Custom({}, arg={:.3})", name, arg)
+            }
+            Gate::CustomArg(_, arg, nodes, name) => write!(
+                f,
+                "Custom({}, arg={:.3}) (controls {})",
+                name,
+                arg,
+                controls(nodes)
+            ),
+            Gate::MeasureReset(bit) => write!(f, "MeasureReset (bit {})", bit),
+            Gate::Param(id, kind) => match kind {
+                GateKind::Rx => write!(f, "Rx(θ=p{})", id.0),
+                GateKind::Ry => write!(f, "Ry(θ=p{})", id.0),
+                GateKind::Rz => write!(f, "Rz(θ=p{})", id.0),
+                GateKind::Phase => write!(f, "Phase(θ=p{})", id.0),
+                GateKind::CR(c) => write!(f, "CR(θ=p{}) (control {})", id.0, c),
+            },
         }
     }
 }
 
 // Contain second variant that references the function in standard_gate_ops.rs
-#[derive(PartialEq, Debug)]
-pub(crate) enum GateCategory<'a> {
+#[derive(Clone, Debug)]
+pub(crate) enum GateCategory {
     Identity,
     Single(fn(Qubit) -> SuperPosition),
     SingleArg(f64, fn(Qubit, f64) -> SuperPosition),
+    SingleArgThree(f64, f64, f64, fn(Qubit, f64, f64, f64) -> SuperPosition),
     Double(usize, fn(Qubit, Qubit) -> SuperPosition),
     DoubleArg(f64, usize, fn(Qubit, Qubit, f64) -> SuperPosition),
     DoubleArgInt(i32, usize, fn(Qubit, Qubit, i32) -> SuperPosition),
     Triple(usize, usize, fn(Qubit, Qubit, Qubit) -> SuperPosition),
-    Custom(fn(ProductState) -> Option<SuperPosition>, &'a [usize]),
+    Custom(fn(ProductState) -> Option<SuperPosition>, Vec<usize>),
+    CustomArg(
+        fn(ProductState, f64) -> Option<SuperPosition>,
+        f64,
+        Vec<usize>,
+    ),
+    Matrix4(Box<[[Complex64; 4]; 4]>, usize),
+    MeasureReset(usize),
+}
+
+// Returns the column of the 4x4 matrix as a superposition, which is the image of the product
+// state labelled by the column.
+pub(crate) fn matrix4_column(matrix: &[[Complex64; 4]; 4], column: usize) -> SuperPosition {
+    SuperPosition::new_with_amplitudes_unchecked(&std::array::from_fn::<Complex64, 4, _>(|row| {
+        matrix[row][column]
+    }))
+}
+
+// Returns if the square matrix of the given dimension is unitary, by checking that its columns
+// are orthonormal.
+pub(crate) fn is_unitary(dim: usize, entry: impl Fn(usize, usize) -> Complex64) -> bool {
+    (0..dim).all(|i| {
+        (0..dim).all(|j| {
+            let inner_product: Complex64 = (0..dim).map(|k| entry(k, i).conj() * entry(k, j)).sum();
+            let expected: Complex64 = if i == j {
+                Complex64::ONE
+            } else {
+                Complex64::ZERO
+            };
+            (inner_product - expected).norm() <= UNITARY_MARGIN
+        })
+    })
+}
+
+// Returns the 4x4 matrix of a controlled single qubit gate, in the basis |control, target>, or
+// None if the gate cannot be controlled.
+fn controlled_matrix(gate: &Gate) -> Option<[[Complex64; 4]; 4]> {
+    if !gate.is_controllable() {
+        return None;
+    }
+    let single: Vec<Vec<Complex64>> = gate.matrix_from_images().ok()?;
+    Some(std::array::from_fn(|row| {
+        std::array::from_fn(|column| match (row < 2, column < 2) {
+            (true, true) if row == column => Complex64::ONE,
+            (false, false) => single[row - 2][column - 2],
+            _ => Complex64::ZERO,
+        })
+    }))
 }
 
 /// Bundles the gate and position together.
 #[derive(Debug)]
 pub(crate) struct GateInfo<'a> {
-    pub cat_gate: GateCategory<'a>,
+    pub cat_gate: &'a GateCategory,
     pub position: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::states::{ProductState, Qubit, SuperPosition};
+    use crate::{complex_re_array, Circuit, Gate, GateFamily, GateKind, ParamId};
+    use num_complex::Complex64;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    fn post_select(prod: ProductState) -> Option<SuperPosition> {
+        match prod.get_qubits()[0] {
+            Qubit::Zero => Some(SuperPosition::new_with_amplitudes_unchecked(
+                &complex_re_array!(2f64.sqrt(), 0f64),
+            )),
+            Qubit::One => Some(SuperPosition::new_with_amplitudes_unchecked(
+                &complex_re_array!(0f64, 0f64),
+            )),
+        }
+    }
+
+    #[test]
+    fn custom_single_post_selects() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit
+            .add_repeating_gate(Gate::H, &[0, 1, 2])
+            .unwrap()
+            .add_gate(Gate::custom_single(post_select, "P"), 1)
+            .unwrap();
+
+        let register: SuperPosition = circuit.simulate().take_state().take();
+        for (i, amp) in register.get_amplitudes().iter().enumerate() {
+            let expected: f64 = if i & 0b010 == 0 { 0.5f64 } else { 0f64 };
+            assert!((amp.re - expected).abs() < 1e-10 && amp.im.abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn hadamard_and_cnot_matrices() {
+        assert_eq!(
+            Gate::H.as_matrix().unwrap(),
+            vec![
+                complex_re_array!(FRAC_1_SQRT_2, FRAC_1_SQRT_2).to_vec(),
+                complex_re_array!(FRAC_1_SQRT_2, -FRAC_1_SQRT_2).to_vec()
+            ]
+        );
+        assert_eq!(
+            Gate::CNot(0).as_matrix().unwrap(),
+            vec![
+                complex_re_array!(1f64, 0f64, 0f64, 0f64).to_vec(),
+                complex_re_array!(0f64, 1f64, 0f64, 0f64).to_vec(),
+                complex_re_array!(0f64, 0f64, 0f64, 1f64).to_vec(),
+                complex_re_array!(0f64, 0f64, 1f64, 0f64).to_vec()
+            ]
+        );
+        assert_eq!(Gate::Toffoli(0, 1).as_matrix().unwrap().len(), 8);
+        assert_eq!(Gate::custom_single(post_select, "P").as_matrix(), None);
+    }
+
+    #[test]
+    fn classifies_diagonal_gates() {
+        for gate in [
+            Gate::Id,
+            Gate::Z,
+            Gate::S,
+            Gate::Sdag,
+            Gate::T,
+            Gate::Tdag,
+            Gate::Rz(0.1f64),
+            Gate::Phase(0.2f64),
+            Gate::Delay(1f64),
+            Gate::CZ(0),
+            Gate::CR(0.3f64, 0),
+            Gate::CPhase(0.3f64, 0),
+            Gate::CRk(2, 0),
+            Gate::Rzz(0.4f64, 0),
+        ] {
+            assert!(gate.is_diagonal(), "{:?} should be diagonal", gate);
+        }
+
+        for gate in [
+            Gate::H,
+            Gate::X,
+            Gate::Y,
+            Gate::Rx(0.1f64),
+            Gate::Ry(0.1f64),
+            Gate::U3(0.1f64, 0.2f64, 0.3f64),
+            Gate::X90,
+            Gate::Y90,
+            Gate::MX90,
+            Gate::MY90,
+            Gate::CNot(0),
+            Gate::CY(0),
+            Gate::Swap(0),
+            Gate::ISwap(0),
+            Gate::Rxx(0.4f64, 0),
+            Gate::Ryy(0.4f64, 0),
+            Gate::Toffoli(0, 1),
+            Gate::MCX(vec![0, 1, 2]),
+            Gate::parity_oracle(&ProductState::new_unchecked(&[Qubit::One])),
+        ] {
+            assert!(!gate.is_diagonal(), "{:?} should not be diagonal", gate);
+        }
+    }
+
+    #[test]
+    fn parity_oracle_computes_dot_product() {
+        let secret = ProductState::new_unchecked(&[Qubit::One, Qubit::Zero, Qubit::One]);
+        for (input, parity) in [
+            ([Qubit::Zero, Qubit::Zero, Qubit::Zero], Qubit::Zero),
+            ([Qubit::One, Qubit::Zero, Qubit::Zero], Qubit::One),
+            ([Qubit::One, Qubit::One, Qubit::Zero], Qubit::One),
+            ([Qubit::One, Qubit::Zero, Qubit::One], Qubit::Zero),
+            ([Qubit::Zero, Qubit::One, Qubit::One], Qubit::One),
+            ([Qubit::Zero, Qubit::One, Qubit::Zero], Qubit::Zero),
+        ] {
+            let register: SuperPosition =
+                ProductState::new_unchecked(&[input[0], input[1], input[2], Qubit::Zero]).into();
+            let mut circuit = Circuit::new(4).unwrap();
+            circuit
+                .add_gate(Gate::parity_oracle(&secret), 3)
+                .unwrap()
+                .change_register(register)
+                .unwrap();
+
+            let expected: SuperPosition =
+                ProductState::new_unchecked(&[input[0], input[1], input[2], parity]).into();
+            assert_eq!(circuit.simulate().take_state().take(), expected);
+        }
+    }
+
+    #[test]
+    fn parity_oracle_finds_secret() {
+        let secret = ProductState::new_unchecked(&[Qubit::Zero, Qubit::One, Qubit::One]);
+        let mut circuit = Circuit::new(4).unwrap();
+        circuit
+            .add_gate(Gate::X, 3)
+            .unwrap()
+            .add_repeating_gate(Gate::H, &[0, 1, 2, 3])
+            .unwrap()
+            .add_gate(Gate::parity_oracle(&secret), 3)
+            .unwrap()
+            .add_repeating_gate(Gate::H, &[0, 1, 2, 3])
+            .unwrap();
+
+        let expected: SuperPosition =
+            ProductState::new_unchecked(&[Qubit::Zero, Qubit::One, Qubit::One, Qubit::One]).into();
+        for (amp, expected_amp) in circuit
+            .simulate()
+            .take_state()
+            .take()
+            .get_amplitudes()
+            .iter()
+            .zip(expected.get_amplitudes())
+        {
+            assert!((amp - expected_amp).norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn commuting_gates() {
+        assert!(Gate::Z.commutes_with(&Gate::Z, 0, 0));
+        assert!(Gate::Z.commutes_with(&Gate::CZ(0), 1, 2));
+        assert!(Gate::X.commutes_with(&Gate::Rx(0.3f64), 1, 1));
+        assert!(Gate::H.commutes_with(&Gate::Y, 0, 1));
+        assert!(Gate::H.commutes_with(&Gate::H, 2, 2));
+        assert!(Gate::CNot(0).commutes_with(&Gate::T, 1, 0));
+        assert!(Gate::X.commutes_with(&Gate::CNot(0), 1, 1));
+        assert!(Gate::Phase(0.1f64).commutes_with(&Gate::H, 0, 0));
+    }
+
+    #[test]
+    fn non_commuting_gates() {
+        assert!(!Gate::X.commutes_with(&Gate::Z, 0, 0));
+        assert!(!Gate::H.commutes_with(&Gate::T, 1, 1));
+        assert!(!Gate::CNot(0).commutes_with(&Gate::Z, 1, 1));
+        assert!(!Gate::CNot(0).commutes_with(&Gate::X, 1, 0));
+        assert!(!Gate::CNot(0).commutes_with(&Gate::CNot(1), 1, 2));
+    }
+
+    #[test]
+    fn classifies_gate_families() {
+        assert_eq!(Gate::H.family(), GateFamily::Clifford);
+        assert_eq!(Gate::S.family(), GateFamily::Clifford);
+        assert_eq!(Gate::CNot(0).family(), GateFamily::Clifford);
+        assert_eq!(Gate::T.family(), GateFamily::NonClifford);
+        assert_eq!(Gate::Rx(0.5f64).family(), GateFamily::Rotation);
+        assert_eq!(Gate::X.family(), GateFamily::Pauli);
+        assert_eq!(Gate::CR(0.5f64, 0).family(), GateFamily::Controlled);
+        assert_eq!(
+            Gate::Param(ParamId(0), GateKind::Ry).family(),
+            GateFamily::Rotation
+        );
+    }
+
+    #[test]
+    fn cnot_from_matrix4() {
+        let cnot_matrix = [
+            complex_re_array!(1f64, 0f64, 0f64, 0f64),
+            complex_re_array!(0f64, 1f64, 0f64, 0f64),
+            complex_re_array!(0f64, 0f64, 0f64, 1f64),
+            complex_re_array!(0f64, 0f64, 1f64, 0f64),
+        ];
+        let cnot: Gate = Gate::from_matrix4(cnot_matrix, 0, String::from("CX")).unwrap();
+        assert_eq!(cnot.as_matrix(), Gate::CNot(0).as_matrix());
+        assert_eq!(cnot.get_nodes(), Some(vec![0]));
+
+        let mut matrix_circuit = Circuit::new(3).unwrap();
+        let mut cnot_circuit = Circuit::new(3).unwrap();
+        for circuit in [&mut matrix_circuit, &mut cnot_circuit] {
+            circuit
+                .add_repeating_gate(Gate::H, &[0, 1])
+                .unwrap()
+                .add_gate(Gate::T, 0)
+                .unwrap();
+        }
+        matrix_circuit.add_gate(cnot, 2).unwrap();
+        cnot_circuit.add_gate(Gate::CNot(0), 2).unwrap();
+        assert_eq!(
+            matrix_circuit.simulate().get_state().take().amplitudes,
+            cnot_circuit.simulate().get_state().take().amplitudes
+        );
+
+        let mut non_unitary = cnot_matrix;
+        non_unitary[0][0] = num_complex::c64(2f64, 0f64);
+        assert!(Gate::from_matrix4(non_unitary, 0, String::from("U")).is_err());
+    }
+
+    #[test]
+    fn displays_gates() {
+        assert_eq!(Gate::H.to_string(), "H");
+        assert_eq!(Gate::CNot(0).to_string(), "CNOT (control 0)");
+        assert_eq!(
+            Gate::Rx(std::f64::consts::FRAC_PI_2).to_string(),
+            "Rx(θ=1.571)"
+        );
+        assert_eq!(Gate::Toffoli(0, 2).to_string(), "Toffoli (controls 0, 2)");
+        assert_eq!(
+            Gate::custom_single(post_select, "P").to_string(),
+            "Custom(P)"
+        );
+        assert_eq!(
+            Gate::Param(ParamId(1), GateKind::CR(3)).to_string(),
+            "CR(θ=p1) (control 3)"
+        );
+        // The name used by the printer is unchanged.
+        assert_eq!(Gate::CNot(0).get_name(), "X");
+    }
+
+    #[test]
+    fn no_matrix_for_invalid_controlled_gates() {
+        for inner in [
+            Gate::MeasureReset(0),
+            Gate::CNot(2),
+            Gate::Param(ParamId(0), GateKind::Rz),
+        ] {
+            assert_eq!(Gate::Controlled(Box::new(inner), 0).as_matrix(), None);
+        }
+        assert!(Gate::MeasureReset(0).matrix_from_images().is_err());
+    }
+
+    #[test]
+    fn custom_gates_compared_by_nodes_and_name() {
+        let post = Gate::Custom(post_select, vec![1], String::from("P"));
+        assert_eq!(post, Gate::Custom(post_select, vec![1], String::from("P")));
+        assert_ne!(post, Gate::Custom(post_select, vec![2], String::from("P")));
+        assert_ne!(post, Gate::Custom(post_select, vec![1], String::from("Q")));
+        assert_ne!(Gate::CR(0.1f64, 0), Gate::CPhase(0.1f64, 0));
+        assert_eq!(Gate::Barrier, Gate::Barrier);
+    }
+
+    #[test]
+    fn iswap_inverse_is_adjoint() {
+        let iswap: Vec<Vec<Complex64>> = Gate::ISwap(0).as_matrix().unwrap();
+        let inverse: Vec<Vec<Complex64>> = Gate::ISwap(0).inverse().unwrap().as_matrix().unwrap();
+        for (i, row) in inverse.iter().enumerate() {
+            for (j, element) in row.iter().enumerate() {
+                assert_eq!(*element, iswap[j][i].conj());
+            }
+        }
+    }
+}
@@ -25,4 +25,60 @@ impl<T> Measurement<T> {
             Self::NonObservable(item) => item,
         }
     }
+
+    /// Returns the item if it is [Measurement::Observable], otherwise `None`.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap();
+    ///
+    /// let bin_count = circuit.simulate().measure_all(10).observable().unwrap();
+    /// assert_eq!(bin_count.len(), 1);
+    /// ```
+    pub fn observable(self) -> Option<T> {
+        match self {
+            Self::Observable(item) => Some(item),
+            Self::NonObservable(_) => None,
+        }
+    }
+
+    /// Returns the item if it is [Measurement::NonObservable], otherwise `None`.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap();
+    ///
+    /// let simulated = circuit.simulate();
+    /// let superpos = simulated.get_state().non_observable().unwrap();
+    /// assert_eq!(superpos.get_num_qubits(), 1);
+    /// ```
+    pub fn non_observable(self) -> Option<T> {
+        match self {
+            Self::Observable(_) => None,
+            Self::NonObservable(item) => Some(item),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Measurement;
+
+    #[test]
+    fn extracts_observable() {
+        assert_eq!(Measurement::Observable(1).observable(), Some(1));
+        assert_eq!(Measurement::NonObservable(1).observable(), None);
+    }
+
+    #[test]
+    fn extracts_non_observable() {
+        assert_eq!(Measurement::NonObservable(1).non_observable(), Some(1));
+        assert_eq!(Measurement::Observable(1).non_observable(), None);
+    }
 }
@@ -9,6 +9,8 @@
 */
 
 use super::{Circuit, Gate};
+use crate::error::print_warning;
+use crate::Float;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
@@ -18,10 +20,14 @@ use std::path::Path;
 /// The user has the option to print the string to the terminal or a text file, where the text file
 /// has the advantage of not wrapping the circuit within the terminal. The [Printer] will also
 /// cache a copy of the diagram so subsequent prints will require no building of the diagram.
-pub struct Printer<'a> {
-    circuit: &'a Circuit,
+pub struct Printer<'a, T: Float = f64> {
+    circuit: &'a Circuit<T>,
     diagram: Option<String>,
     disable_warnings: bool,
+    show_indices: bool,
+    show_angles: bool,
+    ascii: bool,
+    max_width: Option<usize>,
 }
 
 struct DiagramSchema<'a> {
@@ -44,19 +50,42 @@ struct GatePrinterInfo<'a> {
     gate: &'a Gate,
 }
 
+// The ASCII replacements of the box-drawing chars used in the diagram.
+const ASCII_CHARS: [(char, char); 15] = [
+    ('┏', '+'),
+    ('┓', '+'),
+    ('┗', '+'),
+    ('┛', '+'),
+    ('┷', '+'),
+    ('┯', '+'),
+    ('┼', '+'),
+    ('━', '-'),
+    ('─', '-'),
+    ('┨', '|'),
+    ('┠', '|'),
+    ('│', '|'),
+    ('█', '*'),
+    ('┆', ':'),
+    ('»', '>'),
+];
+
 #[derive(Debug)]
 struct Extrema {
     pub max: usize,
     pub min: usize,
 }
 
-impl Printer<'_> {
+impl<T: Float> Printer<'_, T> {
     /// Handle the printing of the given circuit.
-    pub fn new<'circ>(circuit: &'circ Circuit) -> Printer<'circ> {
+    pub fn new<'circ>(circuit: &'circ Circuit<T>) -> Printer<'circ, T> {
         Printer {
             circuit,
             diagram: None,
-            disable_warnings: false,
+            disable_warnings: circuit.suppress_warnings,
+            show_indices: false,
+            show_angles: false,
+            ascii: false,
+            max_width: None,
         }
     }
 
@@ -83,9 +112,9 @@ impl Printer<'_> {
     /// // ┗━━━┛
     /// ```
     pub fn print_diagram(&mut self) {
-        if self.circuit.circuit_gates.len() / self.circuit.num_qubits > 14 && !self.disable_warnings
-        {
-            eprintln!("\x1b[93m[Quantr Warning] The string displaying the circuit diagram exceeds 72 chars, which could cause the circuit to render incorrectly in terminals (due to the wrapping). Instead, consider saving the string to a .txt file by using Printer::save_diagram.\x1b[0m");
+        let number_of_columns: usize = self.circuit.circuit_gates.len() / self.circuit.num_qubits;
+        if number_of_columns.min(self.max_width.unwrap_or(usize::MAX)) > 14 {
+            print_warning("The string displaying the circuit diagram exceeds 72 chars, which could cause the circuit to render incorrectly in terminals (due to the wrapping). Instead, consider saving the string to a .txt file by using Printer::save_diagram, or splitting the diagram with Printer::set_max_width.", self.disable_warnings);
         }
         println!("{}", self.get_or_make_diagram());
     }
@@ -154,11 +183,259 @@ impl Printer<'_> {
         self.get_or_make_diagram()
     }
 
+    /// Returns the circuit diagram as a `quantikz` environment for LaTeX.
+    ///
+    /// Each column of the circuit is a column of the environment, where single gates are drawn
+    /// with `\gate{}`, control nodes with `\ctrl{}`, the targets of Not gates with `\targ{}`, and
+    /// bare wires with `\qw`. The environment requires `\usetikzlibrary{quantikz}` in the
+    /// preamble of the document.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, Printer};
+    ///
+    /// let mut qc: Circuit = Circuit::new(2).unwrap();
+    /// qc.add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// let mut printer: Printer = Printer::new(&qc);
+    /// println!("{}", printer.get_latex());
+    ///
+    /// // The above prints:
+    /// // \begin{quantikz}
+    /// // \lstick{$q_{0}$} & \ctrl{1} & \qw \\
+    /// // \lstick{$q_{1}$} & \targ{} & \qw
+    /// // \end{quantikz}
+    /// ```
+    pub fn get_latex(&mut self) -> String {
+        let num_qubits: usize = self.circuit.num_qubits;
+        let number_of_columns: usize = self.circuit.circuit_gates.len() / num_qubits;
+        let mut rows: Vec<Vec<String>> = (0..num_qubits)
+            .map(|row| vec![format!("\\lstick{{$q_{{{}}}$}}", row)])
+            .collect();
+
+        for column_num in 0..number_of_columns {
+            let column: &[Gate] = self.get_column_of_gates(column_num);
+            let multi_gate: Option<(usize, &Gate)> = column
+                .iter()
+                .enumerate()
+                .find(|(_, gate)| !gate.is_single_gate());
+
+            for (row, gate) in column.iter().enumerate() {
+                let cell: String = match multi_gate {
+                    Some((position, multi_gate)) if row == position => match multi_gate {
+                        Gate::CNot(_) | Gate::Toffoli(_, _) | Gate::MCX(_) => {
+                            String::from("\\targ{}")
+                        }
+                        Gate::Swap(_) => String::from("\\targX{}"),
+                        _ => format!("\\gate{{{}}}", Self::escape_latex(&multi_gate.get_name())),
+                    },
+                    Some((position, multi_gate)) => {
+                        let control_nodes: Vec<usize> = multi_gate
+                            .get_nodes()
+                            .expect("Single gate in drawing multi gate.");
+                        let offset: isize = position as isize - row as isize;
+                        if !control_nodes.contains(&row) {
+                            String::from("\\qw")
+                        } else if let Gate::Swap(_) = multi_gate {
+                            format!("\\swap{{{}}}", offset)
+                        } else {
+                            format!("\\ctrl{{{}}}", offset)
+                        }
+                    }
+                    None if *gate == Gate::Id => String::from("\\qw"),
+                    None => format!("\\gate{{{}}}", Self::escape_latex(&gate.get_name())),
+                };
+                rows[row].push(cell);
+            }
+        }
+
+        let body: String = rows
+            .iter_mut()
+            .map(|row| {
+                row.push(String::from("\\qw"));
+                row.join(" & ")
+            })
+            .collect::<Vec<String>>()
+            .join(" \\\\\n");
+        format!("\\begin{{quantikz}}\n{}\n\\end{{quantikz}}\n", body)
+    }
+
+    /// Saves the circuit diagram as a `quantikz` environment to a LaTeX file, see
+    /// [Printer::get_latex].
+    ///
+    /// If the file already exists, it will overwrite it.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, Printer};
+    ///
+    /// let mut qc: Circuit = Circuit::new(2).unwrap();
+    /// qc.add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// let mut printer: Printer = Printer::new(&qc);
+    /// // printer.save_latex("diagram.tex").unwrap();
+    /// // Saves in directory of Cargo package.
+    /// // (Commented so it doesn't create file during `cargo test`.)
+    /// ```
+    pub fn save_latex(&mut self, file_path: &str) -> std::io::Result<()> {
+        let path: &Path = Path::new(file_path);
+        let mut file = File::create(path)?;
+        file.write_all(self.get_latex().as_bytes())
+    }
+
+    // Escapes the characters of a gate name that are special in LaTeX. The backslash is escaped
+    // first, so that the braces of its replacement are not escaped again.
+    fn escape_latex(name: &str) -> String {
+        name.chars().fold(String::new(), |mut acc, c| {
+            match c {
+                '\\' => acc.push_str("\\textbackslash{}"),
+                '^' | '~' => {
+                    acc.push('\\');
+                    acc.push(c);
+                    acc.push_str("{}");
+                }
+                '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                    acc.push('\\');
+                    acc.push(c);
+                }
+                _ => acc.push(c),
+            }
+            acc
+        })
+    }
+
     /// Sets if the printer should display warnings.
     pub fn set_warnings(&mut self, printing: bool) {
         self.disable_warnings = printing;
     }
 
+    /// Sets if the printer should label each wire with its index, and each column with its
+    /// number along the top of the diagram.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, Printer};
+    ///
+    /// let mut qc: Circuit = Circuit::new(2).unwrap();
+    /// qc.add_gate(Gate::H, 0).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// let mut printer: Printer = Printer::new(&qc);
+    /// printer.show_indices(true);
+    /// printer.print_diagram();
+    ///
+    /// // The above prints:
+    /// //   0    1    
+    /// //   ┏━━━┓     
+    /// // 0 ┨ H ┠──█──
+    /// //   ┗━━━┛  │  
+    /// //          │  
+    /// //        ┏━┷━┓
+    /// // 1 ─────┨ X ┠
+    /// //        ┗━━━┛
+    /// ```
+    pub fn show_indices(&mut self, show: bool) {
+        if self.show_indices != show {
+            self.diagram = None;
+        }
+        self.show_indices = show;
+    }
+
+    /// Sets if the printer should label the gates that take a single angle, such as [Gate::Rz]
+    /// and [Gate::CPhase], with their angle to two decimal places.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, Printer};
+    ///
+    /// let mut qc: Circuit = Circuit::new(2).unwrap();
+    /// qc.add_gate(Gate::CPhase(0.5f64, 0), 1).unwrap();
+    ///
+    /// let mut printer: Printer = Printer::new(&qc);
+    /// printer.show_angles(true);
+    /// printer.print_diagram();
+    ///
+    /// // The above prints:
+    /// // ──█─────────
+    /// //   │         
+    /// //   │         
+    /// // ┏━┷━━━━━━━━┓
+    /// // ┨ CP(0.50) ┠
+    /// // ┗━━━━━━━━━━┛
+    /// ```
+    pub fn show_angles(&mut self, show: bool) {
+        if self.show_angles != show {
+            self.diagram = None;
+        }
+        self.show_angles = show;
+    }
+
+    /// Sets if the printer should draw the diagram with ASCII chars only, instead of UTF-8
+    /// box-drawing chars. This is useful for terminals and logs that do not render UTF-8.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, Printer};
+    ///
+    /// let mut qc: Circuit = Circuit::new(2).unwrap();
+    /// qc.add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// let mut printer: Printer = Printer::new(&qc);
+    /// printer.set_ascii(true);
+    /// printer.print_diagram();
+    ///
+    /// // The above prints:
+    /// // --*--
+    /// //   |  
+    /// //   |  
+    /// // +-+-+
+    /// // | X |
+    /// // +---+
+    /// ```
+    pub fn set_ascii(&mut self, ascii: bool) {
+        if self.ascii != ascii {
+            self.diagram = None;
+        }
+        self.ascii = ascii;
+    }
+
+    /// Sets the maximum number of columns of the circuit that are drawn side by side. Wider
+    /// circuits are split into chunks of at most `cols` columns, which are stacked vertically.
+    /// The wires of each chunk that is continued below are marked with `»`.
+    ///
+    /// If `cols` is zero, the diagram is not split.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, Printer};
+    ///
+    /// let mut qc: Circuit = Circuit::new(1).unwrap();
+    /// qc.add_gates(&[Gate::H]).unwrap()
+    ///     .add_gates(&[Gate::X]).unwrap()
+    ///     .add_gates(&[Gate::H]).unwrap();
+    ///
+    /// let mut printer: Printer = Printer::new(&qc);
+    /// printer.set_max_width(2);
+    /// printer.print_diagram();
+    ///
+    /// // The above prints:
+    /// // ┏━━━┓┏━━━┓  
+    /// // ┨ H ┠┨ X ┠ »
+    /// // ┗━━━┛┗━━━┛  
+    /// //
+    /// //
+    /// // ┏━━━┓
+    /// // ┨ H ┠
+    /// // ┗━━━┛
+    /// ```
+    pub fn set_max_width(&mut self, cols: usize) {
+        let max_width: Option<usize> = if cols == 0 { None } else { Some(cols) };
+        if self.max_width != max_width {
+            self.diagram = None;
+        }
+        self.max_width = max_width;
+    }
+
     // Constructs the diagram, or returns the diagram previously built.
     fn get_or_make_diagram(&mut self) -> String {
         match &self.diagram {
@@ -173,10 +450,16 @@ impl Printer<'_> {
         let mut printed_diagram: Vec<String> =
             vec!["".to_string(); 4 * self.circuit.num_qubits + 1];
 
+        let mut column_widths: Vec<usize> = Vec::with_capacity(number_of_columns);
         for column_num in 0..number_of_columns {
+            let previous_width: usize = printed_diagram[1].chars().count();
+
             // Get a column of gates with all names and length of names
             let (gate_info_column, longest_name_length): (Vec<GatePrinterInfo>, usize) =
-                Self::into_printer_gate_info(self.get_column_of_gates(column_num));
+                Self::into_printer_gate_info(
+                    self.get_column_of_gates(column_num),
+                    self.show_angles,
+                );
 
             let diagram_schematic = DiagramSchema {
                 longest_name_length,
@@ -197,6 +480,33 @@ impl Printer<'_> {
                 // Deals with single gates
                 Self::draw_single_gates(printed_diagram.as_mut_slice(), diagram_schematic);
             }
+
+            column_widths.push(printed_diagram[1].chars().count() - previous_width);
+        }
+
+        if let Some(max_width) = self.max_width.filter(|&cols| cols < number_of_columns) {
+            printed_diagram = Self::split_into_chunks(
+                &printed_diagram,
+                &column_widths,
+                max_width,
+                self.show_indices,
+            );
+        } else if self.show_indices {
+            Self::add_indices(&mut printed_diagram, &column_widths, 0);
+        }
+
+        if self.ascii {
+            for line in printed_diagram.iter_mut() {
+                *line = line
+                    .chars()
+                    .map(|c| {
+                        ASCII_CHARS
+                            .iter()
+                            .find(|(utf8, _)| *utf8 == c)
+                            .map_or(c, |(_, ascii)| *ascii)
+                    })
+                    .collect();
+            }
         }
 
         // Collect all the strings to return a single string giving the diagram
@@ -209,16 +519,100 @@ impl Printer<'_> {
         final_diagram
     }
 
+    // Splits the diagram into chunks of at most max_width columns, that are stacked vertically.
+    // The name line of each wire is marked with » if the chunk is continued below.
+    fn split_into_chunks(
+        printed_diagram: &[String],
+        column_widths: &[usize],
+        max_width: usize,
+        show_indices: bool,
+    ) -> Vec<String> {
+        let number_of_chunks: usize = column_widths.len().div_ceil(max_width);
+        let mut start: usize = 0;
+        let mut split_diagram: Vec<String> = Default::default();
+
+        for (chunk_num, chunk_widths) in column_widths.chunks(max_width).enumerate() {
+            let width: usize = chunk_widths.iter().sum();
+            let is_continued: bool = chunk_num + 1 < number_of_chunks;
+
+            let mut chunk: Vec<String> = printed_diagram
+                .iter()
+                .enumerate()
+                .map(|(row, line)| {
+                    let mut chunk_line: String = line.chars().skip(start).take(width).collect();
+                    if is_continued && row < printed_diagram.len() - 1 {
+                        chunk_line.push_str(if row % 4 == 1 { " »" } else { "  " });
+                    }
+                    chunk_line
+                })
+                .collect();
+
+            if show_indices {
+                Self::add_indices(&mut chunk, chunk_widths, chunk_num * max_width);
+            }
+            split_diagram.extend(chunk);
+            start += width;
+        }
+
+        split_diagram
+    }
+
+    // Prefixes the name line of each wire with its index, and adds a header of column numbers
+    // that are aligned with the start of each column.
+    fn add_indices(
+        printed_diagram: &mut Vec<String>,
+        column_widths: &[usize],
+        first_column: usize,
+    ) {
+        let num_wires: usize = (printed_diagram.len() - 1) / 4;
+        let label_width: usize = (num_wires - 1).to_string().len() + 1;
+
+        for (row, line) in printed_diagram.iter_mut().enumerate().take(4 * num_wires) {
+            let label: String = if row % 4 == 1 {
+                (row / 4).to_string()
+            } else {
+                String::from("")
+            };
+            line.insert_str(0, &format!("{:<1$}", label, label_width));
+        }
+
+        let header: String = column_widths
+            .iter()
+            .enumerate()
+            .fold(" ".repeat(label_width), |acc, (column_num, width)| {
+                acc + &format!("{:<1$}", first_column + column_num, width)
+            });
+        printed_diagram.insert(0, header);
+    }
+
     fn get_column_of_gates(&self, column_num: usize) -> &[Gate] {
         &self.circuit.circuit_gates
             [column_num * self.circuit.num_qubits..(column_num + 1) * self.circuit.num_qubits]
     }
 
-    fn into_printer_gate_info(gates_column: &[Gate]) -> (Vec<GatePrinterInfo>, usize) {
+    fn into_printer_gate_info(
+        gates_column: &[Gate],
+        show_angles: bool,
+    ) -> (Vec<GatePrinterInfo<'_>>, usize) {
         let mut gates_infos: Vec<GatePrinterInfo> = Default::default();
         let mut longest_name_length: usize = 1usize;
         for gate in gates_column.iter() {
-            let gate_name: String = gate.get_name();
+            let gate_name: String = match gate {
+                Gate::Rx(angle)
+                | Gate::Ry(angle)
+                | Gate::Rz(angle)
+                | Gate::Phase(angle)
+                | Gate::CR(angle, _)
+                | Gate::CPhase(angle, _)
+                | Gate::Rxx(angle, _)
+                | Gate::Ryy(angle, _)
+                | Gate::Rzz(angle, _)
+                    if show_angles =>
+                {
+                    format!("{}({:.2})", gate.get_name(), angle)
+                }
+                _ => gate.get_name(),
+            };
             let gate_name_length: usize = gate_name.len();
             if gate_name_length > longest_name_length {
                 longest_name_length = gate_name_length;
@@ -255,6 +649,16 @@ impl Printer<'_> {
                     bottom: " ".repeat(diagram_scheme.longest_name_length + 4),
                     connection: " ".repeat(diagram_scheme.longest_name_length + 4),
                 },
+                Gate::Barrier => {
+                    let left: usize = (diagram_scheme.longest_name_length + 3) / 2;
+                    let right: usize = diagram_scheme.longest_name_length + 3 - left;
+                    RowSchematic {
+                        top: " ".repeat(left) + "┆" + &" ".repeat(right),
+                        name: "─".repeat(left) + "┆" + &"─".repeat(right),
+                        bottom: " ".repeat(left) + "┆" + &" ".repeat(right),
+                        connection: " ".repeat(left) + "┆" + &" ".repeat(right),
+                    }
+                }
                 _ => RowSchematic {
                     top: "┏━".to_string()
                         + &"━".repeat(gate_info.gate_name_length)
@@ -388,7 +792,7 @@ mod tests {
     // the terminal, and then copy the output for the assert_eq! macro.
 
     fn example_cnot(prod: ProductState) -> Option<SuperPosition> {
-        let input_register: [Qubit; 2] = [prod.qubits[0], prod.qubits[1]];
+        let input_register: [Qubit; 2] = [prod.get_unchecked(0), prod.get_unchecked(1)];
         Some(SuperPosition::new_with_amplitudes(match input_register {
                 [Qubit::Zero, Qubit::Zero] => return None,
                 [Qubit::Zero, Qubit::One] => return None, 
@@ -438,4 +842,103 @@ mod tests {
 
         assert_eq!(circuit_printer.get_diagram(), "     ┏━━━┓               ┏━━━┓          ┏━━━┓     \n─────┨ H ┠───────────────┨ Y ┠──█───────┨ X ┠─────\n     ┗━━━┛               ┗━━━┛  │       ┗━┯━┛     \n                                │         │       \n          ┏━━━━━━━━━━━━━┓┏━━━┓┏━┷━┓       │  ┏━━━┓\n──────────┨ Custom CNot ┠┨ Y ┠┨ X ┠──█────┼──┨ X ┠\n          ┗━┯━━━━━━━━━━━┛┗━━━┛┗━┯━┛  │    │  ┗━┯━┛\n            │                   │    │    │    │  \n            │                   │    │    │    │  \n────────────┼───────────────────┼────┼────█────█──\n            │                   │    │            \n            │                   │    │            \n┏━━━┓┏━━━┓  │                   │  ┏━┷━┓          \n┨ H ┠┨ X ┠──█───────────────────█──┨ X ┠──────────\n┗━━━┛┗━━━┛                         ┗━━━┛          \n                                                  \n\n".to_string());
     }
+
+    #[test]
+    fn producing_string_circuit_with_indices() {
+        let mut quantum_circuit = Circuit::new(3).unwrap();
+        quantum_circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::Custom(example_cnot, vec!(0), "Long".to_string()), 2).unwrap()
+            .add_gate(Gate::X, 1).unwrap();
+
+        let mut circuit_printer: Printer = Printer::new(&quantum_circuit);
+        circuit_printer.show_indices(true);
+
+        circuit_printer.print_diagram();
+
+        assert_eq!(circuit_printer.get_diagram(), "  0    1       2    \n  ┏━━━┓             \n0 ┨ H ┠──█──────────\n  ┗━━━┛  │          \n         │          \n         │     ┏━━━┓\n1 ───────┼─────┨ X ┠\n         │     ┗━━━┛\n         │          \n       ┏━┷━━━━┓     \n2 ─────┨ Long ┠─────\n       ┗━━━━━━┛     \n                    \n\n".to_string());
+    }
+
+    #[test]
+    fn producing_string_circuit_with_angles() {
+        let mut quantum_circuit = Circuit::new(2).unwrap();
+        quantum_circuit.add_gate(Gate::CPhase(0.5f64, 0), 1).unwrap();
+
+        let mut circuit_printer: Printer = Printer::new(&quantum_circuit);
+        assert!(circuit_printer.get_diagram().contains("┨ CP ┠"));
+        circuit_printer.show_angles(true);
+
+        assert_eq!(circuit_printer.get_diagram(), "            \n──█─────────\n  │         \n  │         \n┏━┷━━━━━━━━┓\n┨ CP(0.50) ┠\n┗━━━━━━━━━━┛\n            \n\n".to_string());
+    }
+
+    #[test]
+    fn producing_latex_circuit() {
+        let mut quantum_circuit = Circuit::new(3).unwrap();
+        quantum_circuit.add_gate(Gate::H, 0).unwrap()
+            .add_gate(Gate::CNot(0), 2).unwrap()
+            .add_gate(Gate::Custom(example_cnot, vec!(2), "C_1".to_string()), 1).unwrap();
+
+        let mut circuit_printer: Printer = Printer::new(&quantum_circuit);
+
+        assert_eq!(circuit_printer.get_latex(), "\\begin{quantikz}\n\\lstick{$q_{0}$} & \\gate{H} & \\ctrl{2} & \\qw & \\qw \\\\\n\\lstick{$q_{1}$} & \\qw & \\qw & \\gate{C\\_1} & \\qw \\\\\n\\lstick{$q_{2}$} & \\qw & \\targ{} & \\ctrl{-1} & \\qw\n\\end{quantikz}\n".to_string());
+    }
+
+    #[test]
+    fn producing_ascii_circuit() {
+        let mut quantum_circuit = Circuit::new(2).unwrap();
+        quantum_circuit.add_gate(Gate::CNot(0), 1).unwrap();
+
+        let mut circuit_printer: Printer = Printer::new(&quantum_circuit);
+        circuit_printer.set_ascii(true);
+
+        circuit_printer.print_diagram();
+
+        assert_eq!(circuit_printer.get_diagram(), "     \n--*--\n  |  \n  |  \n+-+-+\n| X |\n+---+\n     \n\n".to_string());
+    }
+
+    #[test]
+    fn producing_controlled_gate_circuit() {
+        let mut quantum_circuit = Circuit::new(2).unwrap();
+        quantum_circuit.add_gate(Gate::Controlled(Box::new(Gate::T), 0), 1).unwrap();
+
+        let mut circuit_printer: Printer = Printer::new(&quantum_circuit);
+
+        assert_eq!(circuit_printer.get_diagram(), "     \n──█──\n  │  \n  │  \n┏━┷━┓\n┨ T ┠\n┗━━━┛\n     \n\n".to_string());
+    }
+
+    #[test]
+    fn producing_barrier_circuit() {
+        let mut quantum_circuit = Circuit::new(2).unwrap();
+        quantum_circuit.add_gate(Gate::H, 0).unwrap()
+            .add_barrier()
+            .add_gate(Gate::X, 1).unwrap();
+
+        let mut circuit_printer: Printer = Printer::new(&quantum_circuit);
+
+        assert_eq!(circuit_printer.get_diagram(), "┏━━━┓  ┆       \n┨ H ┠──┆───────\n┗━━━┛  ┆       \n       ┆       \n       ┆  ┏━━━┓\n───────┆──┨ X ┠\n       ┆  ┗━━━┛\n       ┆       \n\n".to_string());
+    }
+
+    #[test]
+    fn producing_string_circuit_split_into_chunks() {
+        let mut quantum_circuit = Circuit::new(1).unwrap();
+        for column_num in 0..20 {
+            quantum_circuit.add_gate(if column_num % 2 == 0 { Gate::H } else { Gate::X }, 0).unwrap();
+        }
+
+        let mut circuit_printer: Printer = Printer::new(&quantum_circuit);
+        circuit_printer.set_max_width(8);
+
+        circuit_printer.print_diagram();
+
+        assert_eq!(circuit_printer.get_diagram(), "┏━━━┓┏━━━┓┏━━━┓┏━━━┓┏━━━┓┏━━━┓┏━━━┓┏━━━┓  \n┨ H ┠┨ X ┠┨ H ┠┨ X ┠┨ H ┠┨ X ┠┨ H ┠┨ X ┠ »\n┗━━━┛┗━━━┛┗━━━┛┗━━━┛┗━━━┛┗━━━┛┗━━━┛┗━━━┛  \n                                          \n\n┏━━━┓┏━━━┓┏━━━┓┏━━━┓┏━━━┓┏━━━┓┏━━━┓┏━━━┓  \n┨ H ┠┨ X ┠┨ H ┠┨ X ┠┨ H ┠┨ X ┠┨ H ┠┨ X ┠ »\n┗━━━┛┗━━━┛┗━━━┛┗━━━┛┗━━━┛┗━━━┛┗━━━┛┗━━━┛  \n                                          \n\n┏━━━┓┏━━━┓┏━━━┓┏━━━┓\n┨ H ┠┨ X ┠┨ H ┠┨ X ┠\n┗━━━┛┗━━━┛┗━━━┛┗━━━┛\n                    \n\n".to_string());
+    }
+
+    #[test]
+    fn producing_latex_with_escaped_gate_name() {
+        let mut quantum_circuit = Circuit::new(2).unwrap();
+        quantum_circuit.add_gate(Gate::Custom(example_cnot, vec!(0), "a\\b^c~{d}".to_string()), 1).unwrap();
+
+        let mut circuit_printer: Printer = Printer::new(&quantum_circuit);
+
+        assert_eq!(circuit_printer.get_latex(), "\\begin{quantikz}\n\\lstick{$q_{0}$} & \\ctrl{1} & \\qw \\\\\n\\lstick{$q_{1}$} & \\gate{a\\textbackslash{}b\\^{}c\\~{}\\{d\\}} & \\qw\n\\end{quantikz}\n".to_string());
+    }
 }
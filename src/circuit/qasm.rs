@@ -0,0 +1,425 @@
+/*
+* Copyright (c) 2024 Andrew Rowan Barlow. Licensed under the EUPL-1.2
+* or later. You may obtain a copy of the licence at
+* https://joinup.ec.europa.eu/collection/eupl/eupl-text-eupl-12. A copy
+* of the EUPL-1.2 licence in English is given in LICENCE.txt which is
+* found in the root directory of this repository.
+*
+* Author: Andrew Rowan Barlow <a.barlow.dev@gmail.com>
+*/
+
+//! Conversion of circuits to and from OpenQASM 2.0.
+
+use super::{Circuit, Gate, QResult};
+use crate::error::QuantrError;
+use crate::Float;
+use std::f64::consts::PI;
+
+impl<T: Float> Circuit<T> {
+    /// Returns the circuit as an OpenQASM 2.0 program, using the gates of `qelib1.inc`.
+    ///
+    /// The gates are emitted column by column, from the first wire to the last. [Gate::Id],
+    /// [Gate::Delay] and [Gate::Phase] are not emitted, as they only change the timing or global
+    /// phase of the circuit. An error is returned if the circuit contains a gate that has no
+    /// equivalent in OpenQASM 2.0, such as [Gate::Custom], [Gate::ISwap], a [Gate::MCX] with more
+    /// than two control nodes, a [Gate::Controlled] that is not of the H or Pauli gates, or an
+    /// unbound [Gate::Param].
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// assert_eq!(
+    ///     circuit.to_qasm().unwrap(),
+    ///     "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[2];\nh q[0];\ncx q[0],q[1];\n"
+    /// );
+    /// ```
+    pub fn to_qasm(&self) -> QResult<String> {
+        let mut program: String = format!(
+            "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[{}];\n",
+            self.num_qubits
+        );
+
+        for column in self.circuit_gates.chunks(self.num_qubits) {
+            for (pos, gate) in column.iter().enumerate() {
+                if let Some(statement) = Self::qasm_statement(gate, pos)? {
+                    program.push_str(&statement);
+                    program.push('\n');
+                }
+            }
+        }
+
+        Ok(program)
+    }
+
+    // Returns the OpenQASM 2.0 statement of a gate at the given position, or None if the gate is
+    // not emitted.
+    fn qasm_statement(gate: &Gate, pos: usize) -> QResult<Option<String>> {
+        let statement: String = match gate {
+            Gate::Id | Gate::Delay(_) | Gate::Phase(_) => return Ok(None),
+            Gate::Barrier => format!("barrier q[{}];", pos),
+            Gate::H => format!("h q[{}];", pos),
+            Gate::X => format!("x q[{}];", pos),
+            Gate::Y => format!("y q[{}];", pos),
+            Gate::Z => format!("z q[{}];", pos),
+            Gate::S => format!("s q[{}];", pos),
+            Gate::Sdag => format!("sdg q[{}];", pos),
+            Gate::T => format!("t q[{}];", pos),
+            Gate::Tdag => format!("tdg q[{}];", pos),
+            Gate::Rx(angle) => format!("rx({}) q[{}];", angle, pos),
+            Gate::Ry(angle) => format!("ry({}) q[{}];", angle, pos),
+            Gate::Rz(angle) => format!("rz({}) q[{}];", angle, pos),
+            // These match the mappings of standard_gate_ops exactly.
+            Gate::X90 => format!("rx(pi) q[{}];", pos),
+            Gate::Y90 => format!("ry(-pi) q[{}];", pos),
+            Gate::MX90 => format!("rx(-pi) q[{}];", pos),
+            Gate::MY90 => format!("ry(pi) q[{}];", pos),
+            Gate::U3(theta, phi, lambda) => {
+                format!("u3({},{},{}) q[{}];", theta, phi, lambda, pos)
+            }
+            Gate::CR(angle, c) | Gate::CPhase(angle, c) => {
+                format!("cu1({}) q[{}],q[{}];", angle, c, pos)
+            }
+            Gate::CRk(k, c) => format!("cu1({}) q[{}],q[{}];", 2f64 * PI / 2f64.powi(*k), c, pos),
+            Gate::CZ(c) => format!("cz q[{}],q[{}];", c, pos),
+            Gate::CY(c) => format!("cy q[{}],q[{}];", c, pos),
+            Gate::CNot(c) => format!("cx q[{}],q[{}];", c, pos),
+            Gate::Swap(c) => format!("swap q[{}],q[{}];", c, pos),
+            // The rzz of qelib1.inc matches Rzz up to a global phase.
+            Gate::Rzz(angle, c) => format!("rzz({}) q[{}],q[{}];", angle, c, pos),
+            Gate::Toffoli(c1, c2) => format!("ccx q[{}],q[{}],q[{}];", c1, c2, pos),
+            Gate::MCX(controls) if controls.len() == 1 => {
+                format!("cx q[{}],q[{}];", controls[0], pos)
+            }
+            Gate::MCX(controls) if controls.len() == 2 => {
+                format!("ccx q[{}],q[{}],q[{}];", controls[0], controls[1], pos)
+            }
+            Gate::Controlled(inner, c) if **inner == Gate::X => format!("cx q[{}],q[{}];", c, pos),
+            Gate::Controlled(inner, c) if **inner == Gate::Y => format!("cy q[{}],q[{}];", c, pos),
+            Gate::Controlled(inner, c) if **inner == Gate::Z => format!("cz q[{}],q[{}];", c, pos),
+            Gate::Controlled(inner, c) if **inner == Gate::H => format!("ch q[{}],q[{}];", c, pos),
+            Gate::ISwap(_)
+            | Gate::Rxx(_, _)
+            | Gate::Ryy(_, _)
+            | Gate::Margolus(_, _)
+            | Gate::MCX(_)
+            | Gate::Custom(_, _, _)
+            | Gate::CustomArg(_, _, _, _)
+            | Gate::Matrix4(_, _, _)
+            | Gate::Controlled(_, _)
+            | Gate::MeasureReset(_)
+            | Gate::Param(_, _) => {
+                return Err(QuantrError::Other {
+                    message: format!(
+                        "The gate, {:?}, at position {}, has no equivalent in OpenQASM 2.0.",
+                        gate, pos
+                    ),
+                })
+            }
+        };
+        Ok(Some(statement))
+    }
+}
+
+impl Circuit {
+    /// Parses an OpenQASM 2.0 program into a circuit.
+    ///
+    /// The number of qubits is given by the single `qreg` declaration. The supported gates are
+    /// `id`, `h`, `x`, `y`, `z`, `s`, `sdg`, `t`, `tdg`, `rx`, `ry`, `rz`, `u3`, `cx`, `cy`, `cz`,
+    /// `cu1`, `swap`, `ccx` and `barrier`, where angles can be numbers or simple expressions of
+    /// `pi`, such as `-3*pi/4`. Single gates are packed into the last column of the circuit with
+    /// [Circuit::try_pack_gate], except for a [Gate::Barrier], which is only packed into a column
+    /// of barriers. The `OPENQASM`, `include` and `creg` statements are skipped, and an error is
+    /// returned for any other unsupported statement.
+    ///
+    /// The `cu1` gate is read as a [Gate::CPhase], and so a [Gate::CR] or [Gate::CRk] that is
+    /// written by [Circuit::to_qasm] is read back as the [Gate::CPhase] with the same matrix.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let circuit = Circuit::from_qasm(
+    ///     "OPENQASM 2.0;
+    ///     include \"qelib1.inc\";
+    ///     qreg q[2];
+    ///     h q[0];
+    ///     cx q[0],q[1];",
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(circuit.get_gates(), &[Gate::H, Gate::Id, Gate::Id, Gate::CNot(0)]);
+    /// ```
+    pub fn from_qasm(src: &str) -> QResult<Circuit> {
+        let program: String = src
+            .lines()
+            .map(|line| line.split("//").next().unwrap_or_default())
+            .collect::<Vec<&str>>()
+            .join("\n");
+
+        let mut circuit: Option<(Circuit, &str)> = None;
+        for statement in program.split(';').map(str::trim) {
+            if statement.is_empty()
+                || statement.starts_with("OPENQASM")
+                || statement.starts_with("include")
+                || statement.starts_with("creg")
+            {
+                continue;
+            }
+
+            if let Some(declaration) = statement.strip_prefix("qreg") {
+                if circuit.is_some() {
+                    return Err(QuantrError::Other {
+                        message: String::from("Only a single qreg declaration is supported."),
+                    });
+                }
+                let (name, size) = Self::parse_qasm_operand(declaration.trim())?;
+                circuit = Some((Circuit::new(size)?, name));
+                continue;
+            }
+
+            let Some((circuit, register)) = circuit.as_mut() else {
+                return Err(QuantrError::Other {
+                    message: format!(
+                        "The statement, {}, appears before the qreg declaration.",
+                        statement
+                    ),
+                });
+            };
+            let (gate, pos) = Self::parse_qasm_gate(statement, register)?;
+            let ends_with_barriers: bool = circuit
+                .circuit_gates
+                .rchunks(circuit.num_qubits)
+                .next()
+                .is_some_and(|column| {
+                    column
+                        .iter()
+                        .all(|gate| matches!(gate, Gate::Barrier | Gate::Id))
+                });
+            if gate == Gate::Barrier && !ends_with_barriers {
+                circuit.add_gate(gate, pos)?;
+            } else {
+                circuit.try_pack_gate(gate, pos)?;
+            }
+        }
+
+        circuit
+            .map(|(circuit, _)| circuit)
+            .ok_or(QuantrError::Other {
+                message: String::from("The program does not declare a qreg."),
+            })
+    }
+
+    // Parses a gate statement, returning the gate and the position of its target.
+    fn parse_qasm_gate(statement: &str, register: &str) -> QResult<(Gate, usize)> {
+        let (head, operands) = match statement.find(')') {
+            Some(close) => statement.split_at(close + 1),
+            None => statement
+                .split_once(char::is_whitespace)
+                .unwrap_or((statement, "")),
+        };
+        let (name, angles) = match head.split_once('(') {
+            Some((name, args)) => (
+                name.trim(),
+                args.trim_end_matches(')')
+                    .split(',')
+                    .map(Self::parse_qasm_angle)
+                    .collect::<QResult<Vec<f64>>>()?,
+            ),
+            None => (head.trim(), Vec::new()),
+        };
+        let qubits: Vec<usize> = operands
+            .split(',')
+            .map(|operand| match Self::parse_qasm_operand(operand.trim()) {
+                Ok((name, index)) if name == register => Ok(index),
+                _ => Err(QuantrError::Other {
+                    message: format!(
+                        "The operand, {}, of the statement, {}, is not a qubit of the register {}.",
+                        operand.trim(),
+                        statement,
+                        register
+                    ),
+                }),
+            })
+            .collect::<QResult<Vec<usize>>>()?;
+
+        Ok(match (name, angles.as_slice(), qubits.as_slice()) {
+            ("id", [], [t]) => (Gate::Id, *t),
+            ("h", [], [t]) => (Gate::H, *t),
+            ("x", [], [t]) => (Gate::X, *t),
+            ("y", [], [t]) => (Gate::Y, *t),
+            ("z", [], [t]) => (Gate::Z, *t),
+            ("s", [], [t]) => (Gate::S, *t),
+            ("sdg", [], [t]) => (Gate::Sdag, *t),
+            ("t", [], [t]) => (Gate::T, *t),
+            ("tdg", [], [t]) => (Gate::Tdag, *t),
+            ("rx", [angle], [t]) => (Gate::Rx(*angle), *t),
+            ("ry", [angle], [t]) => (Gate::Ry(*angle), *t),
+            ("rz", [angle], [t]) => (Gate::Rz(*angle), *t),
+            ("u3", [theta, phi, lambda], [t]) => (Gate::U3(*theta, *phi, *lambda), *t),
+            ("cx", [], [c, t]) => (Gate::CNot(*c), *t),
+            ("cy", [], [c, t]) => (Gate::CY(*c), *t),
+            ("cz", [], [c, t]) => (Gate::CZ(*c), *t),
+            ("cu1", [angle], [c, t]) => (Gate::CPhase(*angle, *c), *t),
+            ("swap", [], [c, t]) => (Gate::Swap(*c), *t),
+            ("rzz", [angle], [c, t]) => (Gate::Rzz(*angle, *c), *t),
+            ("ccx", [], [c1, c2, t]) => (Gate::Toffoli(*c1, *c2), *t),
+            ("barrier", [], [t]) => (Gate::Barrier, *t),
+            _ => {
+                return Err(QuantrError::Other {
+                    message: format!("The statement, {}, is not supported.", statement),
+                })
+            }
+        })
+    }
+
+    // Parses an operand such as q[2], returning the name of the register and the index.
+    fn parse_qasm_operand(operand: &str) -> QResult<(&str, usize)> {
+        operand
+            .strip_suffix(']')
+            .and_then(|operand| operand.split_once('['))
+            .and_then(|(name, index)| Some((name.trim(), index.trim().parse::<usize>().ok()?)))
+            .ok_or(QuantrError::Other {
+                message: format!("The operand, {}, could not be parsed.", operand),
+            })
+    }
+
+    // Parses an angle that is a product of numbers and pi, with an optional sign and divisor,
+    // such as -3*pi/4.
+    fn parse_qasm_angle(expression: &str) -> QResult<f64> {
+        let error = || QuantrError::Other {
+            message: format!("The angle, {}, could not be parsed.", expression.trim()),
+        };
+        let parse_product = |product: &str| -> QResult<f64> {
+            product.split('*').try_fold(1f64, |acc, factor| {
+                match factor.trim() {
+                    "pi" => Ok(PI),
+                    number => number.parse::<f64>().map_err(|_| error()),
+                }
+                .map(|value| acc * value)
+            })
+        };
+
+        let (sign, expression_abs) = match expression.trim().strip_prefix('-') {
+            Some(expression_abs) => (-1f64, expression_abs),
+            None => (1f64, expression.trim()),
+        };
+        match expression_abs.split_once('/') {
+            Some((numerator, denominator)) => {
+                Ok(sign * parse_product(numerator)? / parse_product(denominator)?)
+            }
+            None => Ok(sign * parse_product(expression_abs)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::states::{ProductState, SuperPosition};
+    use crate::{Circuit, Gate};
+    use std::f64::consts::PI;
+
+    fn identity(prod: ProductState) -> Option<SuperPosition> {
+        Some(SuperPosition::from(prod))
+    }
+
+    #[test]
+    fn converts_circuit_to_qasm() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit
+            .add_gates(&[Gate::H, Gate::Id, Gate::Rz(0.5f64)])
+            .unwrap()
+            .add_gate(Gate::CNot(0), 1)
+            .unwrap()
+            .add_gate(Gate::Toffoli(0, 1), 2)
+            .unwrap();
+
+        assert_eq!(
+            circuit.to_qasm().unwrap(),
+            "OPENQASM 2.0;\n\
+            include \"qelib1.inc\";\n\
+            qreg q[3];\n\
+            h q[0];\n\
+            rz(0.5) q[2];\n\
+            cx q[0],q[1];\n\
+            ccx q[0],q[1],q[2];\n"
+        );
+    }
+
+    #[test]
+    fn catches_custom_gate_in_qasm() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit
+            .add_gate(Gate::Custom(identity, vec![0], String::from("I")), 1)
+            .unwrap();
+
+        assert!(circuit.to_qasm().is_err());
+    }
+
+    #[test]
+    fn parses_qasm_snippet() {
+        let circuit = Circuit::from_qasm(
+            "qreg q[3];
+            h q[0];
+            cx q[0],q[2];
+            rz(-pi/2) q[1];",
+        )
+        .unwrap();
+
+        let mut expected_circuit = Circuit::new(3).unwrap();
+        expected_circuit
+            .add_gate(Gate::H, 0)
+            .unwrap()
+            .add_gate(Gate::CNot(0), 2)
+            .unwrap()
+            .add_gate(Gate::Rz(-PI / 2f64), 1)
+            .unwrap();
+
+        assert_eq!(circuit.get_gates(), expected_circuit.get_gates());
+    }
+
+    #[test]
+    fn round_trips_circuit_through_qasm() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit
+            .add_gates(&[Gate::H, Gate::T, Gate::U3(0.1f64, 0.2f64, 0.3f64)])
+            .unwrap()
+            .add_gate(Gate::Toffoli(2, 0), 1)
+            .unwrap()
+            .add_gate(Gate::CPhase(0.5f64, 1), 0)
+            .unwrap();
+
+        let parsed_circuit = Circuit::from_qasm(&circuit.to_qasm().unwrap()).unwrap();
+        assert_eq!(parsed_circuit.get_gates(), circuit.get_gates());
+    }
+
+    #[test]
+    fn catches_unsupported_qasm_statements() {
+        assert!(Circuit::from_qasm("qreg q[1]; measure q[0] -> c[0];").is_err());
+        assert!(Circuit::from_qasm("qreg q[1]; h r[0];").is_err());
+        assert!(Circuit::from_qasm("h q[0]; qreg q[1];").is_err());
+        assert!(Circuit::from_qasm("qreg q[1]; rx(half) q[0];").is_err());
+        assert!(Circuit::from_qasm("OPENQASM 2.0;").is_err());
+    }
+
+    #[test]
+    fn round_trips_barrier_and_cphase_through_qasm() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit
+            .add_gate(Gate::H, 1)
+            .unwrap()
+            .add_barrier()
+            .add_gate(Gate::CPhase(0.5f64, 0), 1)
+            .unwrap();
+
+        let qasm: String = circuit.to_qasm().unwrap();
+        assert!(qasm.contains("barrier q[0];\nbarrier q[1];\ncu1(0.5) q[0],q[1];"));
+
+        let parsed_circuit = Circuit::from_qasm(&qasm).unwrap();
+        assert_eq!(parsed_circuit.get_gates(), circuit.get_gates());
+    }
+}
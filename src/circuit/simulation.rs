@@ -8,121 +8,547 @@
 * Author: Andrew Rowan Barlow <a.barlow.dev@gmail.com>
 */
 
-use super::gate::GateCategory;
+use super::gate::{matrix4_column, GateCategory};
 use super::GateInfo;
-use crate::states::{ProductState, SuperPosition};
-use crate::{Circuit, Gate};
+use super::QResult;
+use crate::complex::to_precision;
+use crate::error::QuantrError;
+use crate::states::{ProductState, Qubit, SuperPosition};
+use crate::{Circuit, Float, Gate};
 use core::iter::zip;
+use fastrand::Rng;
 use num_complex::Complex;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::ops::{Add, Mul};
+use std::sync::OnceLock;
 
-impl Circuit {
-    pub(super) fn simulate_with_register(&self, register: &mut SuperPosition) {
-        let mut qubit_counter: usize = 0;
-        let number_gates: usize = self.circuit_gates.len();
+// The size of the register from which gates are applied in parallel.
+#[cfg(feature = "rayon")]
+const PARALLEL_THRESHOLD: usize = 1 << 10;
+
+// The mapped and untouched states of a chunk of the register, when applying a gate in parallel.
+#[cfg(feature = "rayon")]
+type PartialStates<T> = (HashMap<usize, Complex<T>>, HashMap<usize, Complex<T>>);
+
+impl<T: Float> Circuit<T> {
+    // Returns the outcomes of the Gate::MeasureReset gates, in the order that they were applied.
+    pub(super) fn simulate_with_register(
+        &self,
+        register: &mut SuperPosition<T>,
+    ) -> QResult<Vec<(usize, Qubit)>> {
+        if Self::simulate_classical_gates(&self.circuit_gates, self.num_qubits, register) {
+            if self.config_progress {
+                println!("Simulated classical circuit on a product state.");
+            }
+            return Ok(Vec::new());
+        }
+
+        Self::simulate_gates(
+            &self.circuit_gates,
+            Circuit::link_gates(&self.categorised_gates, &self.circuit_gates),
+            self.num_qubits,
+            self.config_progress,
+            &mut self.rng(),
+            register,
+        )
+    }
 
-        // This will removed in next major update, as the circuit will directly store this. Instead
-        // of what's happening now, in which the gates are being copied into another wapper.
-        let mut categorised_gates: Vec<GateCategory> = Vec::with_capacity(number_gates);
-        for gate in &self.circuit_gates {
-            categorised_gates.push(Gate::linker(gate));
+    // Applies the gates to the register. This is separate from Circuit::simulate_with_register so
+    // that SimulatedCircuit::measure_all_without_cache can resimulate its gates on the same
+    // register, without rebuilding a circuit for each shot. The generator decides the outcomes of
+    // the Gate::MeasureReset gates.
+    pub(crate) fn simulate_gates(
+        circuit_gates: &[Gate],
+        categorised_gates: &[GateCategory],
+        num_qubits: usize,
+        config_progress: bool,
+        rng: &mut Rng,
+        register: &mut SuperPosition<T>,
+    ) -> QResult<Vec<(usize, Qubit)>> {
+        if let Some(Gate::Param(id, _)) = circuit_gates
+            .iter()
+            .find(|gate| matches!(gate, Gate::Param(_, _)))
+        {
+            return Err(QuantrError::Other {
+                message: format!("The parameter, {:?}, has not been bound to a value. See Circuit::bind_parameters.", id),
+            });
         }
+        circuit_gates.iter().try_for_each(Gate::check_controlled)?;
 
-        if self.config_progress {
+        let mut qubit_counter: usize = 0;
+        let number_gates: usize = circuit_gates.len();
+        let mut measurements: Vec<(usize, Qubit)> = Vec::new();
+
+        if config_progress {
             println!("Starting circuit simulation...");
         }
+        #[cfg(feature = "indicatif")]
+        let progress_bar: indicatif::ProgressBar =
+            Circuit::new_progress_bar(config_progress, number_gates);
 
         // Loop through each gate of circuit from starting at top row to bottom, then moving onto the next.
-        for (cat_gate, gate) in zip(categorised_gates, &self.circuit_gates) {
-            if cat_gate == GateCategory::Identity {
+        for (cat_gate, gate) in zip(categorised_gates, circuit_gates) {
+            if matches!(cat_gate, GateCategory::Identity) {
                 qubit_counter += 1;
                 continue;
             }
 
-            let gate_pos: usize = qubit_counter % self.num_qubits;
+            let gate_pos: usize = qubit_counter % num_qubits;
 
-            if self.config_progress {
-                Self::print_circuit_log(gate, &gate_pos, &qubit_counter, &number_gates);
+            #[cfg(not(feature = "indicatif"))]
+            if config_progress {
+                Circuit::print_circuit_log(gate, &gate_pos, &qubit_counter, &number_gates);
+            }
+            #[cfg(feature = "indicatif")]
+            if config_progress {
+                progress_bar.set_message(format!("{:?} on wire {}", gate, gate_pos));
+                progress_bar.set_position(qubit_counter as u64 + 1);
+            }
+
+            if let GateCategory::MeasureReset(bit) = cat_gate {
+                let outcome: Qubit =
+                    Self::measure_and_reset(gate_pos, num_qubits, register, rng.f64());
+                measurements.push((*bit, outcome));
+                qubit_counter += 1;
+                continue;
             }
 
             let gate_to_apply: GateInfo = GateInfo {
                 cat_gate,
                 position: gate_pos,
             };
-            Circuit::apply_gate(gate_to_apply, register);
+            Circuit::apply_gate(gate_to_apply, register)?;
 
             qubit_counter += 1;
         }
+
+        #[cfg(feature = "indicatif")]
+        if config_progress {
+            progress_bar.finish();
+            println!("Finished circuit simulation.");
+        }
+        Ok(measurements)
+    }
+
+    // Measures the qubit on the given wire, where the roll is uniformly sampled from [0, 1). The
+    // register collapses onto the observed qubit and is renormalised, after which the qubit is
+    // reset to |0> by moving the amplitudes of |1> onto |0>.
+    fn measure_and_reset(
+        position: usize,
+        num_qubits: usize,
+        register: &mut SuperPosition<T>,
+        roll: f64,
+    ) -> Qubit {
+        let bit: usize = 1 << (num_qubits - 1 - position);
+        let total: f64 = register
+            .amplitudes
+            .iter()
+            .map(|amp| amp.norm_sqr().into_f64())
+            .sum();
+        let prob_one: f64 = register
+            .amplitudes
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| index & bit != 0)
+            .map(|(_, amp)| amp.norm_sqr().into_f64())
+            .sum();
+
+        let (outcome, prob_outcome): (Qubit, f64) = if roll * total < prob_one {
+            (Qubit::One, prob_one)
+        } else {
+            (Qubit::Zero, total - prob_one)
+        };
+        let scale: T = T::from_f64(prob_outcome.sqrt().recip());
+
+        for index in (0..register.amplitudes.len()).filter(|index| index & bit == 0) {
+            let kept: Complex<T> = match outcome {
+                Qubit::Zero => register.amplitudes[index],
+                Qubit::One => register.amplitudes[index | bit],
+            };
+            register.amplitudes[index] = kept * scale;
+            register.amplitudes[index | bit] = Complex::ZERO;
+        }
+
+        outcome
     }
 
-    // The main algorithm and impetus for this project.
+    // A fast path for circuits that only contain gates that permute the product states, such as
+    // the X, CNot, Toffoli and Swap gates, acting on a register that is a single product state.
+    // The resulting product state is found by flipping and swapping the bits of its index,
+    // keeping the amplitude. Returns false, without changing the register, if the circuit or the
+    // register is not of this form.
+    pub(super) fn simulate_classical_gates(
+        circuit_gates: &[Gate],
+        num_qubits: usize,
+        register: &mut SuperPosition<T>,
+    ) -> bool {
+        let is_classical: bool = circuit_gates.iter().all(|gate| {
+            matches!(
+                gate,
+                Gate::Id
+                    | Gate::Delay(_)
+                    | Gate::Barrier
+                    | Gate::X
+                    | Gate::CNot(_)
+                    | Gate::Toffoli(_, _)
+                    | Gate::MCX(_)
+                    | Gate::Swap(_)
+            )
+        });
+        if !is_classical {
+            return false;
+        }
+
+        let mut non_zero_states = register
+            .amplitudes
+            .iter()
+            .enumerate()
+            .filter(|(_, amp)| **amp != Complex::ZERO);
+        let (mut index, amplitude): (usize, Complex<T>) =
+            match (non_zero_states.next(), non_zero_states.next()) {
+                (Some((index, amp)), None) => (index, *amp),
+                _ => return false,
+            };
+
+        let bit = |qubit: usize| 1usize << (num_qubits - 1 - qubit);
+        for (gate_num, gate) in circuit_gates.iter().enumerate() {
+            let position: usize = gate_num % num_qubits;
+            let is_flipped: bool = match gate {
+                Gate::X => true,
+                Gate::CNot(c) => index & bit(*c) != 0,
+                Gate::Toffoli(c1, c2) => index & bit(*c1) != 0 && index & bit(*c2) != 0,
+                Gate::MCX(controls) => controls.iter().all(|c| index & bit(*c) != 0),
+                Gate::Swap(c) => (index & bit(*c) == 0) != (index & bit(position) == 0),
+                _ => false,
+            };
+            if is_flipped {
+                index ^= bit(position);
+                if let Gate::Swap(c) = gate {
+                    index ^= bit(*c);
+                }
+            }
+        }
+
+        register.amplitudes.fill(Complex::ZERO);
+        register.amplitudes[index] = amplitude;
+        true
+    }
+
+    // Custom gates are applied by mapping each product state of the register, as their mappings
+    // are only known on the product states. All other gates are applied directly to the
+    // amplitudes of the register.
     //
-    // This takes linear mappings defined on how they act on the basis of their product space, to
-    // then apply on an arbitrary register. This algorithm is used instead of matrices, or sparse
-    // matrices, in an effort to reduce memory. Cannot guarantee if this method is the fastest.
-    pub(super) fn apply_gate(gate: GateInfo, register: &mut SuperPosition) {
+    // With the `rayon` feature, gates on large registers are applied across multiple threads.
+    pub(super) fn apply_gate(gate: GateInfo, register: &mut SuperPosition<T>) -> QResult<()> {
+        if !matches!(
+            gate.cat_gate,
+            GateCategory::Custom(_, _) | GateCategory::CustomArg(_, _, _)
+        ) {
+            Self::apply_dense_gate(&gate, register);
+            return Ok(());
+        }
+
+        #[cfg(feature = "rayon")]
+        if register.amplitudes.len() >= PARALLEL_THRESHOLD {
+            return Self::apply_gate_in_parallel(&gate, register);
+        }
+
+        Self::apply_gate_serially(&gate, register)
+    }
+
+    // Applies the gate by index arithmetic on the amplitudes of the register. The matrix of the
+    // gate on the qubits that it acts on is found from the images of their product states, which
+    // is then applied to each group of amplitudes that only differ on these qubits.
+    pub(super) fn apply_dense_gate(gate: &GateInfo, register: &mut SuperPosition<T>) {
+        let qubit = |bit: usize| match bit & 1 {
+            0 => Qubit::Zero,
+            _ => Qubit::One,
+        };
+
+        // The acting positions are ordered so that the first is the most significant bit of the
+        // product states of the gate.
+        let (acting_positions, images): (Vec<usize>, Vec<SuperPosition>) = match *gate.cat_gate {
+            GateCategory::Identity
+            | GateCategory::Custom(_, _)
+            | GateCategory::CustomArg(_, _, _)
+            | GateCategory::MeasureReset(_) => return,
+            GateCategory::Single(func) => (
+                vec![gate.position],
+                (0..2).map(|col| func(qubit(col))).collect(),
+            ),
+            GateCategory::SingleArg(arg, func) => (
+                vec![gate.position],
+                (0..2).map(|col| func(qubit(col), arg)).collect(),
+            ),
+            GateCategory::SingleArgThree(theta, phi, lambda, func) => (
+                vec![gate.position],
+                (0..2)
+                    .map(|col| func(qubit(col), theta, phi, lambda))
+                    .collect(),
+            ),
+            GateCategory::Double(c, func) => (
+                vec![c, gate.position],
+                (0..4)
+                    .map(|col| func(qubit(col >> 1), qubit(col)))
+                    .collect(),
+            ),
+            GateCategory::DoubleArg(arg, c, func) => (
+                vec![c, gate.position],
+                (0..4)
+                    .map(|col| func(qubit(col >> 1), qubit(col), arg))
+                    .collect(),
+            ),
+            GateCategory::DoubleArgInt(arg_int, c, func) => (
+                vec![c, gate.position],
+                (0..4)
+                    .map(|col| func(qubit(col >> 1), qubit(col), arg_int))
+                    .collect(),
+            ),
+            GateCategory::Matrix4(ref matrix, partner) => (
+                vec![partner, gate.position],
+                (0..4).map(|col| matrix4_column(matrix, col)).collect(),
+            ),
+            GateCategory::Triple(c1, c2, func) => (
+                vec![c1, c2, gate.position],
+                (0..8)
+                    .map(|col| func(qubit(col >> 2), qubit(col >> 1), qubit(col)))
+                    .collect(),
+            ),
+        };
+
+        let num_qubits: usize = register.product_dim;
+        let num_acting: usize = acting_positions.len();
+        let masks: Vec<usize> = acting_positions
+            .iter()
+            .map(|position| 1 << (num_qubits - 1 - position))
+            .collect();
+        let acting_mask: usize = masks.iter().fold(0, |acc, mask| acc | mask);
+
+        // The offset of each product state of the gate, from the index where all acting qubits
+        // are zero.
+        let offsets: Vec<usize> = (0..1 << num_acting)
+            .map(|local| {
+                masks
+                    .iter()
+                    .enumerate()
+                    .filter(|(k, _)| (local >> (num_acting - 1 - k)) & 1 == 1)
+                    .fold(0, |acc, (_, mask)| acc | mask)
+            })
+            .collect();
+
+        // Each amplitude is found from the amplitudes of its group in the previous register, where
+        // the row of the gate is given by the acting qubits of the amplitude.
+        #[cfg(feature = "rayon")]
+        if register.amplitudes.len() >= PARALLEL_THRESHOLD {
+            let previous: Vec<Complex<T>> = register.amplitudes.clone();
+            register
+                .amplitudes
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(index, amp)| {
+                    let base: usize = index & !acting_mask;
+                    let row: usize = masks
+                        .iter()
+                        .fold(0, |acc, mask| (acc << 1) | usize::from(index & mask != 0));
+                    *amp = zip(&images, &offsets)
+                        .map(|(image, offset)| {
+                            to_precision::<f64, T>(image.amplitudes[row]) * previous[base + offset]
+                        })
+                        .sum();
+                });
+            return;
+        }
+
+        let mut group: Vec<Complex<T>> = vec![Complex::ZERO; offsets.len()];
+        for base in 0..register.amplitudes.len() {
+            if base & acting_mask != 0 {
+                continue;
+            }
+            for (amp, offset) in zip(group.iter_mut(), &offsets) {
+                *amp = register.amplitudes[base + offset];
+            }
+            for (row, offset) in offsets.iter().enumerate() {
+                register.amplitudes[base + offset] = zip(&images, &group)
+                    .map(|(image, amp)| to_precision::<f64, T>(image.amplitudes[row]) * amp)
+                    .sum();
+            }
+        }
+    }
+
+    pub(super) fn apply_gate_serially(
+        gate: &GateInfo,
+        register: &mut SuperPosition<T>,
+    ) -> QResult<()> {
         // the sum of states that are required to be added to the register
-        let mut mapped_states: HashMap<ProductState, Complex<f64>> = Default::default();
-        let mut untouched_states: HashMap<ProductState, Complex<f64>> = Default::default();
+        let mut mapped_states: HashMap<usize, Complex<T>> = Default::default();
+        let mut untouched_states: HashMap<usize, Complex<T>> = Default::default();
 
         for (prod_state, amp) in register.into_iter() {
             //Looping through super position of register
+            Self::map_product_state(
+                gate,
+                prod_state,
+                amp,
+                &mut mapped_states,
+                &mut untouched_states,
+            )?;
+        }
 
-            // Obtain superposition from applying gate from a specified wire onto the product state, and add control nodes if necersary
-            let mut acting_positions: Vec<usize> = Vec::<usize>::with_capacity(3);
+        Self::set_mapped_states(register, mapped_states, untouched_states);
+        Ok(())
+    }
 
-            let wrapped_super_pos: Option<SuperPosition> = match gate.cat_gate {
-                GateCategory::Identity => None,
-                GateCategory::Single(func) => Some(func(prod_state.get_qubits()[gate.position])),
-                GateCategory::SingleArg(arg, func) => {
-                    Some(func(prod_state.get_qubits()[gate.position], arg))
-                }
-                GateCategory::Double(c, func) => {
-                    acting_positions.push(c);
-                    let qubits = prod_state.get_qubits();
-                    Some(func(qubits[c], qubits[gate.position]))
-                }
-                GateCategory::DoubleArg(arg, c, func) => {
-                    acting_positions.push(c);
-                    let qubits = prod_state.get_qubits();
-                    Some(func(qubits[c], qubits[gate.position], arg))
-                }
-                GateCategory::DoubleArgInt(arg_int, c, func) => {
-                    acting_positions.push(c);
-                    let qubits = prod_state.get_qubits();
-                    Some(func(qubits[c], qubits[gate.position], arg_int))
-                }
-                GateCategory::Triple(c1, c2, func) => {
-                    acting_positions.push(c2);
-                    acting_positions.push(c1);
-                    let qubits = prod_state.get_qubits();
-                    Some(func(qubits[c1], qubits[c2], qubits[gate.position]))
-                }
-                GateCategory::Custom(func, controls) => {
-                    acting_positions.extend(controls.iter().rev());
-                    Self::custom_gate_on_wires(func, controls, gate.position, &prod_state)
+    // Maps the product states of the register across the rayon thread pool, where each thread
+    // folds its product states into its own HashMaps. These are then merged.
+    #[cfg(feature = "rayon")]
+    pub(super) fn apply_gate_in_parallel(
+        gate: &GateInfo,
+        register: &mut SuperPosition<T>,
+    ) -> QResult<()> {
+        let product_dim: usize = register.product_dim;
+        let (mapped_states, untouched_states): PartialStates<T> = register
+            .amplitudes
+            .par_iter()
+            .enumerate()
+            .try_fold(PartialStates::<T>::default, |mut partial, (index, amp)| {
+                Self::map_product_state(
+                    gate,
+                    ProductState::binary_basis(index, product_dim),
+                    *amp,
+                    &mut partial.0,
+                    &mut partial.1,
+                )?;
+                Ok(partial)
+            })
+            .try_reduce(PartialStates::<T>::default, |mut merged, partial| {
+                for (state, amp) in partial.0 {
+                    merged
+                        .0
+                        .entry(state)
+                        .and_modify(|existing_amp| {
+                            *existing_amp = existing_amp.add(amp);
+                        })
+                        .or_insert(amp);
                 }
-            };
+                merged.1.extend(partial.1);
+                Ok(merged)
+            })?;
+
+        Self::set_mapped_states(register, mapped_states, untouched_states);
+        Ok(())
+    }
+
+    // Adds the image of the gate acting on a single product state to the mapped states, or if
+    // the gate does not act on the product state, records it as untouched. The states are keyed
+    // by their index in the computational basis, rather than the product state, to reduce the
+    // size of the keys.
+    fn map_product_state(
+        gate: &GateInfo,
+        prod_state: ProductState,
+        amp: Complex<T>,
+        mapped_states: &mut HashMap<usize, Complex<T>>,
+        untouched_states: &mut HashMap<usize, Complex<T>>,
+    ) -> QResult<()> {
+        // Obtain superposition from applying gate from a specified wire onto the product state, and add control nodes if necersary
+        let mut acting_positions: Vec<usize> = Vec::<usize>::with_capacity(3);
 
-            if let Some(super_pos) = wrapped_super_pos {
-                if !acting_positions.is_empty() {
-                    acting_positions.reverse()
-                };
-                acting_positions.push(gate.position);
-                Self::insert_gate_image_into_product_state(
-                    super_pos,
-                    acting_positions,
-                    prod_state,
-                    amp,
-                    &mut mapped_states,
-                );
-            } else {
-                untouched_states.insert(prod_state, amp);
+        let wrapped_super_pos: Option<SuperPosition> = match *gate.cat_gate {
+            GateCategory::Identity | GateCategory::MeasureReset(_) => None,
+            GateCategory::Single(func) => Some(func(prod_state.get_unchecked(gate.position))),
+            GateCategory::SingleArg(arg, func) => {
+                Some(func(prod_state.get_unchecked(gate.position), arg))
+            }
+            GateCategory::SingleArgThree(theta, phi, lambda, func) => Some(func(
+                prod_state.get_unchecked(gate.position),
+                theta,
+                phi,
+                lambda,
+            )),
+            GateCategory::Double(c, func) => {
+                acting_positions.push(c);
+                Some(func(
+                    prod_state.get_unchecked(c),
+                    prod_state.get_unchecked(gate.position),
+                ))
+            }
+            GateCategory::DoubleArg(arg, c, func) => {
+                acting_positions.push(c);
+                Some(func(
+                    prod_state.get_unchecked(c),
+                    prod_state.get_unchecked(gate.position),
+                    arg,
+                ))
+            }
+            GateCategory::DoubleArgInt(arg_int, c, func) => {
+                acting_positions.push(c);
+                Some(func(
+                    prod_state.get_unchecked(c),
+                    prod_state.get_unchecked(gate.position),
+                    arg_int,
+                ))
+            }
+            GateCategory::Triple(c1, c2, func) => {
+                acting_positions.push(c2);
+                acting_positions.push(c1);
+                Some(func(
+                    prod_state.get_unchecked(c1),
+                    prod_state.get_unchecked(c2),
+                    prod_state.get_unchecked(gate.position),
+                ))
             }
+            GateCategory::Matrix4(ref matrix, partner) => {
+                acting_positions.push(partner);
+                let column: usize = ((prod_state.get_unchecked(partner) as usize) << 1)
+                    | prod_state.get_unchecked(gate.position) as usize;
+                Some(matrix4_column(matrix, column))
+            }
+            GateCategory::Custom(func, ref controls) => {
+                acting_positions.extend(controls.iter().rev());
+                func(Circuit::custom_gate_input(
+                    controls,
+                    gate.position,
+                    &prod_state,
+                ))
+            }
+            GateCategory::CustomArg(func, arg, ref controls) => {
+                acting_positions.extend(controls.iter().rev());
+                func(
+                    Circuit::custom_gate_input(controls, gate.position, &prod_state),
+                    arg,
+                )
+            }
+        };
+
+        if let Some(super_pos) = wrapped_super_pos {
+            if !acting_positions.is_empty() {
+                acting_positions.reverse()
+            };
+            acting_positions.push(gate.position);
+            if super_pos.product_dim != acting_positions.len() {
+                return Err(QuantrError::Other { message: format!("The gate on wire {} mapped the product state, |{}>, to a superposition with product dimension {}, when the gate acts on {} qubits. The mapping of a custom gate must return a superposition with the same number of qubits as its control nodes and position.", gate.position, prod_state, super_pos.product_dim, acting_positions.len()) });
+            }
+            Self::insert_gate_image_into_product_state(
+                super_pos,
+                acting_positions,
+                prod_state,
+                amp,
+                mapped_states,
+            );
+        } else {
+            untouched_states.insert(prod_state.comp_basis(), amp);
         }
 
-        // All states in register considers, and can create new super position
+        Ok(())
+    }
+
+    // All states in register considers, and can create new super position
+    fn set_mapped_states(
+        register: &mut SuperPosition<T>,
+        mut mapped_states: HashMap<usize, Complex<T>>,
+        untouched_states: HashMap<usize, Complex<T>>,
+    ) {
         for (k, v) in untouched_states {
             mapped_states
                 .entry(k)
@@ -131,36 +557,15 @@ impl Circuit {
                 })
                 .or_insert(v);
         }
-        register.set_amplitudes_from_states_unchecked(mapped_states);
-    }
-
-    fn custom_gate_on_wires(
-        operator: fn(ProductState) -> Option<SuperPosition>,
-        controls: &[usize],
-        position: usize,
-        prod_state: &ProductState,
-    ) -> Option<SuperPosition> {
-        if !controls.is_empty() {
-            let mut concat_prodstate: ProductState = prod_state.get_unchecked(controls[0]).into();
-
-            for c in &controls[1..] {
-                //converts product to larger product
-                concat_prodstate = concat_prodstate.kronecker_prod(prod_state.get_unchecked(*c));
-            }
-            concat_prodstate = concat_prodstate.kronecker_prod(prod_state.get_unchecked(position));
-
-            operator(concat_prodstate)
-        } else {
-            operator(ProductState::from(prod_state.qubits[position]))
-        }
+        register.set_amplitudes_from_indices_unchecked(mapped_states);
     }
 
     fn insert_gate_image_into_product_state(
         gate_image: SuperPosition,
         gate_positions: Vec<usize>,
         prod_state: ProductState,
-        amp: Complex<f64>,
-        mapped_states: &mut HashMap<ProductState, Complex<f64>>,
+        amp: Complex<T>,
+        mapped_states: &mut HashMap<usize, Complex<T>>,
     ) {
         // TODO think if looping through mapped_states, but with RAYON, would improve performance
         // Pehaps if gate_image reached a critical mass, such as a wall of hadarmards, it would be
@@ -168,18 +573,98 @@ impl Circuit {
         for (state, state_amp) in gate_image.into_iter() {
             // Insert these image states back into a product space
             let mut swapped_state: ProductState = prod_state.clone();
-            swapped_state.insert_qubits(state.qubits.as_slice(), gate_positions.as_slice());
+            swapped_state.insert_qubits(&state.get_qubits(), gate_positions.as_slice());
 
             mapped_states
-                .entry(swapped_state)
+                .entry(swapped_state.comp_basis())
                 .and_modify(|existing_amp| {
-                    *existing_amp = existing_amp.add(state_amp.mul(amp));
+                    *existing_amp = existing_amp.add(to_precision::<f64, T>(state_amp).mul(amp));
                 })
-                .or_insert(state_amp.mul(amp));
+                .or_insert(to_precision::<f64, T>(state_amp).mul(amp));
+        }
+    }
+}
+
+// The functions that do not depend on the precision of the register.
+impl Circuit {
+    // Applies a gate to the amplitudes of the product states in a subspace, keyed by their index
+    // in the computational basis. Each product state is mapped by the column of the matrix of the
+    // gate that it labels, on the qubits that the gate acts on. The gate must not be a custom
+    // gate or an unbound parameter, so that its matrix is known.
+    pub(super) fn apply_gate_in_subspace(
+        gate: &Gate,
+        position: usize,
+        num_qubits: usize,
+        amplitudes: &HashMap<usize, Complex<f64>>,
+    ) -> HashMap<usize, Complex<f64>> {
+        let matrix: Vec<Vec<Complex<f64>>> = gate
+            .as_matrix()
+            .expect("Gates in a subspace simulation have a known matrix.");
+        let mut acting_positions: Vec<usize> = gate.get_nodes().unwrap_or_default();
+        acting_positions.push(position);
+
+        // The first acting position is the most significant bit of the product states of the gate.
+        let masks: Vec<usize> = acting_positions
+            .iter()
+            .map(|position| 1 << (num_qubits - 1 - position))
+            .collect();
+        let acting_mask: usize = masks.iter().fold(0, |acc, mask| acc | mask);
+        let to_global = |base: usize, local: usize| {
+            masks
+                .iter()
+                .enumerate()
+                .filter(|(k, _)| (local >> (masks.len() - 1 - k)) & 1 == 1)
+                .fold(base, |acc, (_, mask)| acc | mask)
+        };
+
+        let mut mapped: HashMap<usize, Complex<f64>> = HashMap::with_capacity(amplitudes.len());
+        for (&index, &amp) in amplitudes {
+            let column: usize = masks
+                .iter()
+                .fold(0, |acc, mask| (acc << 1) | usize::from(index & mask != 0));
+            for (row, entries) in matrix.iter().enumerate() {
+                if entries[column] != Complex::ZERO {
+                    *mapped
+                        .entry(to_global(index & !acting_mask, row))
+                        .or_insert(Complex::ZERO) += entries[column] * amp;
+                }
+            }
+        }
+        mapped
+    }
+
+    // Links each gate to its category, which is cached so that repeated simulations of the same
+    // circuit, such as in SimulatedCircuit::measure_all_without_cache, skip relinking the gates.
+    // The cache is cleared whenever gates are added to the circuit.
+    pub(crate) fn link_gates<'a>(
+        categorised_gates: &'a OnceLock<Vec<GateCategory>>,
+        circuit_gates: &[Gate],
+    ) -> &'a [GateCategory] {
+        categorised_gates.get_or_init(|| circuit_gates.iter().map(Gate::linker).collect())
+    }
+
+    // Returns the product state of the control nodes followed by the position, which is the
+    // input of the custom gate.
+    fn custom_gate_input(
+        controls: &[usize],
+        position: usize,
+        prod_state: &ProductState,
+    ) -> ProductState {
+        if !controls.is_empty() {
+            let mut concat_prodstate: ProductState = prod_state.get_unchecked(controls[0]).into();
+
+            for c in &controls[1..] {
+                //converts product to larger product
+                concat_prodstate = concat_prodstate.kronecker_prod(prod_state.get_unchecked(*c));
+            }
+            concat_prodstate.kronecker_prod(prod_state.get_unchecked(position))
+        } else {
+            ProductState::from(prod_state.get_unchecked(position))
         }
     }
 
     // If the user toggles the log on, then prints the simulation of each circuit.
+    #[cfg(not(feature = "indicatif"))]
     pub(super) fn print_circuit_log(
         gate: &Gate,
         gate_pos: &usize,
@@ -198,4 +683,20 @@ impl Circuit {
             println!("Finished circuit simulation.")
         }
     }
+
+    // The progress bar over the number of gates, which is hidden unless the user toggles the log
+    // on.
+    #[cfg(feature = "indicatif")]
+    fn new_progress_bar(config_progress: bool, number_gates: usize) -> indicatif::ProgressBar {
+        if !config_progress {
+            return indicatif::ProgressBar::hidden();
+        }
+        let progress_bar = indicatif::ProgressBar::new(number_gates as u64);
+        if let Ok(style) =
+            indicatif::ProgressStyle::with_template("[{bar:40}] {pos}/{len} gates {msg}")
+        {
+            progress_bar.set_style(style.progress_chars("#>-"));
+        }
+        progress_bar
+    }
 }
@@ -13,7 +13,7 @@
 //! These linear functions are defined by how they act on product states of qubits. Defining the
 //! mappings on a basis defines how the gates act on larger product spaces.
 
-use crate::states::{Qubit, SuperPosition};
+use crate::states::{ProductState, Qubit, SuperPosition};
 use crate::{complex_im, complex_im_array, complex_re, complex_re_array};
 use num_complex::{c64, Complex64};
 use std::f64::consts::FRAC_1_SQRT_2;
@@ -41,6 +41,19 @@ pub fn hadamard(register: Qubit) -> SuperPosition {
     })
 }
 
+#[rustfmt::skip]
+pub fn u3(register: Qubit, theta: f64, phi: f64, lambda: f64) -> SuperPosition {
+    let cos_half: f64 = (0.5f64.mul(theta)).cos();
+    let sin_half: f64 = (0.5f64.mul(theta)).sin();
+    let zero_map: [Complex64; 2] = [complex_re!(cos_half), c64(0f64, phi).exp().mul(sin_half)];
+    let one_map: [Complex64; 2] = [-c64(0f64, lambda).exp().mul(sin_half), c64(0f64, phi + lambda).exp().mul(cos_half)];
+
+    SuperPosition::new_with_register_unchecked::<2>(match register {
+        Qubit::Zero => zero_map,
+        Qubit::One => one_map,
+    })
+}
+
 #[rustfmt::skip]
 pub fn rx(register: Qubit, angle: f64) -> SuperPosition {
     let real_parts: Complex64 = complex_re!((0.5f64.mul(angle)).cos());
@@ -225,6 +238,16 @@ pub fn swap(qubit_one: Qubit, qubit_two: Qubit) -> SuperPosition {
     })
 }
 
+#[rustfmt::skip]
+pub fn iswap(qubit_one: Qubit, qubit_two: Qubit) -> SuperPosition {
+    SuperPosition::new_with_register_unchecked::<4>(match [qubit_one, qubit_two] {
+        [Qubit::Zero, Qubit::Zero] => complex_re_array!(1f64, 0f64, 0f64, 0f64),
+        [Qubit::Zero, Qubit::One]  => complex_im_array!(0f64, 0f64, 1f64, 0f64),
+        [Qubit::One, Qubit::Zero]  => complex_im_array!(0f64, 1f64, 0f64, 0f64),
+        [Qubit::One, Qubit::One]   => complex_re_array!(0f64, 0f64, 0f64, 1f64),
+    })
+}
+
 #[rustfmt::skip]
 pub fn cr(qubit_one: Qubit, qubit_two: Qubit, angle: f64) -> SuperPosition {
     let exp_array: [Complex64; 4] = [num_complex::Complex64::ZERO, num_complex::Complex64::ZERO, num_complex::Complex64::ZERO, (c64(0f64, angle)).exp()];
@@ -252,6 +275,46 @@ pub fn crk(qubit_one: Qubit, qubit_two: Qubit, k: i32) -> SuperPosition {
 // Triple gates
 //
 
+#[rustfmt::skip]
+pub fn rxx(qubit_one: Qubit, qubit_two: Qubit, angle: f64) -> SuperPosition {
+    let cos_part: Complex64 = complex_re!((0.5f64.mul(angle)).cos());
+    let sin_part: Complex64 = complex_im!(-(0.5f64.mul(angle)).sin());
+    let zero: Complex64 = num_complex::Complex64::ZERO;
+    SuperPosition::new_with_register_unchecked::<4>(match [qubit_one, qubit_two] {
+        [Qubit::Zero, Qubit::Zero] => [cos_part, zero, zero, sin_part],
+        [Qubit::Zero, Qubit::One]  => [zero, cos_part, sin_part, zero],
+        [Qubit::One, Qubit::Zero]  => [zero, sin_part, cos_part, zero],
+        [Qubit::One, Qubit::One]   => [sin_part, zero, zero, cos_part],
+    })
+}
+
+#[rustfmt::skip]
+pub fn ryy(qubit_one: Qubit, qubit_two: Qubit, angle: f64) -> SuperPosition {
+    let cos_part: Complex64 = complex_re!((0.5f64.mul(angle)).cos());
+    let sin_part_pos: Complex64 = complex_im!((0.5f64.mul(angle)).sin());
+    let sin_part_neg: Complex64 = complex_im!(-(0.5f64.mul(angle)).sin());
+    let zero: Complex64 = num_complex::Complex64::ZERO;
+    SuperPosition::new_with_register_unchecked::<4>(match [qubit_one, qubit_two] {
+        [Qubit::Zero, Qubit::Zero] => [cos_part, zero, zero, sin_part_pos],
+        [Qubit::Zero, Qubit::One]  => [zero, cos_part, sin_part_neg, zero],
+        [Qubit::One, Qubit::Zero]  => [zero, sin_part_neg, cos_part, zero],
+        [Qubit::One, Qubit::One]   => [sin_part_pos, zero, zero, cos_part],
+    })
+}
+
+#[rustfmt::skip]
+pub fn rzz(qubit_one: Qubit, qubit_two: Qubit, angle: f64) -> SuperPosition {
+    let neg_exp: Complex64 = (c64(0f64, -angle*0.5f64)).exp();
+    let pos_exp: Complex64 = (c64(0f64, angle*0.5f64)).exp();
+    let zero: Complex64 = num_complex::Complex64::ZERO;
+    SuperPosition::new_with_register_unchecked::<4>(match [qubit_one, qubit_two] {
+        [Qubit::Zero, Qubit::Zero] => [neg_exp, zero, zero, zero],
+        [Qubit::Zero, Qubit::One]  => [zero, pos_exp, zero, zero],
+        [Qubit::One, Qubit::Zero]  => [zero, zero, pos_exp, zero],
+        [Qubit::One, Qubit::One]   => [zero, zero, zero, neg_exp],
+    })
+}
+
 #[rustfmt::skip]
 pub fn toffoli(qubit_one: Qubit, qubit_two: Qubit, qubit_three: Qubit) -> SuperPosition {
     SuperPosition::new_with_register_unchecked::<8>(match [qubit_one, qubit_two, qubit_three] {
@@ -265,3 +328,30 @@ pub fn toffoli(qubit_one: Qubit, qubit_two: Qubit, qubit_three: Qubit) -> SuperP
         [Qubit::One, Qubit::One, Qubit::One] => {    complex_re_array!(0f64, 0f64, 0f64, 0f64, 0f64, 0f64, 1f64, 0f64) }
     })
 }
+
+// The Toffoli mapping, with a phase of -1 on |101>.
+#[rustfmt::skip]
+pub fn margolus(qubit_one: Qubit, qubit_two: Qubit, qubit_three: Qubit) -> SuperPosition {
+    SuperPosition::new_with_register_unchecked::<8>(match [qubit_one, qubit_two, qubit_three] {
+        [Qubit::Zero, Qubit::Zero, Qubit::Zero] => { complex_re_array!(1f64, 0f64, 0f64, 0f64, 0f64, 0f64, 0f64, 0f64) }
+        [Qubit::Zero, Qubit::Zero, Qubit::One] => {  complex_re_array!(0f64, 1f64, 0f64, 0f64, 0f64, 0f64, 0f64, 0f64) }
+        [Qubit::Zero, Qubit::One, Qubit::Zero] => {  complex_re_array!(0f64, 0f64, 1f64, 0f64, 0f64, 0f64, 0f64, 0f64) }
+        [Qubit::Zero, Qubit::One, Qubit::One] => {   complex_re_array!(0f64, 0f64, 0f64, 1f64, 0f64, 0f64, 0f64, 0f64) }
+        [Qubit::One, Qubit::Zero, Qubit::Zero] => {  complex_re_array!(0f64, 0f64, 0f64, 0f64, 1f64, 0f64, 0f64, 0f64) }
+        [Qubit::One, Qubit::Zero, Qubit::One] => {   complex_re_array!(0f64, 0f64, 0f64, 0f64, 0f64, -1f64, 0f64, 0f64) }
+        [Qubit::One, Qubit::One, Qubit::Zero] => {   complex_re_array!(0f64, 0f64, 0f64, 0f64, 0f64, 0f64, 0f64, 1f64) }
+        [Qubit::One, Qubit::One, Qubit::One] => {    complex_re_array!(0f64, 0f64, 0f64, 0f64, 0f64, 0f64, 1f64, 0f64) }
+    })
+}
+
+// Flips the last qubit if all other qubits, the control nodes, are |1>.
+pub fn mcx(mut prod_state: ProductState) -> Option<SuperPosition> {
+    let qubits: Vec<Qubit> = prod_state.get_qubits();
+    let (_, controls) = qubits.split_last()?;
+    if !controls.iter().all(|&qubit| qubit == Qubit::One) {
+        return None;
+    }
+    let target: usize = controls.len();
+    prod_state.invert_digit(target).ok()?;
+    Some(prod_state.into())
+}
@@ -30,6 +30,7 @@ mod product_states_iter;
 mod qubit;
 mod super_position_iter;
 mod super_positions;
+mod super_positions_density;
 mod super_positions_unchecked;
 
 pub use product_states::ProductState;
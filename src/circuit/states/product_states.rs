@@ -15,11 +15,16 @@ use crate::error::QuantrError;
 use crate::states::Qubit;
 
 /// A product state in the computational basis.
+///
+/// The state is stored as its index in the computational basis, and so a product state can be
+/// formed from at most [usize::BITS] qubits.
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProductState {
-    /// Each element of `Vec<Qubit>` is mapped to bra-ket notation like so:
-    /// `Vec<Qubit>{a, b, ..., c} -> |ab...c>`
-    pub(crate) qubits: Vec<Qubit>,
+    /// The binary digits of `index` are mapped to bra-ket notation like so:
+    /// `0b ab...c -> |ab...c>`
+    pub(crate) index: usize,
+    pub(crate) num_qubits: usize,
 }
 
 impl ProductState {
@@ -28,6 +33,8 @@ impl ProductState {
     /// The product state is mapped to bra-ket notation like so:
     /// `&[a, b, ..., c] -> |ab...c>`
     ///
+    /// An error is returned if the slice is empty, or has more than [usize::BITS] qubits.
+    ///
     /// # Example
     /// ```
     /// use quantr::states::{Qubit, ProductState};
@@ -36,15 +43,50 @@ impl ProductState {
     /// ```
     pub fn new(product_state: &[Qubit]) -> QResult<ProductState> {
         if product_state.is_empty() {
-            return Err(QuantrError {
+            return Err(QuantrError::Other {
                 message: String::from(
                     "The slice of qubits is empty, it needs to at least have one element.",
                 ),
             });
         }
-        Ok(ProductState {
-            qubits: product_state.to_vec(),
-        })
+        if product_state.len() > usize::BITS as usize {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "The slice has {} qubits, while a product state can have at most {}.",
+                    product_state.len(),
+                    usize::BITS
+                ),
+            });
+        }
+        Ok(ProductState::new_unchecked(product_state))
+    }
+
+    /// Creates a single product state from a slice of booleans, where `true` and `false` are
+    /// mapped to [Qubit::One] and [Qubit::Zero] respectively.
+    ///
+    /// An error is returned if the slice is empty, or has more than [usize::BITS] booleans.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::{Qubit, ProductState};
+    ///
+    /// let prod: ProductState = ProductState::from_bools(&[true, false]).unwrap(); // |10>
+    ///
+    /// assert_eq!(vec![Qubit::One, Qubit::Zero], prod.get_qubits());
+    /// ```
+    pub fn from_bools(bits: &[bool]) -> QResult<ProductState> {
+        if bits.is_empty() {
+            return Err(QuantrError::Other {
+                message: String::from(
+                    "The slice of booleans is empty, it needs to at least have one element.",
+                ),
+            });
+        }
+        let qubits: Vec<Qubit> = bits
+            .iter()
+            .map(|&bit| if bit { Qubit::One } else { Qubit::Zero })
+            .collect();
+        ProductState::new(&qubits)
     }
 
     /// Returns the qubit in the ith position, counting from the left of the ket notation.
@@ -62,10 +104,17 @@ impl ProductState {
     /// assert_eq!(None, prod.get(2));
     /// ```
     pub fn get(&self, i: usize) -> Option<&Qubit> {
-        self.qubits.get(i)
+        if i < self.num_qubits {
+            Some(match self.get_unchecked(i) {
+                Qubit::Zero => &Qubit::Zero,
+                Qubit::One => &Qubit::One,
+            })
+        } else {
+            None
+        }
     }
 
-    /// Returns a slice of the qubits that forms the product state.
+    /// Returns the qubits that form the product state.
     ///
     /// See [ProductState::new] for the mapping.
     ///
@@ -75,16 +124,16 @@ impl ProductState {
     ///
     /// let prod: ProductState = ProductState::new(&[Qubit::One, Qubit::Zero]).unwrap();
     ///
-    /// assert_eq!(&[Qubit::One, Qubit::Zero], prod.get_qubits());
+    /// assert_eq!(vec![Qubit::One, Qubit::Zero], prod.get_qubits());
     /// ```
-    pub fn get_qubits(&self) -> &[Qubit] {
-        self.qubits.as_slice()
+    pub fn get_qubits(&self) -> Vec<Qubit> {
+        self.into_iter().collect()
     }
 
-    /// Returns a mutable slice of the qubits that forms the product state. This can be used to
-    /// directly change the elements within the slice that form the `ProductState`.
+    /// Sets the qubit in the ith position, counting from the left of the ket notation.
     ///
-    /// See [ProductState::new] for the mapping.
+    /// An error is returned if the position is larger or equal to the number of qubits that
+    /// defines the product state.
     ///
     /// # Example
     /// ```
@@ -92,33 +141,54 @@ impl ProductState {
     ///
     /// let mut prod: ProductState = ProductState::new(&[Qubit::One, Qubit::Zero]).unwrap();
     ///
-    /// prod.get_mut_qubits()[1] = Qubit::One;
+    /// prod.set(1, Qubit::One).unwrap();
     ///
-    /// assert_eq!(&[Qubit::One, Qubit::One], prod.get_qubits());
+    /// assert_eq!(vec![Qubit::One, Qubit::One], prod.get_qubits());
     /// ```
-    pub fn get_mut_qubits(&mut self) -> &mut [Qubit] {
-        self.qubits.as_mut_slice()
+    pub fn set(&mut self, i: usize, qubit: Qubit) -> QResult<&mut ProductState> {
+        if i >= self.num_qubits {
+            return Err(QuantrError::QubitOutOfBounds {
+                index: i,
+                num_qubits: self.num_qubits,
+                message: format!(
+                    "The position of the qubit, {}, is out of bounds. The product dimension is {}, and so the position must be strictly less.",
+                    i, self.num_qubits
+                ),
+            });
+        }
+        self.set_unchecked(i, qubit);
+        Ok(self)
     }
 
     // Unchecked version of new, doesn't need unwrapped.
     pub(crate) fn new_unchecked(product_state: &[Qubit]) -> ProductState {
         ProductState {
-            qubits: product_state.to_vec(),
+            index: product_state.iter().fold(0, |acc, qubit| match qubit {
+                Qubit::Zero => acc << 1,
+                Qubit::One => (acc << 1) | 1,
+            }),
+            num_qubits: product_state.len(),
         }
     }
 
     // Changes the qubits at specified positions within the product state with a slice of other
     // qubits.
     pub(crate) fn insert_qubits(&mut self, qubits: &[Qubit], pos: &[usize]) {
-        //let mut edited_qubits: Vec<Qubit> = self.qubits.clone();
-
-        for (enum_i, &i) in pos.iter().enumerate() {
-            if self.qubits[i] != qubits[enum_i] {
-                self.qubits[i] = match self.qubits[i] {
-                    Qubit::Zero => Qubit::One,
-                    Qubit::One => Qubit::Zero,
-                };
-            }
+        for (&qubit, &i) in qubits.iter().zip(pos) {
+            self.set_unchecked(i, qubit);
+        }
+    }
+
+    // The mask of the binary digit that labels the qubit in the given position.
+    fn bit_mask(&self, qubit_number: usize) -> usize {
+        1 << (self.num_qubits - 1 - qubit_number)
+    }
+
+    // Sets the qubit in the product state given a position.
+    fn set_unchecked(&mut self, qubit_number: usize, qubit: Qubit) {
+        match qubit {
+            Qubit::Zero => self.index &= !self.bit_mask(qubit_number),
+            Qubit::One => self.index |= self.bit_mask(qubit_number),
         }
     }
 
@@ -133,7 +203,7 @@ impl ProductState {
     /// assert_eq!(3, prod.num_qubits());
     /// ```
     pub fn num_qubits(&self) -> usize {
-        self.qubits.len()
+        self.num_qubits
     }
 
     /// Inverts a binary digit that represents the product state.
@@ -149,24 +219,26 @@ impl ProductState {
     ///
     /// prod.invert_digit(1);
     ///
-    /// assert_eq!(&[Qubit::One, Qubit::One, Qubit::One], prod.get_qubits());
+    /// assert_eq!(vec![Qubit::One, Qubit::One, Qubit::One], prod.get_qubits());
     /// ```
     pub fn invert_digit(&mut self, place_num: usize) -> QResult<&mut ProductState> {
         if place_num >= self.num_qubits() {
-            return Err(QuantrError { message: format!("The position of the binary digit, {}, is out of bounds. The product dimension is {}, and so the position must be strictly less.", place_num, self.num_qubits()) });
+            return Err(QuantrError::QubitOutOfBounds {
+                index: place_num,
+                num_qubits: self.num_qubits(),
+                message: format!("The position of the binary digit, {}, is out of bounds. The product dimension is {}, and so the position must be strictly less.", place_num, self.num_qubits()),
+            });
         }
 
-        let old_qubit: Qubit = self.qubits[place_num];
-        self.qubits[place_num] = if old_qubit == Qubit::Zero {
-            Qubit::One
-        } else {
-            Qubit::Zero
-        };
+        self.index ^= self.bit_mask(place_num);
         Ok(self)
     }
 
     /// Performs the Kronecker product of a product state with a qubit on the RHS.
     ///
+    /// # Panics
+    /// Panics if the product state already has [usize::BITS] qubits.
+    ///
     /// # Example
     /// ```
     /// use quantr::states::{Qubit, ProductState};
@@ -175,43 +247,76 @@ impl ProductState {
     ///
     /// let new_prod = prod.kronecker_prod(Qubit::One);
     ///
-    /// assert_eq!(&[Qubit::Zero, Qubit::Zero, Qubit::One], new_prod.get_qubits());
+    /// assert_eq!(vec![Qubit::Zero, Qubit::Zero, Qubit::One], new_prod.get_qubits());
     /// ```
     pub fn kronecker_prod(mut self, other: Qubit) -> ProductState {
-        self.qubits.push(other);
+        assert!(
+            self.num_qubits < usize::BITS as usize,
+            "A product state can have at most {} qubits.",
+            usize::BITS
+        );
+        self.index <<= 1;
+        self.num_qubits += 1;
+        self.set_unchecked(self.num_qubits - 1, other);
         self
     }
 
+    /// Splits the product state into the qubits before `index`, and the qubits from `index`
+    /// onwards.
+    ///
+    /// An error is returned if `index` is zero, or larger or equal to the number of qubits, as
+    /// one of the product states would be empty.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::{Qubit, ProductState};
+    ///
+    /// let prod: ProductState = ProductState::new(&[Qubit::One, Qubit::Zero, Qubit::Zero]).unwrap();
+    ///
+    /// let (first, second) = prod.split_at(1).unwrap();
+    ///
+    /// assert_eq!(vec![Qubit::One], first.get_qubits());
+    /// assert_eq!(vec![Qubit::Zero, Qubit::Zero], second.get_qubits());
+    /// ```
+    pub fn split_at(&self, index: usize) -> QResult<(ProductState, ProductState)> {
+        if index == 0 || index >= self.num_qubits() {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "The index, {}, must split the product state of {} qubits into two non-empty product states.",
+                    index,
+                    self.num_qubits()
+                ),
+            });
+        }
+
+        let second_size: usize = self.num_qubits - index;
+        Ok((
+            ProductState::binary_basis(self.index >> second_size, index),
+            ProductState::binary_basis(self.index & ((1 << second_size) - 1), second_size),
+        ))
+    }
+
     // Returns the qubit in the product state given a position.
     pub(crate) fn get_unchecked(&self, qubit_number: usize) -> Qubit {
-        self.qubits[qubit_number]
+        if self.index & self.bit_mask(qubit_number) == 0 {
+            Qubit::Zero
+        } else {
+            Qubit::One
+        }
     }
 
     // Converts the computational basis labelling (a binary integer), into base 10.
-    pub(super) fn comp_basis(&self) -> usize {
-        self.qubits
-            .iter()
-            .rev()
-            .enumerate()
-            .map(|(pos, i)| match i {
-                Qubit::Zero => 0u32,
-                Qubit::One => 1 << pos,
-            })
-            .sum::<u32>() as usize
+    pub(crate) fn comp_basis(&self) -> usize {
+        self.index
     }
 
     // Produces a product states based on converting a base 10 number to binary, where the product
     // state in the computational basis is defined from this labelling.
     pub(crate) fn binary_basis(index: usize, basis_size: usize) -> ProductState {
-        let binary_index: Vec<Qubit> = (0..basis_size)
-            .rev()
-            .map(|n| match (index >> n) & 1 == 1 {
-                false => Qubit::Zero,
-                true => Qubit::One,
-            })
-            .collect();
-
-        ProductState::new_unchecked(binary_index.as_slice())
+        ProductState {
+            index,
+            num_qubits: basis_size,
+        }
     }
 }
 
@@ -228,8 +333,7 @@ impl fmt::Display for ProductState {
     /// ```
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let binary_string = self
-            .qubits
-            .iter()
+            .into_iter()
             .map(|q| match q {
                 Qubit::Zero => "0",
                 Qubit::One => "1",
@@ -248,7 +352,7 @@ impl From<Qubit> for ProductState {
     ///
     /// let prod: ProductState = ProductState::from(Qubit::One);
     ///
-    /// assert_eq!(&[Qubit::One], prod.get_qubits());
+    /// assert_eq!(vec![Qubit::One], prod.get_qubits());
     /// ```
     fn from(value: Qubit) -> Self {
         ProductState::new_unchecked(&[value])
@@ -294,8 +398,8 @@ mod tests {
         let mut prod = ProductState::new_unchecked(&[Qubit::One, Qubit::One, Qubit::One]);
         prod.insert_qubits(&[Qubit::Zero, Qubit::Zero], &[0, 2]);
         assert_eq!(
-            ProductState::new_unchecked(&[Qubit::Zero, Qubit::One, Qubit::Zero]).qubits,
-            prod.qubits
+            ProductState::new_unchecked(&[Qubit::Zero, Qubit::One, Qubit::Zero]),
+            prod
         );
     }
 
@@ -334,4 +438,56 @@ mod tests {
                 .unwrap()
         )
     }
+
+    #[test]
+    fn converts_from_product_state_to_integer() {
+        assert_eq!(
+            ProductState::new_unchecked(&[Qubit::One, Qubit::One, Qubit::Zero]).comp_basis(),
+            6
+        );
+        for index in 0..16 {
+            assert_eq!(ProductState::binary_basis(index, 4).comp_basis(), index);
+        }
+    }
+
+    #[test]
+    fn splits_product_state() {
+        let state: ProductState =
+            ProductState::new(&[Qubit::Zero, Qubit::One, Qubit::One, Qubit::Zero]).unwrap();
+        assert_eq!(
+            state.split_at(2).unwrap(),
+            (
+                ProductState::new_unchecked(&[Qubit::Zero, Qubit::One]),
+                ProductState::new_unchecked(&[Qubit::One, Qubit::Zero])
+            )
+        );
+        assert!(state.split_at(0).is_err());
+        assert!(state.split_at(4).is_err());
+    }
+
+    #[test]
+    fn converts_from_bools() {
+        assert_eq!(
+            ProductState::from_bools(&[true, false, true]).unwrap(),
+            ProductState::new_unchecked(&[Qubit::One, Qubit::Zero, Qubit::One])
+        );
+        assert!(ProductState::from_bools(&[]).is_err());
+    }
+
+    #[test]
+    fn packs_qubits_into_index() {
+        const BITS: usize = usize::BITS as usize;
+        let mut prod: ProductState = ProductState::new(&[Qubit::One; BITS]).unwrap();
+        assert_eq!(prod.comp_basis(), usize::MAX);
+        prod.set(BITS - 1, Qubit::Zero)
+            .unwrap()
+            .invert_digit(0)
+            .unwrap();
+        assert_eq!(prod.get(0), Some(&Qubit::Zero));
+        assert_eq!(prod.get(BITS - 1), Some(&Qubit::Zero));
+        assert_eq!(prod.get(BITS), None);
+        assert_eq!(prod.get_qubits()[1..BITS - 1], [Qubit::One; BITS - 2]);
+        assert!(prod.set(BITS, Qubit::One).is_err());
+        assert!(ProductState::new(&[Qubit::Zero; BITS + 1]).is_err());
+    }
 }
@@ -35,7 +35,7 @@ impl<'a> Iterator for ProductStateIter<'a> {
     type Item = Qubit;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(qubit) = self.state.qubits.get(self.index).copied() {
+        if let Some(qubit) = self.state.get(self.index).copied() {
             self.index += 1;
             Some(qubit)
         } else {
@@ -51,7 +51,7 @@ impl<'a> IntoIterator for &'a ProductState {
 
     fn into_iter(self) -> Self::IntoIter {
         ProductStateIter {
-            state: &self,
+            state: self,
             index: 0,
         }
     }
@@ -8,10 +8,11 @@
 * Author: Andrew Rowan Barlow <a.barlow.dev@gmail.com>
 */
 
-use crate::states::ProductState;
+use crate::states::{ProductState, SuperPosition};
 
 /// The fundamental unit in quantum computers.
 #[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Qubit {
     /// |0⟩
     Zero,
@@ -35,4 +36,56 @@ impl Qubit {
     pub fn kronecker_prod(self, other: Qubit) -> ProductState {
         ProductState::new_unchecked(&[self, other])
     }
+
+    /// Measures a superposition of a single qubit, returning the observed qubit.
+    ///
+    /// `None` is returned if the superposition is not of a single qubit, or if it has zero total
+    /// probability.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::{Qubit, SuperPosition};
+    /// use quantr::complex_re_array;
+    /// use std::f64::consts::FRAC_1_SQRT_2;
+    ///
+    /// // The |+> state.
+    /// let plus: SuperPosition =
+    ///     SuperPosition::new_with_amplitudes(&complex_re_array!(FRAC_1_SQRT_2, FRAC_1_SQRT_2))
+    ///         .unwrap();
+    ///
+    /// let observed: Qubit = Qubit::measure(&plus).unwrap();
+    /// println!("Observed |{:?}>", observed);
+    /// ```
+    pub fn measure(superpos: &SuperPosition) -> Option<Qubit> {
+        if superpos.get_num_qubits() != 1 {
+            return None;
+        }
+
+        superpos.sample_index().map(|index| match index {
+            0 => Qubit::Zero,
+            _ => Qubit::One,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::complex_re_array;
+    use crate::states::{Qubit, SuperPosition};
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    #[test]
+    fn measures_plus_state() {
+        fastrand::seed(0);
+        let plus: SuperPosition =
+            SuperPosition::new_with_amplitudes(&complex_re_array!(FRAC_1_SQRT_2, FRAC_1_SQRT_2))
+                .unwrap();
+
+        let zero_count: usize = (0..1000)
+            .filter(|_| Qubit::measure(&plus) == Some(Qubit::Zero))
+            .count();
+        assert!(zero_count > 400 && zero_count < 600);
+
+        assert_eq!(Qubit::measure(&SuperPosition::new(2).unwrap()), None);
+    }
 }
@@ -9,7 +9,8 @@
 */
 
 use crate::states::{ProductState, SuperPosition};
-use num_complex::Complex64;
+use crate::Float;
+use num_complex::Complex;
 
 /// Returns the product state and it's respective amplitude in each iteration.
 ///
@@ -36,14 +37,14 @@ use num_complex::Complex64;
 ///     Some((ProductState::new(&[Qubit::One, Qubit::One]).unwrap(), Complex64::ZERO)));
 /// assert_eq!(iterator_super_pos.next(), None);
 /// ```
-pub struct SuperPositionIterator<'a> {
-    super_position: &'a SuperPosition,
+pub struct SuperPositionIterator<'a, T: Float = f64> {
+    super_position: &'a SuperPosition<T>,
     index: usize,
 }
 
-impl<'a> IntoIterator for &'a SuperPosition {
-    type Item = (ProductState, Complex64);
-    type IntoIter = SuperPositionIterator<'a>;
+impl<'a, T: Float> IntoIterator for &'a SuperPosition<T> {
+    type Item = (ProductState, Complex<T>);
+    type IntoIter = SuperPositionIterator<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
         SuperPositionIterator {
@@ -53,8 +54,8 @@ impl<'a> IntoIterator for &'a SuperPosition {
     }
 }
 
-impl<'a> Iterator for SuperPositionIterator<'a> {
-    type Item = (ProductState, Complex64);
+impl<T: Float> Iterator for SuperPositionIterator<'_, T> {
+    type Item = (ProductState, Complex<T>);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.index < self.super_position.amplitudes.len() {
@@ -8,19 +8,30 @@
 * Author: Andrew Rowan Barlow <a.barlow.dev@gmail.com>
 */
 
+use crate::circuit::gate::GateCategory;
 use crate::circuit::{HashMap, QResult};
+use crate::complex::to_precision;
 use crate::complex_re;
 use crate::error::QuantrError;
 use crate::states::ProductState;
 use crate::states::Qubit;
-use num_complex::Complex64;
+use crate::Float;
+use crate::Gate;
+use core::iter::zip;
+use num_complex::{Complex, Complex64};
+use std::fmt;
 
-const ZERO_MARGIN: f64 = 1e-6;
+pub(super) const ZERO_MARGIN: f64 = 1e-6;
 
 /// A superposition of [ProductState]s.
+///
+/// The amplitudes are stored with the precision of `T`, which is `f64` by default. Constructing
+/// and analysing a superposition is done in `f64`, which can be converted to a lower precision
+/// with [SuperPosition::to_precision].
 #[derive(PartialEq, Debug, Clone)]
-pub struct SuperPosition {
-    pub(crate) amplitudes: Vec<Complex64>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuperPosition<T: Float = f64> {
+    pub(crate) amplitudes: Vec<Complex<T>>,
     pub(crate) product_dim: usize,
 }
 
@@ -40,7 +51,7 @@ impl SuperPosition {
     /// ```
     pub fn new(prod_dimension: usize) -> QResult<SuperPosition> {
         if prod_dimension == 0 {
-            return Err(QuantrError {
+            return Err(QuantrError::Other {
                 message: String::from("The number of qubits must be non-zero."),
             });
         }
@@ -53,6 +64,70 @@ impl SuperPosition {
         })
     }
 
+    /// Creates the state in the computational basis that is labelled by `index`, where the
+    /// binary representation of `index` labels the product state. For example, the index 2 with 3
+    /// qubits returns |010>.
+    ///
+    /// An error is returned if the number of qubits is zero, or the index is not less than
+    /// `2^num_qubits`.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re_array;
+    ///
+    /// let superpos = SuperPosition::basis_state(2, 2).unwrap(); // |10>
+    ///
+    /// assert_eq!(&complex_re_array![0f64, 0f64, 1f64, 0f64], superpos.get_amplitudes());
+    /// ```
+    pub fn basis_state(index: usize, num_qubits: usize) -> QResult<SuperPosition> {
+        if num_qubits == 0 {
+            return Err(QuantrError::Other {
+                message: String::from("The number of qubits must be non-zero."),
+            });
+        }
+
+        if index.checked_shr(num_qubits as u32).unwrap_or(0) != 0 {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "The index, {}, is out of bounds for a superposition of {} qubits.",
+                    index, num_qubits
+                ),
+            });
+        }
+
+        Ok(ProductState::binary_basis(index, num_qubits).into())
+    }
+
+    /// Creates the equal superposition over all `2^num_qubits` states in the computational
+    /// basis, where each amplitude is `1/sqrt(2^num_qubits)`. This is the state produced by
+    /// applying a Hadamard gate to every wire of the zero state.
+    ///
+    /// An error is returned if the number of qubits is zero.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re_array;
+    ///
+    /// let superpos = SuperPosition::equal_superposition(2).unwrap();
+    ///
+    /// assert_eq!(&complex_re_array![0.5f64, 0.5f64, 0.5f64, 0.5f64], superpos.get_amplitudes());
+    /// ```
+    pub fn equal_superposition(num_qubits: usize) -> QResult<SuperPosition> {
+        if num_qubits == 0 {
+            return Err(QuantrError::Other {
+                message: String::from("The number of qubits must be non-zero."),
+            });
+        }
+
+        let dimension: usize = 1 << num_qubits;
+        Ok(SuperPosition {
+            amplitudes: vec![complex_re!(1f64 / (dimension as f64).sqrt()); dimension],
+            product_dim: num_qubits,
+        })
+    }
+
     /// Creates a superposition based on the complex amplitudes of each state labelled in
     /// the computational basis.
     ///
@@ -66,15 +141,17 @@ impl SuperPosition {
     /// assert_eq!(&complex_re_array![1f64, 0f64, 0f64, 0f64], superpos.get_amplitudes());
     /// ```
     pub fn new_with_amplitudes(amplitudes: &[Complex64]) -> QResult<SuperPosition> {
-        if !Self::equal_within_error(amplitudes.iter().map(|x| x.norm_sqr()).sum::<f64>(), 1f64) {
-            return Err(QuantrError{
+        let total: f64 = amplitudes.iter().map(|x| x.norm_sqr()).sum::<f64>();
+        if !Self::equal_within_error(total, 1f64) {
+            return Err(QuantrError::ProbabilityNotConserved {
+                total,
                 message: String::from("Slice given to set amplitudes in super position does not conserve probability, the absolute square sum of the coefficents must be one."),
             });
         }
 
         let length = amplitudes.len();
         if (length & (length - 1)) != 0 {
-            return Err(QuantrError {
+            return Err(QuantrError::Other {
                 message: String::from(
                     "The length of the array must be of the form 2**n where n is an integer.",
                 ),
@@ -106,20 +183,27 @@ impl SuperPosition {
         hash_amplitudes: HashMap<ProductState, Complex64>,
     ) -> QResult<SuperPosition> {
         if hash_amplitudes.is_empty() {
-            return Err(QuantrError { message: String::from("An empty HashMap was given. A superposition must have at least one non-zero state.") });
+            return Err(QuantrError::Other { message: String::from("An empty HashMap was given. A superposition must have at least one non-zero state.") });
         }
 
         let product_dim: usize = hash_amplitudes.keys().next().unwrap().num_qubits();
         let mut total_amplitude: f64 = 0f64;
         for (states, amplitude) in &hash_amplitudes {
             if states.num_qubits() != product_dim {
-                return Err(QuantrError { message: format!("The first state has product dimension of {}, whilst the state, |{}>, found as a key in the HashMap has dimension {}.", product_dim, states, states.num_qubits()) });
+                return Err(QuantrError::DimensionMismatch {
+                    expected: product_dim,
+                    found: states.num_qubits(),
+                    message: format!("The first state has product dimension of {}, whilst the state, |{}>, found as a key in the HashMap has dimension {}.", product_dim, states, states.num_qubits()),
+                });
             }
             total_amplitude += amplitude.norm_sqr();
         }
 
         if !Self::equal_within_error(total_amplitude, 1f64) {
-            return Err(QuantrError { message: format!("The total sum of the absolute square of all amplitudes, {}, does not equal 1. That is, the superpositon does not conserve probability.", total_amplitude) });
+            return Err(QuantrError::ProbabilityNotConserved {
+                total: total_amplitude,
+                message: format!("The total sum of the absolute square of all amplitudes, {}, does not equal 1. That is, the superpositon does not conserve probability.", total_amplitude),
+            });
         }
 
         let mut amplitudes: Vec<Complex64> = vec![num_complex::Complex64::ZERO; 1 << product_dim];
@@ -130,66 +214,48 @@ impl SuperPosition {
         })
     }
 
-    /// Retrieves the coefficient of the product state in the computational basis given by the list index. Returns `None` if the
-    /// index is greater than the product dimension of the superposition.
+    /// Creates a superposition from a sparse map of amplitudes, where each key is the index of the
+    /// product state in the computational basis. States that are not keys have zero amplitude.
     ///
-    /// # Example
-    /// ```
-    /// use quantr::states::SuperPosition;
-    /// use quantr::{complex_re_array, complex_re};
-    ///
-    /// let superpos = SuperPosition::new_with_amplitudes(&complex_re_array![0f64, 1f64, 0f64, 0f64]).unwrap();
-    ///
-    /// assert_eq!(complex_re!(1f64), superpos.get_amplitude(1).unwrap());
-    /// ```
-    pub fn get_amplitude(&self, pos: usize) -> Option<Complex64> {
-        self.amplitudes.get(pos).cloned()
-    }
-
-    /// Returns the number of qubits that each product state in the super position is composed of by using the Kronecker product.
+    /// An error is returned if the number of qubits is zero, an index is not less than
+    /// `2^num_qubits`, or the amplitudes do not conserve probability.
     ///
     /// # Example
     /// ```
+    /// use std::collections::HashMap;
     /// use quantr::states::SuperPosition;
     /// use quantr::{complex_re_array, complex_re};
     ///
-    /// let superpos = SuperPosition::new_with_amplitudes(&complex_re_array![0f64, 1f64, 0f64, 0f64]).unwrap();
+    /// let superpos = SuperPosition::from_sparse(HashMap::from([(1, complex_re!(1f64))]), 2).unwrap();
     ///
-    /// assert_eq!(2, superpos.get_num_qubits());
+    /// assert_eq!(&complex_re_array![0f64, 1f64, 0f64, 0f64], superpos.get_amplitudes());
     /// ```
-    pub fn get_num_qubits(&self) -> usize {
-        self.product_dim
-    }
+    pub fn from_sparse(
+        map: HashMap<usize, Complex64>,
+        num_qubits: usize,
+    ) -> QResult<SuperPosition> {
+        if num_qubits == 0 {
+            return Err(QuantrError::Other {
+                message: String::from("The number of qubits must be non-zero."),
+            });
+        }
 
-    /// Returns the minimum dimension of the Hilbert space that the superposition can exist in.
-    ///
-    /// # Example
-    /// ```
-    /// use quantr::states::SuperPosition;
-    /// use quantr::{complex_re_array, complex_re};
-    ///
-    /// let superpos = SuperPosition::new_with_amplitudes(&complex_re_array![0f64, 1f64, 0f64, 0f64]).unwrap();
-    ///
-    /// assert_eq!(4, superpos.get_dimension());
-    /// ```
-    pub fn get_dimension(&self) -> usize {
-        self.amplitudes.len()
-    }
+        let mut amplitudes: Vec<Complex64> = vec![Complex64::ZERO; 1 << num_qubits];
+        for (index, amplitude) in map {
+            match amplitudes.get_mut(index) {
+                Some(amp) => *amp = amplitude,
+                None => {
+                    return Err(QuantrError::Other {
+                        message: format!(
+                            "The index, {}, is out of bounds for a superposition of {} qubits.",
+                            index, num_qubits
+                        ),
+                    })
+                }
+            }
+        }
 
-    /// Returns a slice of the coefficients ordered in the computational basis of increasing order from
-    /// left to right.
-    ///
-    /// # Example
-    /// ```
-    /// use quantr::states::SuperPosition;
-    /// use quantr::{complex_re_array, complex_re};
-    ///
-    /// let superpos = SuperPosition::new(2).unwrap();
-    ///
-    /// assert_eq!(&complex_re_array![1f64, 0f64, 0f64, 0f64], superpos.get_amplitudes());
-    /// ```
-    pub fn get_amplitudes(&self) -> &[Complex64] {
-        self.amplitudes.as_slice()
+        Self::new_with_amplitudes(&amplitudes)
     }
 
     /// Retrieves the coefficient of the product state labelled in the computational basis.
@@ -205,8 +271,12 @@ impl SuperPosition {
     /// assert_eq!(complex_re!(1f64), superpos.get_amplitude_from_state(prod_state).unwrap());
     /// ```
     pub fn get_amplitude_from_state(&self, prod_state: ProductState) -> QResult<Complex64> {
-        if 2usize << (prod_state.qubits.len() - 1) != self.amplitudes.len() {
-            return Err(QuantrError { message: format!("Unable to retreive product state, |{:?}> with dimension {}. The superposition is a linear combination of states with different dimension. These dimensions should be equal.", prod_state.to_string(), prod_state.num_qubits()),});
+        if 2usize << (prod_state.num_qubits() - 1) != self.amplitudes.len() {
+            return Err(QuantrError::DimensionMismatch {
+                expected: self.product_dim,
+                found: prod_state.num_qubits(),
+                message: format!("Unable to retreive product state, |{:?}> with dimension {}. The superposition is a linear combination of states with different dimension. These dimensions should be equal.", prod_state.to_string(), prod_state.num_qubits()),
+            });
         }
         Ok(self.amplitudes[prod_state.comp_basis()])
     }
@@ -228,13 +298,17 @@ impl SuperPosition {
     /// ```
     pub fn set_amplitudes(&mut self, amplitudes: &[Complex64]) -> QResult<&mut SuperPosition> {
         if amplitudes.len() != self.amplitudes.len() {
-            return Err(QuantrError {
+            return Err(QuantrError::DimensionMismatch {
+                expected: self.amplitudes.len(),
+                found: amplitudes.len(),
                 message: format!("The slice given to set the amplitudes in the computational basis has length {}, when it should have length {}.", amplitudes.len(), self.amplitudes.len()),
             });
         }
 
-        if !Self::equal_within_error(amplitudes.iter().map(|x| x.norm_sqr()).sum::<f64>(), 1f64) {
-            return Err(QuantrError {
+        let total: f64 = amplitudes.iter().map(|x| x.norm_sqr()).sum::<f64>();
+        if !Self::equal_within_error(total, 1f64) {
+            return Err(QuantrError::ProbabilityNotConserved {
+                total,
                 message: String::from("Slice given to set amplitudes in super position does not conserve probability, the absolute square sum of the coefficents must be one."),
             });
         }
@@ -273,20 +347,27 @@ impl SuperPosition {
     ) -> QResult<&mut SuperPosition> {
         // Check if amplitudes and product states are correct.
         if amplitudes.is_empty() {
-            return Err(QuantrError { message: String::from("An empty HashMap was given. A superposition must have at least one non-zero state.") });
+            return Err(QuantrError::Other { message: String::from("An empty HashMap was given. A superposition must have at least one non-zero state.") });
         }
 
         let product_size: usize = self.amplitudes.len().trailing_zeros() as usize;
         let mut total_amplitude: f64 = 0f64;
         for (states, amplitude) in &amplitudes {
             if states.num_qubits() != product_size {
-                return Err(QuantrError { message: format!("The first state has product dimension of {}, whilst the state, |{}>, found as a key in the HashMap has dimension {}.", product_size, states, states.num_qubits()) });
+                return Err(QuantrError::DimensionMismatch {
+                    expected: product_size,
+                    found: states.num_qubits(),
+                    message: format!("The first state has product dimension of {}, whilst the state, |{}>, found as a key in the HashMap has dimension {}.", product_size, states, states.num_qubits()),
+                });
             }
             total_amplitude += amplitude.norm_sqr();
         }
 
         if !Self::equal_within_error(total_amplitude, 1f64) {
-            return Err(QuantrError { message: String::from("The total sum of the absolute square of all amplitudes does not equal 1. That is, the superpositon does not conserve probability.") });
+            return Err(QuantrError::ProbabilityNotConserved {
+                total: total_amplitude,
+                message: String::from("The total sum of the absolute square of all amplitudes does not equal 1. That is, the superpositon does not conserve probability."),
+            });
         }
 
         Self::from_hash_to_array(amplitudes, &mut self.amplitudes);
@@ -322,28 +403,341 @@ impl SuperPosition {
         super_pos_as_hash
     }
 
-    /// Observe the superposition and return the measuremed state in the computational basis.
+    /// Returns a sparse map of the amplitudes, where each key is the index of the product state in
+    /// the computational basis. States with zero amplitude are not included.
     ///
-    /// If `None` is returned, then the state vector does not conserve probability. More
-    /// precisely, the sum of the conjugate square of coefficients is less than one. The sum could
-    /// be greater than one, however a `Some(Complex64)` type would be returned. The
-    /// non-conservation of probability can happen due to the use of implementing non-unitary
-    /// gates through `Custom::gate`.
-    pub fn measure(&self) -> Option<ProductState> {
-        let mut cummalitive: f64 = 0f64;
-        let dice_roll: f64 = fastrand::f64();
-        for (i, probability) in self.amplitudes.iter().map(|x| x.norm_sqr()).enumerate() {
-            cummalitive += probability;
-            if dice_roll < cummalitive {
-                return Some(ProductState::binary_basis(i, self.product_dim));
+    /// This is the same as [SuperPosition::to_hash_map], however avoids constructing a
+    /// [ProductState] for each key.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re;
+    ///
+    /// let superpos = SuperPosition::new(2).unwrap();
+    ///
+    /// assert_eq!(HashMap::from([(0, complex_re!(1f64))]), superpos.to_sparse());
+    /// ```
+    pub fn to_sparse(&self) -> HashMap<usize, Complex64> {
+        self.amplitudes
+            .iter()
+            .enumerate()
+            .filter(|(_, amp)| !Self::equal_within_error(amp.norm_sqr(), 0f64))
+            .map(|(i, amp)| (i, *amp))
+            .collect()
+    }
+
+    /// Returns an iterator over the index of each state in the computational basis, together with
+    /// the probability of measuring it.
+    ///
+    /// All `2^n` states are yielded in increasing order of their index, including those with zero
+    /// probability. This is the lazy counterpart to [crate::SimulatedCircuit::probabilities],
+    /// which avoids allocating a vector.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re_array;
+    ///
+    /// let superpos = SuperPosition::new_with_amplitudes(&complex_re_array![0f64, 0f64, 1f64, 0f64]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     superpos.basis_probabilities().collect::<Vec<(usize, f64)>>(),
+    ///     vec![(0, 0f64), (1, 0f64), (2, 1f64), (3, 0f64)]
+    /// );
+    /// ```
+    pub fn basis_probabilities(&self) -> impl Iterator<Item = (usize, f64)> + '_ {
+        self.amplitudes
+            .iter()
+            .enumerate()
+            .map(|(i, amp)| (i, amp.norm_sqr()))
+    }
+
+    /// Scales the amplitudes so that the superposition conserves probability, and returns the
+    /// total probability before it was renormalised.
+    ///
+    /// This is useful after a post-selection, where the amplitudes outside of a subspace have been
+    /// set to zero, for example with [SuperPosition::set_amplitudes_unchecked]. An error is
+    /// returned if the total probability is zero, as the state cannot be renormalised.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re_array;
+    /// use std::f64::consts::FRAC_1_SQRT_2;
+    ///
+    /// let mut superpos =
+    ///     SuperPosition::new_with_amplitudes_unchecked(&complex_re_array![FRAC_1_SQRT_2, 0f64]);
+    ///
+    /// let total_probability: f64 = superpos.renormalise().unwrap();
+    ///
+    /// assert!((total_probability - 0.5f64).abs() < 1e-10);
+    /// assert!((superpos.get_amplitudes()[0].re - 1f64).abs() < 1e-10);
+    /// ```
+    pub fn renormalise(&mut self) -> QResult<f64> {
+        let total_probability: f64 = self.amplitudes.iter().map(|x| x.norm_sqr()).sum::<f64>();
+        if Self::equal_within_error(total_probability, 0f64) {
+            return Err(QuantrError::Other {
+                message: String::from("The superposition has zero total probability, and so it cannot be renormalised."),
+            });
+        }
+
+        let norm: f64 = total_probability.sqrt();
+        for amp in self.amplitudes.iter_mut() {
+            *amp /= norm;
+        }
+        Ok(total_probability)
+    }
+
+    /// Returns the fidelity, |<self|other>|², between two pure states.
+    ///
+    /// Both superpositions are assumed to be normalised. An error is returned if the
+    /// superpositions have a different number of qubits.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::{Qubit, SuperPosition};
+    /// use quantr::complex_re_array;
+    /// use std::f64::consts::FRAC_1_SQRT_2;
+    ///
+    /// let zero = SuperPosition::from(Qubit::Zero);
+    /// let plus =
+    ///     SuperPosition::new_with_amplitudes(&complex_re_array![FRAC_1_SQRT_2, FRAC_1_SQRT_2])
+    ///         .unwrap();
+    ///
+    /// assert!((zero.fidelity(&plus).unwrap() - 0.5f64).abs() < 1e-10);
+    /// ```
+    pub fn fidelity(&self, other: &SuperPosition) -> QResult<f64> {
+        if self.product_dim != other.product_dim {
+            return Err(QuantrError::DimensionMismatch {
+                expected: self.product_dim,
+                found: other.product_dim,
+                message: format!("The superpositions have {} and {} qubits, and so their fidelity cannot be found.", self.product_dim, other.product_dim),
+            });
+        }
+
+        Ok(zip(&self.amplitudes, &other.amplitudes)
+            .map(|(a, b)| a.conj() * b)
+            .sum::<Complex64>()
+            .norm_sqr())
+    }
+
+    /// Returns the superposition with `extra` qubits in the |0> state added to its left, that
+    /// is |0...0> ⊗ |ψ>.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::{ProductState, Qubit, SuperPosition};
+    ///
+    /// let superpos = SuperPosition::from(Qubit::One).pad_left(1); // |01>
+    ///
+    /// assert_eq!(
+    ///     superpos,
+    ///     SuperPosition::from(ProductState::new(&[Qubit::Zero, Qubit::One]).unwrap())
+    /// );
+    /// ```
+    pub fn pad_left(&self, extra: usize) -> SuperPosition {
+        let mut amplitudes: Vec<Complex64> = vec![Complex64::ZERO; self.amplitudes.len() << extra];
+        amplitudes[..self.amplitudes.len()].copy_from_slice(&self.amplitudes);
+        SuperPosition {
+            amplitudes,
+            product_dim: self.product_dim + extra,
+        }
+    }
+
+    /// Returns the superposition with `extra` qubits in the |0> state added to its right, that
+    /// is |ψ> ⊗ |0...0>.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::{ProductState, Qubit, SuperPosition};
+    ///
+    /// let superpos = SuperPosition::from(Qubit::One).pad_right(1); // |10>
+    ///
+    /// assert_eq!(
+    ///     superpos,
+    ///     SuperPosition::from(ProductState::new(&[Qubit::One, Qubit::Zero]).unwrap())
+    /// );
+    /// ```
+    pub fn pad_right(&self, extra: usize) -> SuperPosition {
+        let mut amplitudes: Vec<Complex64> = vec![Complex64::ZERO; self.amplitudes.len() << extra];
+        for (i, amp) in self.amplitudes.iter().enumerate() {
+            amplitudes[i << extra] = *amp;
+        }
+        SuperPosition {
+            amplitudes,
+            product_dim: self.product_dim + extra,
+        }
+    }
+
+    /// Returns the n-fold tensor product of the superposition with itself, |ψ> ⊗ ... ⊗ |ψ>.
+    ///
+    /// If `n` is zero, the scalar state with a single amplitude of one, and no qubits, is
+    /// returned.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::{ProductState, Qubit, SuperPosition};
+    ///
+    /// let superpos = SuperPosition::from(Qubit::One).tensor_pow(3); // |111>
+    ///
+    /// assert_eq!(
+    ///     superpos,
+    ///     SuperPosition::from(ProductState::new(&[Qubit::One; 3]).unwrap())
+    /// );
+    /// ```
+    pub fn tensor_pow(&self, n: usize) -> SuperPosition {
+        let mut amplitudes: Vec<Complex64> = vec![complex_re!(1f64)];
+        for _ in 0..n {
+            amplitudes = amplitudes
+                .iter()
+                .flat_map(|a| self.amplitudes.iter().map(move |b| a * b))
+                .collect();
+        }
+        SuperPosition {
+            amplitudes,
+            product_dim: self.product_dim * n,
+        }
+    }
+
+    /// Applies a single gate to the `target` qubit, conditioned on the `control` qubit being in
+    /// the |1> state. This is the same as adding a controlled gate to a circuit, however it acts
+    /// directly on the superposition.
+    ///
+    /// An error is returned if the gate is not a single unitary gate, the control and target are
+    /// the same qubit, or either is out of bounds of the superposition.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::Gate;
+    ///
+    /// let mut superpos = SuperPosition::basis_state(2, 2).unwrap(); // |10>
+    /// superpos.apply_controlled_gate(Gate::X, 0, 1).unwrap();
+    ///
+    /// assert_eq!(superpos, SuperPosition::basis_state(3, 2).unwrap()); // |11>
+    /// ```
+    pub fn apply_controlled_gate(
+        &mut self,
+        gate: Gate,
+        control: usize,
+        target: usize,
+    ) -> QResult<&mut SuperPosition> {
+        if !gate.is_controllable() {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "The gate, {:?}, is not a single unitary gate with a bound angle, and so cannot be controlled.",
+                    gate
+                ),
+            });
+        }
+
+        if control >= self.product_dim || target >= self.product_dim {
+            return Err(QuantrError::QubitOutOfBounds {
+                index: control.max(target),
+                num_qubits: self.product_dim,
+                message: format!("The control, {}, or target, {}, is out of bounds for a superposition of {} qubits.", control, target, self.product_dim),
+            });
+        }
+
+        if control == target {
+            return Err(QuantrError::OverlappingControlNodes {
+                gate: format!("{:?}", gate),
+                message: format!(
+                    "The control and target are both on qubit {}, and must be different.",
+                    control
+                ),
+            });
+        }
+
+        let (image_zero, image_one): (SuperPosition, SuperPosition) = match gate.linker() {
+            GateCategory::Single(func) => (func(Qubit::Zero), func(Qubit::One)),
+            GateCategory::SingleArg(arg, func) => (func(Qubit::Zero, arg), func(Qubit::One, arg)),
+            GateCategory::SingleArgThree(theta, phi, lambda, func) => (
+                func(Qubit::Zero, theta, phi, lambda),
+                func(Qubit::One, theta, phi, lambda),
+            ),
+            GateCategory::Identity => return Ok(self),
+            _ => {
+                return Err(QuantrError::Other {
+                    message: format!(
+                        "The gate, {:?}, is not unitary, and so cannot be controlled.",
+                        gate
+                    ),
+                })
+            }
+        };
+
+        // Qubit 0 labels the most significant bit of the index.
+        let control_bit: usize = 1 << (self.product_dim - 1 - control);
+        let target_bit: usize = 1 << (self.product_dim - 1 - target);
+        for i in 0..self.amplitudes.len() {
+            if i & control_bit == 0 || i & target_bit != 0 {
+                continue;
+            }
+            let j: usize = i | target_bit;
+            let (amp_zero, amp_one): (Complex64, Complex64) =
+                (self.amplitudes[i], self.amplitudes[j]);
+            self.amplitudes[i] =
+                image_zero.amplitudes[0] * amp_zero + image_one.amplitudes[0] * amp_one;
+            self.amplitudes[j] =
+                image_zero.amplitudes[1] * amp_zero + image_one.amplitudes[1] * amp_one;
+        }
+
+        Ok(self)
+    }
+
+    /// Divides each amplitude by the norm of the superposition, so that it conserves probability.
+    ///
+    /// This is the same as [SuperPosition::renormalise], however returns the superposition so that
+    /// it can be chained. An error is returned if the total probability is zero.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re_array;
+    ///
+    /// let mut superpos = SuperPosition::new_with_amplitudes_unchecked(&complex_re_array![1f64, 1f64]);
+    /// superpos.normalise().unwrap();
+    ///
+    /// let total_probability: f64 = superpos.get_amplitudes().iter().map(|x| x.norm_sqr()).sum();
+    /// assert!((total_probability - 1f64).abs() < 1e-10);
+    /// ```
+    pub fn normalise(&mut self) -> QResult<&mut SuperPosition> {
+        self.renormalise()?;
+        Ok(self)
+    }
+
+    /// Multiplies the superposition by a global phase, so that the first amplitude that is not
+    /// near zero becomes real and positive.
+    ///
+    /// A global phase does not change the probability of any measurement, and so this gives a
+    /// canonical representative of the state, which is useful when comparing states that may only
+    /// differ by a global phase.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_im_array;
+    ///
+    /// let mut superpos = SuperPosition::new_with_amplitudes(&complex_im_array![0f64, 1f64]).unwrap();
+    /// superpos.canonicalise_phase();
+    ///
+    /// assert_eq!(superpos, SuperPosition::basis_state(1, 1).unwrap());
+    /// ```
+    pub fn canonicalise_phase(&mut self) {
+        if let Some(&leading_amp) = self.amplitudes.iter().find(|amp| amp.norm() > ZERO_MARGIN) {
+            let phase: Complex64 = leading_amp.conj() / leading_amp.norm();
+            for amp in self.amplitudes.iter_mut() {
+                *amp *= phase;
             }
         }
-        None
     }
 
     pub(super) fn from_hash_to_array(
         hash_amplitudes: HashMap<ProductState, Complex64>,
-        vec_amplitudes: &mut Vec<Complex64>,
+        vec_amplitudes: &mut [Complex64],
     ) {
         let length: usize = vec_amplitudes.len();
         let trailing_length: usize = length.trailing_zeros() as usize;
@@ -357,6 +751,141 @@ impl SuperPosition {
     }
 }
 
+impl<T: Float> SuperPosition<T> {
+    /// Retrieves the coefficient of the product state in the computational basis given by the list index. Returns `None` if the
+    /// index is greater than the product dimension of the superposition.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::{complex_re_array, complex_re};
+    ///
+    /// let superpos = SuperPosition::new_with_amplitudes(&complex_re_array![0f64, 1f64, 0f64, 0f64]).unwrap();
+    ///
+    /// assert_eq!(complex_re!(1f64), superpos.get_amplitude(1).unwrap());
+    /// ```
+    pub fn get_amplitude(&self, pos: usize) -> Option<Complex<T>> {
+        self.amplitudes.get(pos).cloned()
+    }
+
+    /// Returns the number of qubits that each product state in the super position is composed of by using the Kronecker product.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::{complex_re_array, complex_re};
+    ///
+    /// let superpos = SuperPosition::new_with_amplitudes(&complex_re_array![0f64, 1f64, 0f64, 0f64]).unwrap();
+    ///
+    /// assert_eq!(2, superpos.get_num_qubits());
+    /// ```
+    pub fn get_num_qubits(&self) -> usize {
+        self.product_dim
+    }
+
+    /// Returns the minimum dimension of the Hilbert space that the superposition can exist in.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::{complex_re_array, complex_re};
+    ///
+    /// let superpos = SuperPosition::new_with_amplitudes(&complex_re_array![0f64, 1f64, 0f64, 0f64]).unwrap();
+    ///
+    /// assert_eq!(4, superpos.get_dimension());
+    /// ```
+    pub fn get_dimension(&self) -> usize {
+        self.amplitudes.len()
+    }
+
+    /// Returns a slice of the coefficients ordered in the computational basis of increasing order from
+    /// left to right.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::{complex_re_array, complex_re};
+    ///
+    /// let superpos = SuperPosition::new(2).unwrap();
+    ///
+    /// assert_eq!(&complex_re_array![1f64, 0f64, 0f64, 0f64], superpos.get_amplitudes());
+    /// ```
+    pub fn get_amplitudes(&self) -> &[Complex<T>] {
+        self.amplitudes.as_slice()
+    }
+
+    /// Observe the superposition and return the measuremed state in the computational basis.
+    ///
+    /// If `None` is returned, then the state vector does not conserve probability. More
+    /// precisely, the sum of the conjugate square of coefficients is less than one. The sum could
+    /// be greater than one, however a `Some(Complex64)` type would be returned. The
+    /// non-conservation of probability can happen due to the use of implementing non-unitary
+    /// gates through `Custom::gate`.
+    pub fn measure(&self) -> Option<ProductState> {
+        self.sample_index()
+            .map(|i| ProductState::binary_basis(i, self.product_dim))
+    }
+
+    /// Observe the superposition and return the index of the measured state in the
+    /// computational basis.
+    ///
+    /// This is the same as [SuperPosition::measure], but avoids constructing a [ProductState]
+    /// when only the index is needed. `None` is returned for the same reasons.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re_array;
+    ///
+    /// let superpos = SuperPosition::new_with_amplitudes(&complex_re_array![0f64, 0f64, 1f64, 0f64]).unwrap();
+    ///
+    /// assert_eq!(Some(2), superpos.sample_index());
+    /// ```
+    pub fn sample_index(&self) -> Option<usize> {
+        self.sample_index_from_roll(fastrand::f64())
+    }
+
+    // Returns the index of the state that the dice roll, uniform in [0, 1), lands on.
+    pub(crate) fn sample_index_from_roll(&self, dice_roll: f64) -> Option<usize> {
+        let mut cummalitive: f64 = 0f64;
+        for (i, probability) in self
+            .amplitudes
+            .iter()
+            .map(|x| x.norm_sqr().into_f64())
+            .enumerate()
+        {
+            cummalitive += probability;
+            if dice_roll < cummalitive {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// Returns a copy of the superposition, with the amplitudes converted to the precision of
+    /// `U`.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    ///
+    /// let superpos = SuperPosition::new(2).unwrap();
+    /// let superpos_f32: SuperPosition<f32> = superpos.to_precision();
+    ///
+    /// assert_eq!(superpos_f32.get_amplitudes()[0].re, 1f32);
+    /// ```
+    pub fn to_precision<U: Float>(&self) -> SuperPosition<U> {
+        SuperPosition {
+            amplitudes: self
+                .amplitudes
+                .iter()
+                .map(|amp| to_precision(*amp))
+                .collect(),
+            product_dim: self.product_dim,
+        }
+    }
+}
+
 impl From<ProductState> for SuperPosition {
     /// Returns the [ProductState] as a [SuperPosition].
     ///
@@ -392,11 +921,63 @@ impl From<Qubit> for SuperPosition {
     }
 }
 
+impl<T: Float> fmt::Display for SuperPosition<T> {
+    /// Returns the superposition as a linear combination of product states, omitting the product
+    /// states that have a zero amplitude. The amplitudes are given to three decimal places.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re_array;
+    /// use std::f64::consts::FRAC_1_SQRT_2;
+    ///
+    /// let bell_state = SuperPosition::new_with_amplitudes(&complex_re_array!(
+    ///     FRAC_1_SQRT_2, 0f64, 0f64, -FRAC_1_SQRT_2
+    /// )).unwrap();
+    ///
+    /// assert_eq!(bell_state.to_string(), "0.707|00> - 0.707|11>");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut is_first_term: bool = true;
+        for (state, amp) in self.into_iter() {
+            if amp.norm().into_f64() < ZERO_MARGIN {
+                continue;
+            }
+
+            let coefficient: String = format_coefficient(amp);
+            if is_first_term {
+                write!(f, "{}|{}>", coefficient, state)?;
+                is_first_term = false;
+            } else if let Some(magnitude) = coefficient.strip_prefix('-') {
+                write!(f, " - {}|{}>", magnitude, state)?;
+            } else {
+                write!(f, " + {}|{}>", coefficient, state)?;
+            }
+        }
+
+        if is_first_term {
+            write!(f, "0")?;
+        }
+        Ok(())
+    }
+}
+
+// Formats the amplitude compactly, omitting the real or imaginary part if it is zero.
+fn format_coefficient<T: Float>(amp: Complex<T>) -> String {
+    if amp.im.abs().into_f64() < ZERO_MARGIN {
+        format!("{:.3}", amp.re)
+    } else if amp.re.abs().into_f64() < ZERO_MARGIN {
+        format!("{:.3}i", amp.im)
+    } else {
+        format!("({:.3}{:+.3}i)", amp.re, amp.im)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::circuit::HashMap;
     use crate::states::{ProductState, Qubit, SuperPosition};
-    use crate::{complex_im, complex_re};
+    use crate::{complex_im, complex_re, Circuit, Gate};
     use num_complex::Complex64;
     use std::f64::consts::FRAC_1_SQRT_2;
 
@@ -542,4 +1123,238 @@ mod tests {
             ])
             .unwrap();
     }
+
+    #[test]
+    fn renormalises_half_weight_state() {
+        let mut super_pos = SuperPosition::new_with_amplitudes_unchecked(&[
+            complex_re!(0.5f64),
+            num_complex::Complex64::ZERO,
+            num_complex::Complex64::ZERO,
+            complex_im!(0.5f64),
+        ]);
+
+        let total_probability: f64 = super_pos.renormalise().unwrap();
+
+        assert!((total_probability - 0.5f64).abs() < 1e-10);
+        assert!((super_pos.amplitudes[0].re - FRAC_1_SQRT_2).abs() < 1e-10);
+        assert!((super_pos.amplitudes[3].im - FRAC_1_SQRT_2).abs() < 1e-10);
+    }
+
+    #[test]
+    #[should_panic]
+    fn catches_renormalising_zero_state() {
+        SuperPosition::new_with_amplitudes_unchecked(&[num_complex::Complex64::ZERO; 2])
+            .renormalise()
+            .unwrap();
+    }
+
+    #[test]
+    fn samples_index_from_biased_distribution() {
+        fastrand::seed(0);
+        let super_pos = SuperPosition::new_with_amplitudes(&[
+            complex_re!(0.8f64.sqrt()),
+            num_complex::Complex64::ZERO,
+            num_complex::Complex64::ZERO,
+            complex_re!(0.2f64.sqrt()),
+        ])
+        .unwrap();
+
+        let mut counts: [usize; 4] = [0; 4];
+        for _ in 0..1000 {
+            counts[super_pos.sample_index().unwrap()] += 1;
+        }
+
+        assert_eq!(counts[1] + counts[2], 0);
+        assert!(counts[0] > 750 && counts[0] < 850);
+        assert_eq!(counts[0] + counts[3], 1000);
+    }
+
+    #[test]
+    fn creates_basis_state_from_index() {
+        assert_eq!(
+            SuperPosition::basis_state(2, 2).unwrap(),
+            SuperPosition::from(ProductState::new_unchecked(&[Qubit::One, Qubit::Zero]))
+        );
+        assert!(SuperPosition::basis_state(4, 2).is_err());
+        assert!(SuperPosition::basis_state(0, 0).is_err());
+    }
+
+    #[test]
+    fn controlled_x_matches_cnot() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit
+            .add_gates(&[Gate::H, Gate::Ry(0.7f64), Gate::H])
+            .unwrap();
+        let mut superpos: SuperPosition = circuit.clone_and_simulate().take_state().take();
+
+        circuit.add_gate(Gate::CNot(2), 0).unwrap();
+        superpos.apply_controlled_gate(Gate::X, 2, 0).unwrap();
+
+        assert_eq!(circuit.simulate().take_state().take(), superpos);
+        assert!(superpos.apply_controlled_gate(Gate::CNot(1), 2, 0).is_err());
+        assert!(superpos.apply_controlled_gate(Gate::X, 1, 1).is_err());
+        assert!(superpos.apply_controlled_gate(Gate::X, 3, 0).is_err());
+    }
+
+    #[test]
+    fn pads_qubit_with_zero_states() {
+        let one: SuperPosition = SuperPosition::from(Qubit::One);
+
+        assert_eq!(
+            one.pad_right(1),
+            SuperPosition::from(ProductState::new_unchecked(&[Qubit::One, Qubit::Zero]))
+        );
+        assert_eq!(
+            one.pad_left(1),
+            SuperPosition::from(ProductState::new_unchecked(&[Qubit::Zero, Qubit::One]))
+        );
+        assert_eq!(one.pad_left(0), one);
+    }
+
+    #[test]
+    fn normalises_unnormalised_state() {
+        let mut superpos: SuperPosition =
+            SuperPosition::new_with_amplitudes_unchecked(&[complex_re!(3f64), complex_im!(4f64)]);
+        superpos.normalise().unwrap();
+
+        let total_probability: f64 = superpos.amplitudes.iter().map(|x| x.norm_sqr()).sum();
+        assert!((total_probability - 1f64).abs() < 1e-10);
+        assert!((superpos.amplitudes[0].re - 0.6f64).abs() < 1e-10);
+        assert!(
+            SuperPosition::new_with_amplitudes_unchecked(&[Complex64::ZERO; 2])
+                .normalise()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn canonicalises_global_phase() {
+        let amplitudes = [complex_re!(0.6f64), complex_im!(0.8f64)];
+        let global_phase = Complex64::from_polar(1f64, std::f64::consts::FRAC_PI_3);
+        let mut superpos: SuperPosition = SuperPosition::new_with_amplitudes(&amplitudes).unwrap();
+        let mut rotated_superpos: SuperPosition =
+            SuperPosition::new_with_amplitudes(&amplitudes.map(|amp| amp * global_phase)).unwrap();
+        assert!((superpos.amplitudes[0] - rotated_superpos.amplitudes[0]).norm() > 0.1f64);
+
+        superpos.canonicalise_phase();
+        rotated_superpos.canonicalise_phase();
+
+        for (amp, rotated_amp) in superpos.amplitudes.iter().zip(&rotated_superpos.amplitudes) {
+            assert!((amp - rotated_amp).norm() < 1e-10);
+        }
+        assert!(rotated_superpos.amplitudes[0].im.abs() < 1e-10);
+        assert!(rotated_superpos.amplitudes[0].re > 0f64);
+    }
+
+    #[test]
+    fn fidelity_of_single_qubit_states() {
+        let zero = SuperPosition::from(Qubit::Zero);
+        let one = SuperPosition::from(Qubit::One);
+        let plus = SuperPosition::new_with_amplitudes(&[
+            complex_re!(FRAC_1_SQRT_2),
+            complex_re!(FRAC_1_SQRT_2),
+        ])
+        .unwrap();
+
+        assert!((zero.fidelity(&zero).unwrap() - 1f64).abs() < 1e-10);
+        assert!(zero.fidelity(&one).unwrap().abs() < 1e-10);
+        assert!((zero.fidelity(&plus).unwrap() - 0.5f64).abs() < 1e-10);
+        assert!(zero.fidelity(&zero.pad_left(1)).is_err());
+    }
+
+    #[test]
+    fn tensor_pow_of_plus_state() {
+        let plus = SuperPosition::new_with_amplitudes(&[
+            complex_re!(FRAC_1_SQRT_2),
+            complex_re!(FRAC_1_SQRT_2),
+        ])
+        .unwrap();
+
+        let squared: SuperPosition = plus.tensor_pow(2);
+        assert_eq!(squared.get_num_qubits(), 2);
+        for amp in squared.get_amplitudes() {
+            assert!((amp.re - 0.5f64).abs() < 1e-10 && amp.im.abs() < 1e-10);
+        }
+
+        assert_eq!(plus.tensor_pow(1), plus);
+        assert_eq!(plus.tensor_pow(0).get_amplitudes(), &[complex_re!(1f64)]);
+    }
+
+    #[test]
+    fn round_trips_bell_state_through_sparse_map() {
+        let bell_state = SuperPosition::new_with_amplitudes(&[
+            complex_re!(FRAC_1_SQRT_2),
+            Complex64::ZERO,
+            Complex64::ZERO,
+            complex_re!(FRAC_1_SQRT_2),
+        ])
+        .unwrap();
+
+        let sparse: HashMap<usize, Complex64> = bell_state.to_sparse();
+        assert_eq!(sparse.len(), 2);
+        assert_eq!(SuperPosition::from_sparse(sparse, 2).unwrap(), bell_state);
+
+        assert!(SuperPosition::from_sparse(HashMap::from([(4, complex_re!(1f64))]), 2).is_err());
+        assert!(SuperPosition::from_sparse(HashMap::from([(0, complex_re!(0.5f64))]), 2).is_err());
+    }
+
+    #[test]
+    fn basis_probabilities_sum_to_one() {
+        let superpos: SuperPosition = SuperPosition::new_with_amplitudes(&[
+            complex_re!(0.5f64),
+            complex_im!(0.5f64),
+            complex_re!(-0.5f64),
+            complex_re!(0.5f64),
+        ])
+        .unwrap();
+
+        let total: f64 = superpos.basis_probabilities().map(|(_, p)| p).sum();
+        assert!((total - 1f64).abs() < 1e-10);
+        assert_eq!(superpos.basis_probabilities().count(), 4);
+    }
+
+    #[test]
+    fn displays_bell_state() {
+        let bell_state: SuperPosition = SuperPosition::new_with_amplitudes(&[
+            complex_re!(FRAC_1_SQRT_2),
+            Complex64::ZERO,
+            Complex64::ZERO,
+            complex_im!(FRAC_1_SQRT_2),
+        ])
+        .unwrap();
+        let display: String = bell_state.to_string();
+
+        assert!(display.contains("|00>"));
+        assert!(display.contains("|11>"));
+        assert!(!display.contains("|01>"));
+        assert!(!display.contains("|10>"));
+        assert_eq!(display, "0.707|00> + 0.707i|11>");
+    }
+
+    #[test]
+    fn equal_superposition_matches_hadamard_wall() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_repeating_gate(Gate::H, &[0, 1, 2]).unwrap();
+        let hadamard_wall: SuperPosition = circuit.simulate().take_state().take();
+
+        let superpos: SuperPosition = SuperPosition::equal_superposition(3).unwrap();
+
+        for (amp, expected) in superpos
+            .get_amplitudes()
+            .iter()
+            .zip(hadamard_wall.get_amplitudes())
+        {
+            assert!((amp - expected).norm() < 1e-10);
+        }
+        assert!(SuperPosition::equal_superposition(0).is_err());
+    }
+
+    #[test]
+    fn catches_controlled_measure_reset() {
+        let mut superpos: SuperPosition = SuperPosition::basis_state(3, 2).unwrap();
+        assert!(superpos
+            .apply_controlled_gate(Gate::MeasureReset(0), 0, 1)
+            .is_err());
+        assert_eq!(superpos, SuperPosition::basis_state(3, 2).unwrap());
+    }
 }
@@ -0,0 +1,421 @@
+/*
+* Copyright (c) 2024 Andrew Rowan Barlow. Licensed under the EUPL-1.2
+* or later. You may obtain a copy of the licence at
+* https://joinup.ec.europa.eu/collection/eupl/eupl-text-eupl-12. A copy
+* of the EUPL-1.2 licence in English is given in LICENCE.txt which is
+* found in the root directory of this repository.
+*
+* Author: Andrew Rowan Barlow <a.barlow.dev@gmail.com>
+*/
+
+//! Partial traces and entropies of subsystems of a superposition.
+
+use super::super_positions::ZERO_MARGIN;
+use crate::circuit::QResult;
+use crate::error::QuantrError;
+use crate::states::SuperPosition;
+use crate::Float;
+use core::iter::zip;
+use num_complex::Complex64;
+
+// The maximum number of Jacobi sweeps before the eigenvalues are returned.
+const MAX_SWEEPS: usize = 100;
+
+impl SuperPosition {
+    /// Returns the density matrix, |ψ><ψ|, of the superposition.
+    ///
+    /// This is a dense `2^n × 2^n` matrix, where `n` is the number of qubits, and so should only
+    /// be used for small superpositions. The rows and columns are labelled by the product states
+    /// in the computational basis.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::{Qubit, SuperPosition};
+    /// use quantr::complex_re;
+    ///
+    /// let density_matrix = SuperPosition::from(Qubit::One).density_matrix();
+    ///
+    /// assert_eq!(density_matrix[1][1], complex_re!(1f64));
+    /// assert_eq!(density_matrix[0][0], complex_re!(0f64));
+    /// ```
+    pub fn density_matrix(&self) -> Vec<Vec<Complex64>> {
+        self.amplitudes
+            .iter()
+            .map(|a| self.amplitudes.iter().map(|b| a * b.conj()).collect())
+            .collect()
+    }
+
+    /// Returns the reduced density matrix of the kept qubits, by tracing out the remaining qubits.
+    ///
+    /// Even though the superposition is a pure state, the reduced state of a subsystem is
+    /// generally mixed, and so is returned as a dense `2^k × 2^k` matrix, where `k` is the number
+    /// of kept qubits. The rows and columns are labelled by the product states of the kept qubits,
+    /// in the order that they are given. An error is returned if a qubit is out of bounds, or
+    /// repeated.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::{ProductState, Qubit, SuperPosition};
+    /// use quantr::complex_re;
+    ///
+    /// // |01>
+    /// let superpos = SuperPosition::from(ProductState::new(&[Qubit::Zero, Qubit::One]).unwrap());
+    ///
+    /// // The reduced state of the second qubit is |1><1|.
+    /// let density_matrix = superpos.reduced_density_matrix(&[1]).unwrap();
+    /// assert_eq!(density_matrix[1][1], complex_re!(1f64));
+    /// assert_eq!(density_matrix[0][0], complex_re!(0f64));
+    /// ```
+    pub fn reduced_density_matrix(&self, keep: &[usize]) -> QResult<Vec<Vec<Complex64>>> {
+        self.check_subsystem(keep)?;
+        Ok(self.reduced_density_matrix_unchecked(keep))
+    }
+
+    /// Returns whether the superposition is entangled across at least one single-qubit cut.
+    ///
+    /// For each qubit, the purity, Tr(ρ²), of its reduced state is found. A qubit is separable
+    /// from the rest of the superposition if, and only if, the purity is one. Therefore, the
+    /// superposition is considered entangled if any purity is less than one by more than `tol`.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re_array;
+    /// use std::f64::consts::FRAC_1_SQRT_2;
+    ///
+    /// let bell_state = SuperPosition::new_with_amplitudes(&complex_re_array!(
+    ///     FRAC_1_SQRT_2, 0f64, 0f64, FRAC_1_SQRT_2
+    /// )).unwrap();
+    ///
+    /// assert!(bell_state.is_entangled(1e-10));
+    /// assert!(!SuperPosition::new(2).unwrap().is_entangled(1e-10));
+    /// ```
+    pub fn is_entangled(&self, tol: f64) -> bool {
+        (0..self.product_dim).any(|qubit| !self.is_product_state(qubit, tol))
+    }
+
+    /// Returns the Rényi entropy of order `alpha`, in bits, of the reduced state of the
+    /// partition, S_α = log2(Tr(ρ^α)) / (1 - α).
+    ///
+    /// This is found from the eigenvalues of the reduced density matrix of the partition. As
+    /// `alpha` tends to one, the Rényi entropy tends to the von Neumann entropy, which is returned
+    /// when `alpha` is within [f64::EPSILON] of one. An error is returned if `alpha` is negative,
+    /// or if a qubit of the partition is out of bounds, or repeated.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re_array;
+    /// use std::f64::consts::FRAC_1_SQRT_2;
+    ///
+    /// let bell_state = SuperPosition::new_with_amplitudes(&complex_re_array!(
+    ///     FRAC_1_SQRT_2, 0f64, 0f64, FRAC_1_SQRT_2
+    /// )).unwrap();
+    ///
+    /// let entropy: f64 = bell_state.renyi_entropy(&[0], 2f64).unwrap();
+    /// assert!((entropy - 1f64).abs() < 1e-6);
+    /// ```
+    pub fn renyi_entropy(&self, partition: &[usize], alpha: f64) -> QResult<f64> {
+        self.check_subsystem(partition)?;
+        if alpha < 0f64 {
+            return Err(QuantrError::Other {
+                message: format!(
+                    "The order of the Rényi entropy, {}, must be non-negative.",
+                    alpha
+                ),
+            });
+        }
+
+        if (alpha - 1f64).abs() < f64::EPSILON {
+            return Ok(self.subsystem_entropy_unchecked(partition));
+        }
+
+        let trace: f64 = hermitian_eigenvalues(&self.reduced_density_matrix_unchecked(partition))
+            .into_iter()
+            .filter(|&eigenvalue| eigenvalue > ZERO_MARGIN)
+            .map(|eigenvalue| eigenvalue.powf(alpha))
+            .sum();
+        Ok(trace.log2() / (1f64 - alpha))
+    }
+
+    /// Returns the conditional von Neumann entropy, S(A|B) = S(AB) - S(B), in bits, where A is
+    /// the subsystem of the given qubits, and B is the subsystem of the remaining qubits.
+    ///
+    /// As the superposition is a pure state, S(AB) is zero, and so this is the negative entropy of
+    /// the reduced state of B. A negative conditional entropy indicates that A and B are
+    /// entangled. An error is returned if a qubit is out of bounds, or repeated.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::states::SuperPosition;
+    /// use quantr::complex_re_array;
+    /// use std::f64::consts::FRAC_1_SQRT_2;
+    ///
+    /// let bell_state = SuperPosition::new_with_amplitudes(&complex_re_array!(
+    ///     FRAC_1_SQRT_2, 0f64, 0f64, FRAC_1_SQRT_2
+    /// )).unwrap();
+    ///
+    /// let entropy: f64 = bell_state.conditional_entropy(&[0]).unwrap();
+    /// assert!((entropy + 1f64).abs() < 1e-6);
+    /// ```
+    pub fn conditional_entropy(&self, subsystem_a: &[usize]) -> QResult<f64> {
+        self.check_subsystem(subsystem_a)?;
+        let subsystem_b: Vec<usize> = self.complement_of(subsystem_a);
+        Ok(-self.subsystem_entropy_unchecked(&subsystem_b))
+    }
+
+    // Returns whether the qubit is separable from the rest of the superposition, by checking that
+    // the purity of its reduced state is one, within the tolerance.
+    fn is_product_state(&self, qubit: usize, tol: f64) -> bool {
+        let density_matrix: Vec<Vec<Complex64>> = self.reduced_density_matrix_unchecked(&[qubit]);
+        let purity: f64 = density_matrix
+            .iter()
+            .flatten()
+            .map(|entry| entry.norm_sqr())
+            .sum();
+        1f64 - purity <= tol
+    }
+
+    // Traces out the qubits that are not kept. The kept qubits label the rows and columns of the
+    // matrix in the order that they are given, where the first qubit is the most significant bit.
+    pub(crate) fn reduced_density_matrix_unchecked(&self, keep: &[usize]) -> Vec<Vec<Complex64>> {
+        let traced: Vec<usize> = self.complement_of(keep);
+        let bit = |index: usize, qubit: usize| (index >> (self.product_dim - 1 - qubit)) & 1;
+
+        // The amplitudes are arranged as a matrix with rows labelled by the kept qubits, and
+        // columns labelled by the traced qubits.
+        let mut coefficients: Vec<Vec<Complex64>> =
+            vec![vec![Complex64::ZERO; 1 << traced.len()]; 1 << keep.len()];
+        for (index, amp) in self.amplitudes.iter().enumerate() {
+            let kept_index: usize = keep.iter().fold(0, |acc, &q| (acc << 1) | bit(index, q));
+            let traced_index: usize = traced.iter().fold(0, |acc, &q| (acc << 1) | bit(index, q));
+            coefficients[kept_index][traced_index] = *amp;
+        }
+
+        coefficients
+            .iter()
+            .map(|row| {
+                coefficients
+                    .iter()
+                    .map(|other_row| zip(row, other_row).map(|(a, b)| a * b.conj()).sum())
+                    .collect()
+            })
+            .collect()
+    }
+
+    // The von Neumann entropy, in bits, of the reduced state of the subsystem.
+    pub(crate) fn subsystem_entropy_unchecked(&self, subsystem: &[usize]) -> f64 {
+        hermitian_eigenvalues(&self.reduced_density_matrix_unchecked(subsystem))
+            .into_iter()
+            .filter(|&eigenvalue| eigenvalue > ZERO_MARGIN)
+            .map(|eigenvalue| -eigenvalue * eigenvalue.log2())
+            .sum()
+    }
+
+    // The Shannon entropy, in bits, of the probabilities of measuring each product state.
+    pub(crate) fn measurement_entropy(&self) -> f64 {
+        self.amplitudes
+            .iter()
+            .map(|amp| amp.norm_sqr())
+            .filter(|&probability| probability > ZERO_MARGIN)
+            .map(|probability| -probability * probability.log2())
+            .sum()
+    }
+}
+
+impl<T: Float> SuperPosition<T> {
+    // Checks that the qubits of a subsystem are in range of the superposition, and distinct.
+    pub(crate) fn check_subsystem(&self, qubits: &[usize]) -> QResult<()> {
+        if let Some(out_of_bounds) = qubits.iter().find(|&&q| q >= self.product_dim) {
+            return Err(QuantrError::QubitOutOfBounds {
+                index: *out_of_bounds,
+                num_qubits: self.product_dim,
+                message: format!(
+                    "The qubit, {}, is out of bounds for a superposition of {} qubits.",
+                    out_of_bounds, self.product_dim
+                ),
+            });
+        }
+
+        for (i, qubit) in qubits.iter().enumerate() {
+            if qubits[i + 1..].contains(qubit) {
+                return Err(QuantrError::Other {
+                    message: format!("The qubit, {}, is repeated in the subsystem.", qubit),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns the qubits that are not in the subsystem, in ascending order.
+    pub(crate) fn complement_of(&self, qubits: &[usize]) -> Vec<usize> {
+        (0..self.product_dim)
+            .filter(|q| !qubits.contains(q))
+            .collect()
+    }
+}
+
+// Returns the eigenvalues of a Hermitian matrix in descending order.
+//
+// The matrix, H = X + iY, is embedded into the real symmetric matrix [[X, -Y], [Y, X]], which has
+// the same eigenvalues as H but with each repeated twice. These are found with the cyclic Jacobi
+// method.
+pub(crate) fn hermitian_eigenvalues(matrix: &[Vec<Complex64>]) -> Vec<f64> {
+    let dim: usize = matrix.len();
+    let mut real_matrix: Vec<Vec<f64>> = vec![vec![0f64; 2 * dim]; 2 * dim];
+    for (i, row) in matrix.iter().enumerate() {
+        for (j, entry) in row.iter().enumerate() {
+            real_matrix[i][j] = entry.re;
+            real_matrix[i + dim][j + dim] = entry.re;
+            real_matrix[i][j + dim] = -entry.im;
+            real_matrix[i + dim][j] = entry.im;
+        }
+    }
+
+    let mut eigenvalues: Vec<f64> = symmetric_eigenvalues(real_matrix);
+    eigenvalues.sort_by(|a, b| b.total_cmp(a));
+    eigenvalues.into_iter().step_by(2).collect()
+}
+
+fn symmetric_eigenvalues(mut matrix: Vec<Vec<f64>>) -> Vec<f64> {
+    let dim: usize = matrix.len();
+    for _ in 0..MAX_SWEEPS {
+        let off_diagonal: f64 = (0..dim)
+            .flat_map(|p| (0..dim).filter(move |&q| q != p).map(move |q| (p, q)))
+            .map(|(p, q)| matrix[p][q] * matrix[p][q])
+            .sum();
+        if off_diagonal < f64::EPSILON * f64::EPSILON {
+            break;
+        }
+
+        for p in 0..dim {
+            for q in p + 1..dim {
+                if matrix[p][q] == 0f64 {
+                    continue;
+                }
+                // Rotates in the (p, q) plane so that the (p, q) entry vanishes.
+                let theta: f64 = (matrix[q][q] - matrix[p][p]) / (2f64 * matrix[p][q]);
+                let tan: f64 = theta.signum() / (theta.abs() + (theta * theta + 1f64).sqrt());
+                let cos: f64 = 1f64 / (tan * tan + 1f64).sqrt();
+                let sin: f64 = tan * cos;
+                for row in matrix.iter_mut() {
+                    let (kp, kq) = (row[p], row[q]);
+                    row[p] = cos * kp - sin * kq;
+                    row[q] = sin * kp + cos * kq;
+                }
+                let (upper_rows, lower_rows) = matrix.split_at_mut(q);
+                for (pk, qk) in zip(upper_rows[p].iter_mut(), lower_rows[0].iter_mut()) {
+                    (*pk, *qk) = (cos * *pk - sin * *qk, sin * *pk + cos * *qk);
+                }
+            }
+        }
+    }
+
+    (0..dim).map(|i| matrix[i][i]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hermitian_eigenvalues;
+    use crate::states::SuperPosition;
+    use crate::{complex_re, complex_re_array};
+    use num_complex::{c64, Complex64};
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    #[test]
+    fn finds_eigenvalues_of_hermitian_matrix() {
+        // The Pauli-Y matrix has eigenvalues of 1 and -1.
+        let pauli_y: Vec<Vec<Complex64>> = vec![
+            vec![Complex64::ZERO, c64(0f64, -1f64)],
+            vec![c64(0f64, 1f64), Complex64::ZERO],
+        ];
+        let eigenvalues: Vec<f64> = hermitian_eigenvalues(&pauli_y);
+        assert!((eigenvalues[0] - 1f64).abs() < 1e-10);
+        assert!((eigenvalues[1] + 1f64).abs() < 1e-10);
+
+        let diagonal: Vec<Vec<Complex64>> = vec![
+            vec![complex_re!(0.25f64), Complex64::ZERO],
+            vec![Complex64::ZERO, complex_re!(0.75f64)],
+        ];
+        let eigenvalues: Vec<f64> = hermitian_eigenvalues(&diagonal);
+        assert!((eigenvalues[0] - 0.75f64).abs() < 1e-10);
+        assert!((eigenvalues[1] - 0.25f64).abs() < 1e-10);
+    }
+
+    #[test]
+    fn density_matrix_of_zero_state() {
+        let density_matrix = SuperPosition::new(1).unwrap().density_matrix();
+        assert_eq!(
+            density_matrix,
+            vec![
+                vec![complex_re!(1f64), Complex64::ZERO],
+                vec![Complex64::ZERO, Complex64::ZERO]
+            ]
+        );
+
+        let trace: Complex64 = (0..2).map(|i| density_matrix[i][i]).sum();
+        assert_eq!(trace, complex_re!(1f64));
+    }
+
+    #[test]
+    fn reduced_density_matrix_of_bell_state() {
+        let bell_state: SuperPosition = SuperPosition::new_with_amplitudes(&complex_re_array!(
+            FRAC_1_SQRT_2,
+            0f64,
+            0f64,
+            FRAC_1_SQRT_2
+        ))
+        .unwrap();
+
+        for qubit in [0, 1] {
+            let density_matrix = bell_state.reduced_density_matrix(&[qubit]).unwrap();
+            assert!((density_matrix[0][0] - complex_re!(0.5f64)).norm() < 1e-10);
+            assert!((density_matrix[1][1] - complex_re!(0.5f64)).norm() < 1e-10);
+            assert!(density_matrix[0][1].norm() < 1e-10);
+            assert!(density_matrix[1][0].norm() < 1e-10);
+        }
+
+        assert!(bell_state.reduced_density_matrix(&[2]).is_err());
+        assert!(bell_state.reduced_density_matrix(&[0, 0]).is_err());
+    }
+
+    #[test]
+    fn detects_entangled_states() {
+        let bell_state: SuperPosition = SuperPosition::new_with_amplitudes(&complex_re_array!(
+            FRAC_1_SQRT_2,
+            0f64,
+            0f64,
+            FRAC_1_SQRT_2
+        ))
+        .unwrap();
+        assert!(bell_state.is_entangled(1e-10));
+
+        // |+>|1>
+        let product_state: SuperPosition = SuperPosition::new_with_amplitudes(&complex_re_array!(
+            0f64,
+            FRAC_1_SQRT_2,
+            0f64,
+            FRAC_1_SQRT_2
+        ))
+        .unwrap();
+        assert!(!product_state.is_entangled(1e-10));
+    }
+
+    #[test]
+    fn conditional_entropy_of_bell_state() {
+        let bell_state: SuperPosition = SuperPosition::new_with_amplitudes(&complex_re_array!(
+            FRAC_1_SQRT_2,
+            0f64,
+            0f64,
+            FRAC_1_SQRT_2
+        ))
+        .unwrap();
+        assert!((bell_state.conditional_entropy(&[0]).unwrap() + 1f64).abs() < 1e-6);
+        assert!(bell_state.conditional_entropy(&[2]).is_err());
+        assert!(bell_state.conditional_entropy(&[1, 1]).is_err());
+
+        // The whole state has no conditional entropy, as B is empty.
+        assert!(bell_state.conditional_entropy(&[0, 1]).unwrap().abs() < 1e-6);
+    }
+}
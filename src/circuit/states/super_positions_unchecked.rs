@@ -8,9 +8,9 @@
 * Author: Andrew Rowan Barlow <a.barlow.dev@gmail.com>
 */
 use crate::circuit::HashMap;
-use crate::complex_re;
 use crate::states::{ProductState, SuperPosition};
-use num_complex::Complex64;
+use crate::Float;
+use num_complex::{Complex, Complex64};
 
 impl SuperPosition {
     pub(crate) fn new_with_hash_amplitudes_unchecked(
@@ -36,29 +36,6 @@ impl SuperPosition {
         }
     }
 
-    pub(crate) fn new_unchecked(num_qubits: usize) -> SuperPosition {
-        let mut new_amps: Vec<Complex64> = vec![num_complex::Complex64::ZERO; 1 << num_qubits];
-        new_amps[0] = complex_re!(1f64);
-        SuperPosition {
-            amplitudes: new_amps,
-            product_dim: num_qubits,
-        }
-    }
-
-    /// Sets the amplitudes of a [SuperPosition] from a HashMap, **without** check on conservation of
-    /// probability.
-    pub(crate) fn set_amplitudes_from_states_unchecked(
-        &mut self,
-        mut hash_amplitudes: HashMap<ProductState, Complex64>,
-    ) -> &mut SuperPosition {
-        for (i, amp) in self.amplitudes.iter_mut().enumerate() {
-            *amp = hash_amplitudes
-                .remove(&ProductState::binary_basis(i, self.product_dim))
-                .unwrap_or(complex_re!(0f64));
-        }
-        self
-    }
-
     /// Same as [SuperPosition::new_with_amplitudes], but **without** checks on dimension size being a
     /// power of two and the conservation of probability.
     pub fn new_with_amplitudes_unchecked(amplitudes: &[Complex64]) -> SuperPosition {
@@ -76,3 +53,27 @@ impl SuperPosition {
         self
     }
 }
+
+impl<T: Float> SuperPosition<T> {
+    pub(crate) fn new_unchecked(num_qubits: usize) -> SuperPosition<T> {
+        let mut new_amps: Vec<Complex<T>> = vec![Complex::ZERO; 1 << num_qubits];
+        new_amps[0] = Complex::new(T::one(), T::zero());
+        SuperPosition {
+            amplitudes: new_amps,
+            product_dim: num_qubits,
+        }
+    }
+
+    /// Sets the amplitudes of a [SuperPosition] from a HashMap of the indices of the product
+    /// states in the computational basis, **without** check on conservation of probability.
+    pub(crate) fn set_amplitudes_from_indices_unchecked(
+        &mut self,
+        hash_amplitudes: HashMap<usize, Complex<T>>,
+    ) -> &mut SuperPosition<T> {
+        self.amplitudes.fill(Complex::ZERO);
+        for (i, amp) in hash_amplitudes {
+            self.amplitudes[i] = amp;
+        }
+        self
+    }
+}
@@ -8,7 +8,12 @@
 * Author: Andrew Rowan Barlow <a.barlow.dev@gmail.com>
 */
 
-//! Macros for the num_complex crate.
+//! Macros for the num_complex crate, and the floating point types that the amplitudes of a
+//! register can be stored in.
+
+use num_complex::Complex;
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
 
 /// Usage: `complex_re_array!(input: [f64; n]) -> [Complex<f64>; n]`
 /// Returns an array of complex numbers with zero imaginary part, and the real part set by `input`.
@@ -87,3 +92,47 @@ macro_rules! complex_im {
         $crate::num_complex::Complex64 { re: 0f64, im: $i }
     };
 }
+
+/// The floating point type of the real and imaginary parts of the amplitudes of a
+/// [crate::states::SuperPosition], which sets the precision of a simulation.
+///
+/// This is implemented for `f64`, which is the default throughout quantr, and `f32` which halves
+/// the memory of the register at the cost of precision. The gates are always defined with `f64`
+/// amplitudes, which are converted to the precision of the register when they are applied.
+pub trait Float:
+    num_traits::Float + num_traits::ConstZero + Default + Debug + Display + Sum + Send + Sync + 'static
+{
+    /// Converts from an `f64`, rounding to the nearest value if the precision is lower.
+    fn from_f64(value: f64) -> Self;
+
+    /// Converts into an `f64`.
+    fn into_f64(self) -> f64;
+}
+
+impl Float for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn into_f64(self) -> f64 {
+        self
+    }
+}
+
+impl Float for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn into_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+// Converts a complex number to the precision of `U`, via `f64`.
+pub(crate) fn to_precision<T: Float, U: Float>(amp: Complex<T>) -> Complex<U> {
+    Complex::new(
+        U::from_f64(amp.re.into_f64()),
+        U::from_f64(amp.im.into_f64()),
+    )
+}
@@ -14,13 +14,56 @@ use std::error::Error;
 use std::fmt;
 
 /// Relays error messages resulting from quantr.
-pub struct QuantrError {
-    pub(crate) message: String,
+///
+/// The variants allow errors to be handled programmatically, while the [fmt::Display]
+/// implementation gives the message of the variant, which describes the error in the context that
+/// it occurred.
+///
+/// # Example
+/// ```
+/// use quantr::{Circuit, Gate, QuantrError};
+///
+/// let mut circuit = Circuit::new(2).unwrap();
+///
+/// match circuit.add_gate(Gate::X, 2) {
+///     Err(QuantrError::QubitOutOfBounds { index, num_qubits, .. }) => {
+///         assert_eq!((index, num_qubits), (2, 2));
+///     }
+///     _ => panic!("The position should be out of bounds."),
+/// }
+/// ```
+pub enum QuantrError {
+    /// A qubit, or wire, is out of bounds for the number of qubits.
+    QubitOutOfBounds {
+        index: usize,
+        num_qubits: usize,
+        message: String,
+    },
+    /// The control nodes of a gate overlap with each other, or with the position of the gate.
+    OverlappingControlNodes { gate: String, message: String },
+    /// The absolute square sum of the amplitudes, `total`, does not equal one.
+    ProbabilityNotConserved { total: f64, message: String },
+    /// A dimension, such as a number of qubits or the length of a slice, does not match the
+    /// expected dimension.
+    DimensionMismatch {
+        expected: usize,
+        found: usize,
+        message: String,
+    },
+    /// Any other incorrect use of quantr, which is described by the message.
+    Other { message: String },
 }
 
 impl fmt::Display for QuantrError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "\x1b[91m[Quantr Error] {}\x1b[0m ", self.message)
+        let message: &String = match self {
+            QuantrError::QubitOutOfBounds { message, .. }
+            | QuantrError::OverlappingControlNodes { message, .. }
+            | QuantrError::ProbabilityNotConserved { message, .. }
+            | QuantrError::DimensionMismatch { message, .. }
+            | QuantrError::Other { message } => message,
+        };
+        write!(f, "\x1b[91m[Quantr Error] {}\x1b[0m ", message)
     }
 }
 
@@ -31,3 +74,12 @@ impl fmt::Debug for QuantrError {
 }
 
 impl Error for QuantrError {}
+
+// Prints the warning to stderr, unless warnings are suppressed. Returns whether the warning was
+// printed.
+pub(crate) fn print_warning(message: &str, suppress: bool) -> bool {
+    if !suppress {
+        eprintln!("\x1b[93m[Quantr Warning] {}\x1b[0m", message);
+    }
+    !suppress
+}
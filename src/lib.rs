@@ -78,8 +78,9 @@ mod simulated_circuit;
 pub extern crate num_complex;
 
 //  Make available for public use.
-pub use circuit::gate::Gate;
+pub use circuit::gate::{Gate, GateFamily, GateKind, ParamId};
 pub use circuit::printer::Printer;
-pub use circuit::{measurement::Measurement, states, Circuit};
+pub use circuit::{measurement::Measurement, states, Circuit, Circuit32, Circuit64};
+pub use complex::Float;
 pub use error::QuantrError;
-pub use simulated_circuit::SimulatedCircuit;
+pub use simulated_circuit::{ComparisonReport, PauliBasis, SimulatedCircuit};
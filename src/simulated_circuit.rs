@@ -8,25 +8,63 @@
 * Author: Andrew Rowan Barlow <a.barlow.dev@gmail.com>
 */
 
+use crate::circuit::gate::GateCategory;
+use crate::circuit::QResult;
+use crate::error::{print_warning, QuantrError};
 use crate::{
-    complex_re,
-    states::{ProductState, SuperPosition},
+    states::{ProductState, Qubit, SuperPosition},
     Measurement,
 };
-use crate::{Circuit, Gate};
+use crate::{Circuit, Float, Gate};
+use core::iter::zip;
+use fastrand::Rng;
+use num_complex::Complex;
 use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Measures of how close the resulting superposition of a simulated circuit is to an ideal state.
+///
+/// Produced by [SimulatedCircuit::compare_to_ideal].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComparisonReport {
+    /// The fidelity, |<ideal|simulated>|², of the two pure states.
+    pub fidelity: f64,
+    /// The trace distance between the two pure states, sqrt(1 - fidelity).
+    pub trace_distance: f64,
+    /// The total variation distance between the probability distributions of measuring the two
+    /// states in the computational basis.
+    pub total_variation_distance: f64,
+}
+
+/// The Pauli basis that a qubit is measured in, see [SimulatedCircuit::measure_all_in_basis].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PauliBasis {
+    /// The eigenbasis of Pauli-X, |+> and |->, which are observed as |0> and |1> respectively.
+    X,
+    /// The eigenbasis of Pauli-Y, |+i> and |-i>, which are observed as |0> and |1> respectively.
+    Y,
+    /// The computational basis, |0> and |1>.
+    Z,
+}
 
 /// Contains the resulting state vector produced from the simulation of a circuit.
-pub struct SimulatedCircuit {
+///
+/// The amplitudes of the state vector have the precision of `T`, which is inherited from the
+/// circuit that was simulated.
+pub struct SimulatedCircuit<T: Float = f64> {
     // Copy of Circuit struct but removed the wrapper around register.
     pub(crate) circuit_gates: Vec<Gate>,
     pub(crate) num_qubits: usize,
-    pub(crate) register: SuperPosition,
+    pub(crate) register: SuperPosition<T>,
     pub(crate) config_progress: bool,
     pub(super) disable_warnings: bool,
+    pub(crate) seed: Option<u64>,
+    pub(crate) reset_register: Option<SuperPosition<T>>,
+    pub(crate) measurements: Vec<(usize, Qubit)>,
+    pub(crate) categorised_gates: OnceLock<Vec<GateCategory>>,
 }
 
-impl SimulatedCircuit {
+impl<T: Float> SimulatedCircuit<T> {
     /// Returns a `HashMap` that contains the number of times the corresponding state was observed over
     /// `n` measurements of the superpositions (shots).
     ///
@@ -62,70 +100,423 @@ impl SimulatedCircuit {
     /// ```
     pub fn measure_all(&self, shots: usize) -> Measurement<HashMap<ProductState, usize>> {
         let mut bin_count: HashMap<ProductState, usize> = Default::default();
-        if self.circuit_gates.iter().any(|x| x.is_custom_gate()) && !self.disable_warnings {
-            eprintln!("\x1b[93m[Quantr Warning] Custom gates were detected in the circuit. Measurements will be taken from a cached register in memory, and so if the Custom gate does NOT implement a unitary mapping, the measure_all method will most likely lead to wrong results. To simulate a circuit without cache, see SimulatedCircuit::measure_all_without_cache.\x1b[0m")
+        if self.circuit_gates.iter().any(|x| x.is_custom_gate()) {
+            print_warning("Custom gates were detected in the circuit. Measurements will be taken from a cached register in memory, and so if the Custom gate does NOT implement a unitary mapping, the measure_all method will most likely lead to wrong results. To simulate a circuit without cache, see SimulatedCircuit::measure_all_without_cache.", self.disable_warnings);
         }
 
+        let mut rng: Rng = self.rng();
         for _ in 0..shots {
-            self.add_to_bin(&mut bin_count);
+            self.add_to_bin(&self.register, &mut bin_count, &mut rng);
         }
         Measurement::Observable(bin_count)
     }
 
+    /// Returns an iterator that measures the cached register on each call of `next`, yielding the
+    /// observed product state.
+    ///
+    /// Each measurement is independent, and is reproducible if a seed is given by
+    /// [SimulatedCircuit::set_seed]. This allows for any
+    /// number of shots to be streamed into a custom accumulator, without allocating the bin count
+    /// of [SimulatedCircuit::measure_all]. The iterator is unbounded, unless the register does not
+    /// conserve probability and fails to collapse to a state, in which case it ends.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::X, 1).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// for state in simulated_circuit.sample_iter().take(10) {
+    ///     assert_eq!(state.to_string(), "01");
+    /// }
+    /// ```
+    pub fn sample_iter(&self) -> impl Iterator<Item = ProductState> + '_ {
+        let mut rng: Rng = self.rng();
+        std::iter::from_fn(move || {
+            self.register
+                .sample_index_from_roll(rng.f64())
+                .map(|index| ProductState::binary_basis(index, self.num_qubits))
+        })
+    }
+
+    /// Returns a `HashMap` that contains the number of times the corresponding state of the given
+    /// wires was observed over `n` measurements (shots).
+    ///
+    /// This is the same as [SimulatedCircuit::measure_all], however each observed state is
+    /// reduced to the qubits of the given wires, in the order that they are given. The remaining
+    /// wires are ignored. An error is returned if a wire is out of bounds, or repeated.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(3).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// // Only the first and second wires are binned, so all shots observe |10>.
+    /// let bin_count = simulated_circuit.measure_qubits(&[0, 1], 100).unwrap().take();
+    /// for (state, count) in bin_count {
+    ///     assert_eq!(state.to_string(), "10");
+    ///     assert_eq!(count, 100);
+    /// }
+    /// ```
+    pub fn measure_qubits(
+        &self,
+        qubits: &[usize],
+        shots: usize,
+    ) -> QResult<Measurement<HashMap<ProductState, usize>>> {
+        self.register.check_subsystem(qubits)?;
+
+        let mut bin_count: HashMap<ProductState, usize> = Default::default();
+        let mut rng: Rng = self.rng();
+        for _ in 0..shots {
+            if let Some(index) = self.register.sample_index_from_roll(rng.f64()) {
+                let observed: Vec<Qubit> = qubits
+                    .iter()
+                    .map(|&q| match (index >> (self.num_qubits - 1 - q)) & 1 {
+                        0 => Qubit::Zero,
+                        _ => Qubit::One,
+                    })
+                    .collect();
+                *bin_count
+                    .entry(ProductState::new_unchecked(&observed))
+                    .or_insert(0) += 1;
+            }
+        }
+        Ok(Measurement::Observable(bin_count))
+    }
+
+    /// Returns the observed states over `n` measurements (shots) together with the number of times
+    /// each was observed, and the empirical probability `count/shots` of observing it.
+    ///
+    /// The states are sorted in increasing order of the computational basis, and only states that
+    /// were observed at least once are included. The measurements are taken in the same way as
+    /// [SimulatedCircuit::measure_all].
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// for (state, count, probability) in simulated_circuit.measurement_report(100) {
+    ///     println!("|{}> : {} ({})", state, count, probability);
+    /// }
+    ///
+    /// // |10> : 100 (1)
+    /// ```
+    pub fn measurement_report(&self, shots: usize) -> Vec<(ProductState, usize, f64)> {
+        let mut report: Vec<(ProductState, usize, f64)> = self
+            .measure_all(shots)
+            .take()
+            .into_iter()
+            .map(|(state, count)| (state, count, count as f64 / shots as f64))
+            .collect();
+        report.sort_by_key(|(state, _, _)| state.comp_basis());
+        report
+    }
+
+    /// Returns an ASCII histogram of `n` measurements (shots) of the resulting superposition,
+    /// sampled from a generator with the given seed.
+    ///
+    /// Each observed state is given a line, sorted in increasing order of the computational
+    /// basis, with a bar of `#` characters that is proportional to the number of times it was
+    /// observed. A state observed in every shot has a bar of 50 characters. States that were not
+    /// observed are omitted.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// println!("{}", simulated_circuit.histogram_string(1000, 42));
+    ///
+    /// // |00> ######################### 497
+    /// // |10> ######################### 503
+    /// ```
+    pub fn histogram_string(&self, shots: usize, seed: u64) -> String {
+        const BAR_WIDTH: usize = 50;
+
+        let mut bin_count: HashMap<usize, usize> = Default::default();
+        let mut rng: Rng = Rng::with_seed(seed);
+        for _ in 0..shots {
+            if let Some(index) = self.register.sample_index_from_roll(rng.f64()) {
+                *bin_count.entry(index).or_insert(0) += 1;
+            }
+        }
+
+        let mut bins: Vec<(usize, usize)> = bin_count.into_iter().collect();
+        bins.sort_unstable();
+
+        let mut histogram: String = String::new();
+        for (index, count) in bins {
+            let bar_length: usize = ((count * BAR_WIDTH) as f64 / shots as f64).round() as usize;
+            histogram.push_str(&format!(
+                "|{}> {} {}\n",
+                ProductState::binary_basis(index, self.num_qubits),
+                "#".repeat(bar_length),
+                count
+            ));
+        }
+        histogram
+    }
+
+    /// Returns the sample mean and variance of `f` over `n` measurements (shots) of the resulting
+    /// superposition, in that order.
+    ///
+    /// This estimates the expectation value of an observable that is diagonal in the
+    /// computational basis, with the variance giving its error. The variance is the unbiased
+    /// estimator, and is zero if fewer than two shots are taken. The measurements are taken in the
+    /// same way as [SimulatedCircuit::measure_all].
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{ProductState, Qubit};
+    ///
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// // The eigenvalue of Pauli-Z for the observed state.
+    /// let pauli_z = |state: &ProductState| match state.get_qubits()[0] {
+    ///     Qubit::Zero => 1f64,
+    ///     Qubit::One => -1f64,
+    /// };
+    ///
+    /// let (mean, variance) = simulated_circuit.sampled_statistics(1000, pauli_z);
+    /// println!("<Z> = {} ± {}", mean, (variance / 1000f64).sqrt());
+    /// ```
+    pub fn sampled_statistics<F: Fn(&ProductState) -> f64>(
+        &self,
+        shots: usize,
+        f: F,
+    ) -> (f64, f64) {
+        let samples: Vec<(f64, usize)> = self
+            .measure_all(shots)
+            .take()
+            .iter()
+            .map(|(state, &count)| (f(state), count))
+            .collect();
+
+        let mean: f64 = samples
+            .iter()
+            .map(|(value, count)| value * *count as f64)
+            .sum::<f64>()
+            / shots as f64;
+
+        let variance: f64 = if shots < 2 {
+            0f64
+        } else {
+            samples
+                .iter()
+                .map(|(value, count)| (value - mean).powi(2) * *count as f64)
+                .sum::<f64>()
+                / (shots - 1) as f64
+        };
+
+        (mean, variance)
+    }
+
+    /// Samples a single qubit of the resulting superposition, returning the observed qubit and
+    /// the marginal probability of observing |1> on that wire.
+    ///
+    /// The remaining qubits are not observed. An error is returned if `qubit` is not a wire of
+    /// the circuit.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::H, 1).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// let (observed, probability_one) = simulated_circuit.sample_qubit(1).unwrap();
+    ///
+    /// println!("Observed {:?}, with probability of |1> being {}", observed, probability_one);
+    /// ```
+    pub fn sample_qubit(&self, qubit: usize) -> QResult<(Qubit, f64)> {
+        if qubit >= self.num_qubits {
+            return Err(QuantrError::QubitOutOfBounds {
+                index: qubit,
+                num_qubits: self.num_qubits,
+                message: format!(
+                    "The qubit, {}, is out of bounds for the circuit with {} qubits.",
+                    qubit, self.num_qubits
+                ),
+            });
+        }
+
+        let shift: usize = self.num_qubits - 1 - qubit;
+        let probability_one: f64 = self
+            .register
+            .amplitudes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (i >> shift) & 1 == 1)
+            .map(|(_, amp)| amp.norm_sqr().into_f64())
+            .sum();
+
+        let observed: Qubit = if self.rng().f64() < probability_one {
+            Qubit::One
+        } else {
+            Qubit::Zero
+        };
+        Ok((observed, probability_one))
+    }
+
+    /// Returns the exact expectation value of the tensor product of Pauli-Z operators on the given
+    /// wires, ⟨ψ|Z⊗...⊗Z|ψ⟩, for the resulting superposition |ψ>.
+    ///
+    /// This is calculated directly from the amplitudes, where each state in the computational
+    /// basis contributes its probability weighted by the parity of the qubits on the given wires.
+    /// An error is returned if a wire is out of bounds, or repeated.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// assert_eq!(simulated_circuit.expectation_z(&[0]).unwrap(), -1f64);
+    /// assert_eq!(simulated_circuit.expectation_z(&[1]).unwrap(), 1f64);
+    /// ```
+    pub fn expectation_z(&self, qubits: &[usize]) -> QResult<f64> {
+        self.register.check_subsystem(qubits)?;
+
+        let mask: usize = qubits
+            .iter()
+            .fold(0, |acc, &q| acc | (1 << (self.num_qubits - 1 - q)));
+        Ok(self
+            .register
+            .amplitudes
+            .iter()
+            .enumerate()
+            .map(|(i, amp)| match (i & mask).count_ones() % 2 {
+                0 => amp.norm_sqr().into_f64(),
+                _ => -amp.norm_sqr().into_f64(),
+            })
+            .sum())
+    }
+
     /// Similar to [SimulatedCircuit::measure_all], however for every shot it will simulate the
-    /// circuit, where the input register is reset to the zero state.
+    /// circuit, where the input register is reset to the zero state, or to the register given by
+    /// [SimulatedCircuit::set_reset_register].
     ///
     /// This _potentially_ allows for mixed states to be simulated, through the implementation of
-    /// [Gate::Custom]. In doing so will dramatically increase the simulation time, as a new
-    /// circuit will be simulated for each shot.
+    /// [Gate::Custom]. In doing so will dramatically increase the simulation time, as the
+    /// circuit will be simulated again for each shot. The register and the linked gates are
+    /// reused between shots, so that only the gates themselves are reapplied.
     pub fn measure_all_without_cache(
-        self,
+        mut self,
         shots: usize,
     ) -> Measurement<HashMap<ProductState, usize>> {
         let mut bin_count: HashMap<ProductState, usize> = Default::default();
-        let mut simulated_circ = self;
-        simulated_circ.add_to_bin(&mut bin_count);
-        if simulated_circ.config_progress {
-            println!("Measured state # 1/{}", shots);
-        }
-        for i in 0..shots - 1 {
-            // reset to |0> register
-            simulated_circ
-                .register
-                .amplitudes
-                .fill(num_complex::Complex64::ZERO);
-            simulated_circ.register.amplitudes[0] = complex_re!(1f64);
-            if simulated_circ.config_progress {
-                println!("Register reset to zero state")
+        let mut rng: Rng = self.rng();
+        let categorised_gates: &[GateCategory] =
+            Circuit::link_gates(&self.categorised_gates, &self.circuit_gates);
+        for i in 0..shots {
+            // The first shot is taken from the simulated register, unless a reset register is set.
+            if i > 0 || self.reset_register.is_some() {
+                match &self.reset_register {
+                    Some(reset_register) => {
+                        self.register
+                            .amplitudes
+                            .copy_from_slice(&reset_register.amplitudes);
+                        if self.config_progress {
+                            println!("Register reset to the reset register")
+                        }
+                    }
+                    None => {
+                        self.register.amplitudes.fill(Complex::ZERO);
+                        self.register.amplitudes[0] = Complex::new(T::one(), T::zero());
+                        if self.config_progress {
+                            println!("Register reset to zero state")
+                        }
+                    }
+                }
+                if let Err(error) = Circuit::simulate_gates(
+                    &self.circuit_gates,
+                    categorised_gates,
+                    self.num_qubits,
+                    self.config_progress,
+                    &mut rng,
+                    &mut self.register,
+                ) {
+                    panic!("{}", error)
+                }
             }
-            let circuit = Circuit {
-                circuit_gates: simulated_circ.circuit_gates,
-                num_qubits: simulated_circ.num_qubits,
-                register: Some(simulated_circ.register),
-                config_progress: simulated_circ.config_progress,
-            };
-            simulated_circ = circuit.simulate();
-            simulated_circ.add_to_bin(&mut bin_count);
-            if simulated_circ.config_progress {
-                println!("Measured state # {}/{}", i + 2, shots);
+            self.add_to_bin(&self.register, &mut bin_count, &mut rng);
+            if self.config_progress {
+                println!("Measured state # {}/{}", i + 1, shots);
             }
         }
         Measurement::Observable(bin_count)
     }
 
-    fn add_to_bin(&self, bin: &mut HashMap<ProductState, usize>) {
-        match self.register.measure() {
-            Some(state) => {
+    /// Sets the register that [SimulatedCircuit::measure_all_without_cache] resets to before
+    /// simulating each shot, in place of the zero state.
+    ///
+    /// This allows for the statistics of a fixed input state to be studied across shots. An error
+    /// is returned if the number of qubits of the register does not match the circuit.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{ProductState, Qubit};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::CNot(0), 1).unwrap();
+    /// let mut simulated_circuit = circuit.simulate();
+    /// simulated_circuit
+    ///     .set_reset_register(ProductState::new(&[Qubit::One, Qubit::Zero]).unwrap().into())
+    ///     .unwrap();
+    ///
+    /// let bin_count = simulated_circuit.measure_all_without_cache(10).take();
+    /// assert_eq!(bin_count[&ProductState::new(&[Qubit::One, Qubit::One]).unwrap()], 10);
+    /// ```
+    pub fn set_reset_register(&mut self, reg: SuperPosition<T>) -> QResult<()> {
+        if reg.product_dim != self.num_qubits {
+            return Err(QuantrError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: reg.product_dim,
+                message: format!("The reset register has a product state dimension of {}, while the number of qubits is {}. These must equal each other.", reg.product_dim, self.num_qubits),
+            });
+        }
+
+        self.reset_register = Some(reg);
+        Ok(())
+    }
+
+    fn add_to_bin(
+        &self,
+        register: &SuperPosition<T>,
+        bin: &mut HashMap<ProductState, usize>,
+        rng: &mut Rng,
+    ) {
+        match register.sample_index_from_roll(rng.f64()) {
+            Some(index) => {
+                let state: ProductState = ProductState::binary_basis(index, self.num_qubits);
                 bin.entry(state)
                     .and_modify(|count| {
-                        *count = *count + 1;
+                        *count += 1;
                     })
                     .or_insert(1);
             }
-            None if !self.disable_warnings => {
-                eprintln!("\x1b[93m[Quantr Warning] The superposition failed to collapse to a state during repeat measurements. This is likely due to the use of Gate::Custom where the mapping is not unitary.\x1b[0m")
+            None => {
+                print_warning("The superposition failed to collapse to a state during repeat measurements. This is likely due to the use of Gate::Custom where the mapping is not unitary.", self.disable_warnings);
             }
-            None => {}
         }
     }
 
@@ -155,10 +546,217 @@ impl SimulatedCircuit {
     /// // |000> : 0 - 0.71...i
     /// // |001> : 0 + 0.71...i
     /// ```
-    pub fn get_state(&self) -> Measurement<&SuperPosition> {
+    pub fn get_state(&self) -> Measurement<&SuperPosition<T>> {
         Measurement::NonObservable(&self.register)
     }
 
+    /// Returns the amplitude of the product state in the resulting superposition.
+    ///
+    /// An error is returned if the product state does not have the same number of qubits as the
+    /// circuit.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, complex_re};
+    /// use quantr::states::{ProductState, Qubit};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::X, 1).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// let state = ProductState::new(&[Qubit::Zero, Qubit::One]).unwrap();
+    /// assert_eq!(simulated_circuit.amplitude_of(&state).unwrap(), complex_re!(1f64));
+    /// ```
+    pub fn amplitude_of(&self, state: &ProductState) -> QResult<Complex<T>> {
+        if state.num_qubits() != self.num_qubits {
+            return Err(QuantrError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: state.num_qubits(),
+                message: format!("The state has {} qubits, while the circuit has {} qubits. These must equal each other.", state.num_qubits(), self.num_qubits),
+            });
+        }
+
+        Ok(self.register.amplitudes[state.comp_basis()])
+    }
+
+    /// Returns the probabilities of measuring each state of the resulting superposition, ordered in
+    /// the computational basis.
+    ///
+    /// The i-th entry is the probability of measuring the i-th product state, and so the length of
+    /// the vector is `2^num_qubits`. These are found exactly from the amplitudes, without sampling.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// assert_eq!(simulated_circuit.probabilities(), vec![0f64, 1f64]);
+    /// ```
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.register
+            .amplitudes
+            .iter()
+            .map(|a| a.norm_sqr().into_f64())
+            .collect()
+    }
+
+    /// Consumes the simulated circuit, returning the probability of measuring each product state.
+    ///
+    /// This is the same as [SimulatedCircuit::probabilities], however keyed by [ProductState] and
+    /// without cloning the register. States that have zero probability are not included.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{ProductState, Qubit};
+    ///
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap();
+    /// let probabilities = circuit.simulate().into_probabilities();
+    ///
+    /// assert_eq!(probabilities.len(), 1);
+    /// assert_eq!(probabilities[&ProductState::new(&[Qubit::One]).unwrap()], 1f64);
+    /// ```
+    pub fn into_probabilities(self) -> HashMap<ProductState, f64> {
+        let num_qubits: usize = self.num_qubits;
+        self.register
+            .amplitudes
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, amp)| {
+                let probability: f64 = amp.norm_sqr().into_f64();
+                (probability > 0f64)
+                    .then(|| (ProductState::binary_basis(index, num_qubits), probability))
+            })
+            .collect()
+    }
+
+    /// Returns the exact probability of each outcome from measuring the given qubits, in the order
+    /// that they are given.
+    ///
+    /// Each leaf of the measurement tree is returned as a product state of the measured qubits,
+    /// where the first qubit in `order` is the first qubit of the product state. This is the same
+    /// as [SimulatedCircuit::probabilities], however restricted to the measured qubits. The leaves
+    /// are ordered in the computational basis, and include those with zero probability. An error
+    /// is returned if a qubit is out of bounds, or repeated.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// let leaves = simulated_circuit.measurement_tree(&[1, 0]).unwrap();
+    /// assert_eq!(leaves[1].0.to_string(), "01");
+    /// assert_eq!(leaves[1].1, 1f64);
+    /// ```
+    pub fn measurement_tree(&self, order: &[usize]) -> QResult<Vec<(ProductState, f64)>> {
+        self.register.check_subsystem(order)?;
+
+        let mut probabilities: Vec<f64> = vec![0f64; 1 << order.len()];
+        for (index, amp) in self.register.amplitudes.iter().enumerate() {
+            let leaf: usize = order.iter().fold(0, |acc, &q| {
+                (acc << 1) | ((index >> (self.num_qubits - 1 - q)) & 1)
+            });
+            probabilities[leaf] += amp.norm_sqr().into_f64();
+        }
+
+        Ok(probabilities
+            .into_iter()
+            .enumerate()
+            .map(|(leaf, probability)| (ProductState::binary_basis(leaf, order.len()), probability))
+            .collect())
+    }
+
+    /// Returns the amplitudes of the resulting superposition as `(re, im)` pairs, ordered in the
+    /// computational basis.
+    ///
+    /// This is a view of the state that does not depend on `num_complex`, which is useful for
+    /// bindings to other languages. Like [SimulatedCircuit::get_state], it is a non-physical
+    /// observable.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::X, 0).unwrap();
+    ///
+    /// assert_eq!(circuit.simulate().amplitudes_as_pairs(), vec![(0f64, 0f64), (1f64, 0f64)]);
+    /// ```
+    pub fn amplitudes_as_pairs(&self) -> Vec<(f64, f64)> {
+        self.register
+            .amplitudes
+            .iter()
+            .map(|amp| (amp.re.into_f64(), amp.im.into_f64()))
+            .collect()
+    }
+
+    /// Sets the seed of the random number generator that is used to measure the resulting
+    /// superposition, such as in [SimulatedCircuit::measure_all] and
+    /// [SimulatedCircuit::measure_all_without_cache].
+    ///
+    /// Each measurement starts from the seed, and so repeating a measurement gives the same
+    /// result. By default the seed is that of the circuit, given by [crate::Circuit::set_seed]. If
+    /// neither is set, a generator is seeded from the global generator of `fastrand` for each
+    /// measurement.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_repeating_gate(Gate::H, &[0, 1]).unwrap();
+    /// let mut simulated_circuit = circuit.simulate();
+    /// simulated_circuit.set_seed(42);
+    ///
+    /// assert_eq!(
+    ///     simulated_circuit.measure_all(100).take(),
+    ///     simulated_circuit.measure_all(100).take()
+    /// );
+    /// ```
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    fn rng(&self) -> Rng {
+        match self.seed {
+            Some(seed) => Rng::with_seed(seed),
+            None => Rng::new(),
+        }
+    }
+
+    /// Returns the outcomes of the [Gate::MeasureReset] gates from simulating the circuit, in the
+    /// order that the gates were applied.
+    ///
+    /// Each outcome is paired with the classical bit that is given by its gate. The list is empty
+    /// if the circuit has no such gates.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::Qubit;
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::X, 1).unwrap()
+    ///     .add_gate(Gate::MeasureReset(0), 0).unwrap()
+    ///     .add_gate(Gate::MeasureReset(1), 1).unwrap();
+    ///
+    /// let simulated_circuit = circuit.simulate();
+    /// assert_eq!(
+    ///     simulated_circuit.get_measurements(),
+    ///     &[(0, Qubit::Zero), (1, Qubit::One)]
+    /// );
+    /// ```
+    pub fn get_measurements(&self) -> &[(usize, Qubit)] {
+        &self.measurements
+    }
+
     /// Sets if the printer should display warnings.
     pub fn print_warnings(&mut self, printing: bool) {
         self.disable_warnings = printing;
@@ -182,7 +780,832 @@ impl SimulatedCircuit {
 
     /// Takes ownership of the state that the `SimulatedCircuit` wraps around, that is the state
     /// that resulted from a circuit simulation.
-    pub fn take_state(self) -> Measurement<SuperPosition> {
+    pub fn take_state(self) -> Measurement<SuperPosition<T>> {
         Measurement::NonObservable(self.register)
     }
 }
+
+impl SimulatedCircuit {
+    /// Returns the exact expectation value of a Hamiltonian, ⟨ψ|H|ψ⟩, for the resulting
+    /// superposition |ψ>, where the Hamiltonian is a weighted sum of Pauli strings.
+    ///
+    /// Each term is a weight together with the Pauli gates of its string, given as pairs of a wire
+    /// and one of [Gate::X], [Gate::Y], [Gate::Z] or [Gate::Id]. The remaining wires of the string
+    /// are the identity. The Pauli string is applied to a copy of the superposition, from which
+    /// the real part of the inner product with |ψ> is weighted and summed. An error is returned
+    /// if a gate is not a Pauli gate, or a wire is out of bounds or repeated within a term.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// // H = 2 X0 + Z0 Z1
+    /// let hamiltonian = [
+    ///     (2f64, vec![(0, Gate::X)]),
+    ///     (1f64, vec![(0, Gate::Z), (1, Gate::Z)]),
+    /// ];
+    ///
+    /// let expectation: f64 = simulated_circuit.expectation_hamiltonian(&hamiltonian).unwrap();
+    /// assert!((expectation - 2f64).abs() < 1e-10);
+    /// ```
+    pub fn expectation_hamiltonian(&self, terms: &[(f64, Vec<(usize, Gate)>)]) -> QResult<f64> {
+        let mut expectation: f64 = 0f64;
+        for (weight, pauli_string) in terms {
+            let positions: Vec<usize> =
+                pauli_string.iter().map(|(position, _)| *position).collect();
+            self.register.check_subsystem(&positions)?;
+
+            let mut column: Vec<Gate> = vec![Gate::Id; self.num_qubits];
+            for (position, gate) in pauli_string {
+                if !matches!(gate, Gate::Id | Gate::X | Gate::Y | Gate::Z) {
+                    return Err(QuantrError::Other {
+                        message: format!(
+                            "The gate, {:?}, of a Pauli string must be a Pauli gate or the identity.",
+                            gate
+                        ),
+                    });
+                }
+                column[*position] = gate.clone();
+            }
+
+            let categorised_gates: Vec<GateCategory> = column.iter().map(Gate::linker).collect();
+            let mut register: SuperPosition = self.register.clone();
+            Circuit::simulate_gates(
+                &column,
+                &categorised_gates,
+                self.num_qubits,
+                false,
+                &mut self.rng(),
+                &mut register,
+            )?;
+
+            let inner_product: f64 = zip(&self.register.amplitudes, &register.amplitudes)
+                .map(|(amp, pauli_amp)| (amp.conj() * pauli_amp).re)
+                .sum();
+            expectation += weight * inner_product;
+        }
+
+        Ok(expectation)
+    }
+
+    /// Returns the resulting superposition after a change of basis on the given wires, so that a
+    /// measurement in the computational basis is a measurement in the new basis.
+    ///
+    /// The gate `basis[i]` is applied on the wire `positions[i]`. For example, [Gate::H] rotates
+    /// the X-basis to the computational basis, and [Gate::Sdag] followed by [Gate::H] rotates the
+    /// Y-basis. The resulting superposition of the circuit is left unchanged. An error is returned
+    /// if the slices differ in length, a wire is out of bounds or repeated, or a gate is not a
+    /// single-qubit gate.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    /// use quantr::states::{ProductState, Qubit};
+    ///
+    /// // Prepares |+>.
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// let rotated = simulated_circuit.measure_in_basis(&[Gate::H], &[0]).unwrap();
+    /// assert_eq!(rotated.measure(), Some(ProductState::new(&[Qubit::Zero]).unwrap()));
+    /// ```
+    pub fn measure_in_basis(&self, basis: &[Gate], positions: &[usize]) -> QResult<SuperPosition> {
+        if basis.len() != positions.len() {
+            return Err(QuantrError::DimensionMismatch {
+                expected: positions.len(),
+                found: basis.len(),
+                message: format!(
+                    "The number of basis gates, {}, does not match the number of positions, {}.",
+                    basis.len(),
+                    positions.len()
+                ),
+            });
+        }
+        self.register.check_subsystem(positions)?;
+
+        let mut column: Vec<Gate> = vec![Gate::Id; self.num_qubits];
+        for (gate, &position) in zip(basis, positions) {
+            if !gate.is_single_gate() {
+                return Err(QuantrError::Other {
+                    message: format!("The basis gate, {:?}, must be a single-qubit gate.", gate),
+                });
+            }
+            column[position] = gate.clone();
+        }
+
+        let categorised_gates: Vec<GateCategory> = column.iter().map(Gate::linker).collect();
+        let mut register: SuperPosition = self.register.clone();
+        Circuit::simulate_gates(
+            &column,
+            &categorised_gates,
+            self.num_qubits,
+            false,
+            &mut self.rng(),
+            &mut register,
+        )?;
+        Ok(register)
+    }
+
+    /// Measures each wire in the given Pauli basis over a number of shots, returning the number of
+    /// times each product state is observed.
+    ///
+    /// The wire `i` is measured in `basis[i]`. This is equivalent to applying [Gate::H] before a
+    /// measurement in the computational basis for [PauliBasis::X], and [Gate::Sdag] followed by
+    /// [Gate::H] for [PauliBasis::Y]. The resulting superposition of the circuit is left
+    /// unchanged. An error is returned if the number of bases is not the number of qubits.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate, PauliBasis};
+    /// use quantr::states::{ProductState, Qubit};
+    ///
+    /// // Prepares |+>.
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// let bin_count = simulated_circuit.measure_all_in_basis(&[PauliBasis::X], 100).unwrap().take();
+    /// assert_eq!(bin_count[&ProductState::new(&[Qubit::Zero]).unwrap()], 100);
+    /// ```
+    pub fn measure_all_in_basis(
+        &self,
+        basis: &[PauliBasis],
+        shots: usize,
+    ) -> QResult<Measurement<HashMap<ProductState, usize>>> {
+        if basis.len() != self.num_qubits {
+            return Err(QuantrError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: basis.len(),
+                message: format!("The basis has {} entries, while the circuit has {} qubits. These must equal each other.", basis.len(), self.num_qubits),
+            });
+        }
+
+        // The first column applies Sdag to the Y wires, and the second applies H to the X and Y
+        // wires.
+        let rotation: Vec<Gate> = basis
+            .iter()
+            .map(|pauli| match pauli {
+                PauliBasis::Y => Gate::Sdag,
+                PauliBasis::X | PauliBasis::Z => Gate::Id,
+            })
+            .chain(basis.iter().map(|pauli| match pauli {
+                PauliBasis::X | PauliBasis::Y => Gate::H,
+                PauliBasis::Z => Gate::Id,
+            }))
+            .collect();
+
+        let categorised_gates: Vec<GateCategory> = rotation.iter().map(Gate::linker).collect();
+        let mut register: SuperPosition = self.register.clone();
+        Circuit::simulate_gates(
+            &rotation,
+            &categorised_gates,
+            self.num_qubits,
+            false,
+            &mut self.rng(),
+            &mut register,
+        )?;
+
+        let mut bin_count: HashMap<ProductState, usize> = Default::default();
+        let mut rng: Rng = self.rng();
+        for _ in 0..shots {
+            self.add_to_bin(&register, &mut bin_count, &mut rng);
+        }
+        Ok(Measurement::Observable(bin_count))
+    }
+
+    /// Compares the resulting superposition of the circuit to an ideal superposition, returning the
+    /// fidelity, trace distance and total variation distance between them.
+    ///
+    /// Both states are assumed to be normalised, and the fidelity is found with
+    /// [SuperPosition::fidelity]. An error is returned if the ideal superposition does not have the
+    /// same number of qubits as the circuit.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{states::SuperPosition, Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(1).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// let report = simulated_circuit
+    ///     .compare_to_ideal(&SuperPosition::new(1).unwrap())
+    ///     .unwrap();
+    /// assert!((report.fidelity - 0.5f64).abs() < 1e-10);
+    /// assert!((report.total_variation_distance - 0.5f64).abs() < 1e-10);
+    /// ```
+    pub fn compare_to_ideal(&self, ideal: &SuperPosition) -> QResult<ComparisonReport> {
+        let fidelity: f64 = ideal.fidelity(&self.register)?;
+        let total_variation_distance: f64 = zip(&ideal.amplitudes, &self.register.amplitudes)
+            .map(|(a, b)| (a.norm_sqr() - b.norm_sqr()).abs())
+            .sum::<f64>()
+            / 2f64;
+
+        Ok(ComparisonReport {
+            fidelity,
+            trace_distance: (1f64 - fidelity).max(0f64).sqrt(),
+            total_variation_distance,
+        })
+    }
+
+    /// Returns the Shannon entropy, -Σ p_i log2(p_i), in bits, of the probabilities of measuring
+    /// each product state of the resulting superposition.
+    ///
+    /// This quantifies how spread out the measurement outcomes are, from zero for a product state,
+    /// up to `num_qubits` for an equal superposition of all product states. Probabilities that
+    /// are effectively zero are skipped.
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// assert!((simulated_circuit.entropy() - 1f64).abs() < 1e-10);
+    /// ```
+    pub fn entropy(&self) -> f64 {
+        self.register.measurement_entropy()
+    }
+
+    /// Returns the conditional von Neumann entropy, S(A|B), in bits, where A is the subsystem of
+    /// the given wires, and B is the subsystem of the remaining wires, see
+    /// [SuperPosition::conditional_entropy].
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// // Prepares a Bell state.
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// let entropy: f64 = simulated_circuit.conditional_entropy(&[0]).unwrap();
+    /// assert!((entropy + 1f64).abs() < 1e-6);
+    /// ```
+    pub fn conditional_entropy(&self, subsystem_a: &[usize]) -> QResult<f64> {
+        self.register.conditional_entropy(subsystem_a)
+    }
+
+    /// Returns the Rényi entropy of order `alpha`, in bits, of the reduced state of the given
+    /// wires, see [SuperPosition::renyi_entropy].
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// // Prepares a Bell state.
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    /// let simulated_circuit = circuit.simulate();
+    ///
+    /// let entropy: f64 = simulated_circuit.renyi_entropy(&[0], 2f64).unwrap();
+    /// assert!((entropy - 1f64).abs() < 1e-6);
+    /// ```
+    pub fn renyi_entropy(&self, partition: &[usize], alpha: f64) -> QResult<f64> {
+        self.register.renyi_entropy(partition, alpha)
+    }
+
+    /// Returns whether any wire of the resulting superposition is entangled with the remaining
+    /// wires, within the tolerance, see [SuperPosition::is_entangled].
+    ///
+    /// # Example
+    /// ```
+    /// use quantr::{Circuit, Gate};
+    ///
+    /// // Prepares a Bell state.
+    /// let mut circuit = Circuit::new(2).unwrap();
+    /// circuit.add_gate(Gate::H, 0).unwrap()
+    ///     .add_gate(Gate::CNot(0), 1).unwrap();
+    ///
+    /// assert!(circuit.simulate().is_entangled(1e-10));
+    /// ```
+    pub fn is_entangled(&self, tol: f64) -> bool {
+        self.register.is_entangled(tol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PauliBasis;
+    use crate::states::{ProductState, Qubit, SuperPosition};
+    use crate::{complex_re, Circuit, Gate};
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    #[test]
+    fn expectation_of_z_hamiltonian() {
+        let hamiltonian = [(0.5f64, vec![(0, Gate::Z)]), (0.5f64, vec![(1, Gate::Z)])];
+
+        // |00> has an expectation of 1, |10> of 0, and |11> of -1.
+        for (gates, expected) in [(vec![], 1f64), (vec![0], 0f64), (vec![0, 1], -1f64)] {
+            let mut circuit = Circuit::new(2).unwrap();
+            circuit.add_repeating_gate(Gate::X, &gates).unwrap();
+            let expectation: f64 = circuit
+                .simulate()
+                .expectation_hamiltonian(&hamiltonian)
+                .unwrap();
+            assert!((expectation - expected).abs() < 1e-10);
+        }
+
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap();
+        assert!(circuit
+            .simulate()
+            .expectation_hamiltonian(&[(1f64, vec![(0, Gate::H)])])
+            .is_err());
+    }
+
+    #[test]
+    fn histogram_of_deterministic_circuit() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::X, 1).unwrap();
+        let histogram: String = circuit.simulate().histogram_string(100, 5);
+
+        assert_eq!(histogram.lines().count(), 1);
+        assert_eq!(histogram, format!("|01> {} 100\n", "#".repeat(50)));
+    }
+
+    #[test]
+    fn conditional_entropy_of_bell_pair() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit
+            .add_gate(Gate::H, 0)
+            .unwrap()
+            .add_gate(Gate::CNot(0), 1)
+            .unwrap();
+        let simulated_circuit = circuit.simulate();
+
+        let entropy: f64 = simulated_circuit.conditional_entropy(&[0]).unwrap();
+        assert!((entropy + 1f64).abs() < 1e-6);
+        assert!(simulated_circuit.conditional_entropy(&[2]).is_err());
+        assert!(simulated_circuit.conditional_entropy(&[1, 1]).is_err());
+    }
+
+    #[test]
+    fn conditional_entropy_of_product_state() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_repeating_gate(Gate::H, &[0, 1]).unwrap();
+
+        let entropy: f64 = circuit.simulate().conditional_entropy(&[1]).unwrap();
+        assert!(entropy.abs() < 1e-6);
+    }
+
+    #[test]
+    fn sampled_statistics_of_z_parity() {
+        fastrand::seed(0);
+        let mut circuit = Circuit::new(1).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap();
+
+        let z_parity = |state: &ProductState| {
+            if state
+                .get_qubits()
+                .iter()
+                .filter(|&&q| q == Qubit::One)
+                .count()
+                % 2
+                == 0
+            {
+                1f64
+            } else {
+                -1f64
+            }
+        };
+        let (mean, variance) = circuit.simulate().sampled_statistics(2000, z_parity);
+
+        assert!(mean.abs() < 0.1f64);
+        assert!((variance - 1f64).abs() < 0.1f64);
+    }
+
+    #[test]
+    fn sample_iter_of_hadamard_circuit() {
+        let mut circuit = Circuit::new(1).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap();
+        let mut simulated_circuit = circuit.simulate();
+        simulated_circuit.set_seed(11);
+
+        let ones: usize = simulated_circuit
+            .sample_iter()
+            .take(1000)
+            .filter(|state| state.get_qubits()[0] == Qubit::One)
+            .count();
+        assert!(ones > 400 && ones < 600);
+
+        // The same seed gives the same samples.
+        let first: Vec<ProductState> = simulated_circuit.sample_iter().take(20).collect();
+        let second: Vec<ProductState> = simulated_circuit.sample_iter().take(20).collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn measures_single_qubit_of_register() {
+        fastrand::seed(0);
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gate(Gate::H, 2).unwrap();
+
+        let bin_count = circuit
+            .simulate()
+            .measure_qubits(&[2], 1000)
+            .unwrap()
+            .take();
+
+        assert_eq!(bin_count.len(), 2);
+        for (state, count) in bin_count {
+            assert_eq!(state.num_qubits(), 1);
+            assert!(count > 400 && count < 600);
+        }
+    }
+
+    #[test]
+    fn catches_measuring_out_of_bounds_qubit() {
+        let circuit = Circuit::new(2).unwrap();
+        assert!(circuit.simulate().measure_qubits(&[2], 10).is_err());
+    }
+
+    #[test]
+    fn measure_all_without_cache_matches_cached_distribution() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit
+            .add_repeating_gate(Gate::H, &[0, 1])
+            .unwrap()
+            .add_gate(Gate::CNot(1), 2)
+            .unwrap()
+            .add_gate(Gate::Ry(0.8f64), 0)
+            .unwrap();
+
+        // Simulating a unitary circuit does not draw random numbers, and so both methods observe
+        // the same states for the same seed.
+        let mut cached_circuit = circuit.clone_and_simulate();
+        cached_circuit.set_seed(3);
+        let cached = cached_circuit.measure_all(300).take();
+
+        let mut uncached_circuit = circuit.simulate();
+        uncached_circuit.set_seed(3);
+        let uncached = uncached_circuit.measure_all_without_cache(300).take();
+
+        assert_eq!(cached, uncached);
+    }
+
+    #[test]
+    fn measure_all_without_cache_from_reset_register() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::H, 1).unwrap();
+        let mut simulated_circuit = circuit.simulate();
+        simulated_circuit
+            .set_reset_register(
+                ProductState::new(&[Qubit::One, Qubit::Zero])
+                    .unwrap()
+                    .into(),
+            )
+            .unwrap();
+        assert!(simulated_circuit
+            .set_reset_register(SuperPosition::new(3).unwrap())
+            .is_err());
+
+        // The first wire is always |1>, whereas the second is in an equal superposition.
+        let bin_count = simulated_circuit.measure_all_without_cache(1000).take();
+        assert_eq!(bin_count.len(), 2);
+        for (state, count) in bin_count {
+            assert!(state.to_string() == "10" || state.to_string() == "11");
+            assert!(count > 400 && count < 600);
+        }
+    }
+
+    #[test]
+    fn links_gates_once_for_repeated_simulations() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit
+            .add_gate(Gate::H, 0)
+            .unwrap()
+            .add_gate(Gate::CNot(0), 1)
+            .unwrap();
+        let simulated_circuit = circuit.simulate();
+
+        // The gates are linked when the circuit is simulated, and the same links are returned for
+        // each subsequent simulation.
+        let linked_gates = simulated_circuit.categorised_gates.get().unwrap().as_ptr();
+        let relinked_gates = Circuit::link_gates(
+            &simulated_circuit.categorised_gates,
+            &simulated_circuit.circuit_gates,
+        )
+        .as_ptr();
+        assert_eq!(linked_gates, relinked_gates);
+
+        let bin_count = simulated_circuit.measure_all_without_cache(400).take();
+        assert_eq!(bin_count.len(), 2);
+        for state in bin_count.keys() {
+            assert!(state.to_string() == "00" || state.to_string() == "11");
+        }
+    }
+
+    #[test]
+    fn measurement_tree_of_bell_pair() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit
+            .add_gate(Gate::H, 0)
+            .unwrap()
+            .add_gate(Gate::CNot(0), 1)
+            .unwrap();
+        let leaves: Vec<(ProductState, f64)> =
+            circuit.simulate().measurement_tree(&[0, 1]).unwrap();
+
+        assert_eq!(leaves.len(), 4);
+        for (state, probability) in leaves {
+            match state.to_string().as_str() {
+                "00" | "11" => assert!((probability - 0.5f64).abs() < 1e-10),
+                _ => assert!(probability.abs() < 1e-10),
+            }
+        }
+    }
+
+    #[test]
+    fn compares_circuit_to_its_own_state() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit
+            .add_gate(Gate::H, 0)
+            .unwrap()
+            .add_gate(Gate::CNot(0), 1)
+            .unwrap();
+        let simulated_circuit = circuit.simulate();
+        let ideal: SuperPosition = simulated_circuit.register.clone();
+
+        let report = simulated_circuit.compare_to_ideal(&ideal).unwrap();
+        assert!((report.fidelity - 1f64).abs() < 1e-10);
+        assert!(report.trace_distance.abs() < 1e-6);
+        assert!(report.total_variation_distance.abs() < 1e-10);
+
+        assert!(simulated_circuit
+            .compare_to_ideal(&SuperPosition::new(3).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn measures_plus_state_in_x_basis() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_repeating_gate(Gate::H, &[0, 1]).unwrap();
+        let simulated_circuit = circuit.simulate();
+
+        let rotated: SuperPosition = simulated_circuit
+            .measure_in_basis(&[Gate::H], &[1])
+            .unwrap();
+        for _ in 0..20 {
+            assert_eq!(rotated.measure().unwrap().get_unchecked(1), Qubit::Zero);
+        }
+
+        assert!(simulated_circuit
+            .measure_in_basis(&[Gate::H, Gate::H], &[0])
+            .is_err());
+        assert!(simulated_circuit
+            .measure_in_basis(&[Gate::CNot(0)], &[1])
+            .is_err());
+        assert!(simulated_circuit
+            .measure_in_basis(&[Gate::H], &[2])
+            .is_err());
+    }
+
+    #[test]
+    fn into_probabilities_matches_probabilities() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit
+            .add_gate(Gate::Ry(0.8f64), 0)
+            .unwrap()
+            .add_gate(Gate::CNot(0), 1)
+            .unwrap();
+        let simulated_circuit = circuit.simulate();
+        let probabilities: Vec<f64> = simulated_circuit.probabilities();
+        let probability_map = simulated_circuit.into_probabilities();
+
+        assert_eq!(probability_map.len(), 2);
+        for (index, probability) in probabilities.into_iter().enumerate() {
+            let state = ProductState::binary_basis(index, 2);
+            assert_eq!(
+                probability_map.get(&state).copied().unwrap_or(0f64),
+                probability
+            );
+        }
+    }
+
+    #[test]
+    fn amplitude_of_hadamard_on_last_qubit() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_gate(Gate::H, 2).unwrap();
+        let simulated_circuit = circuit.simulate();
+
+        let zero_state = ProductState::new(&[Qubit::Zero; 3]).unwrap();
+        let amplitude = simulated_circuit.amplitude_of(&zero_state).unwrap();
+        assert!((amplitude - complex_re!(FRAC_1_SQRT_2)).norm() < 1e-10);
+        assert!(simulated_circuit
+            .amplitude_of(&ProductState::new(&[Qubit::Zero; 2]).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn renyi_entropy_of_entangled_states() {
+        let mut bell_circuit = Circuit::new(2).unwrap();
+        bell_circuit
+            .add_gate(Gate::H, 0)
+            .unwrap()
+            .add_gate(Gate::CNot(0), 1)
+            .unwrap();
+        let renyi_two: f64 = bell_circuit.simulate().renyi_entropy(&[0], 2f64).unwrap();
+        assert!((renyi_two - 1f64).abs() < 1e-6);
+
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit
+            .add_gate(Gate::Ry(0.8f64), 0)
+            .unwrap()
+            .add_gate(Gate::CNot(0), 1)
+            .unwrap();
+        let simulated_circuit = circuit.simulate();
+        let von_neumann: f64 = -simulated_circuit.conditional_entropy(&[1]).unwrap();
+
+        assert_eq!(
+            simulated_circuit.renyi_entropy(&[0], 1f64).unwrap(),
+            von_neumann
+        );
+        let near_limit: f64 = simulated_circuit.renyi_entropy(&[0], 1.0001f64).unwrap();
+        assert!((near_limit - von_neumann).abs() < 1e-3);
+        assert!(simulated_circuit.renyi_entropy(&[0], -1f64).is_err());
+        assert!(simulated_circuit.renyi_entropy(&[2], 2f64).is_err());
+    }
+
+    #[test]
+    fn measures_plus_state_in_x_and_z_bases() {
+        let mut circuit = Circuit::new(1).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap();
+        let mut simulated_circuit = circuit.simulate();
+        simulated_circuit.set_seed(7);
+        let zero = ProductState::new(&[Qubit::Zero]).unwrap();
+        let one = ProductState::new(&[Qubit::One]).unwrap();
+
+        let x_bin = simulated_circuit
+            .measure_all_in_basis(&[PauliBasis::X], 1000)
+            .unwrap()
+            .take();
+        assert_eq!(x_bin.get(&zero), Some(&1000));
+        assert_eq!(x_bin.get(&one), None);
+
+        let z_bin = simulated_circuit
+            .measure_all_in_basis(&[PauliBasis::Z], 1000)
+            .unwrap()
+            .take();
+        assert!(z_bin[&zero].abs_diff(500) < 60);
+        assert_eq!(z_bin[&zero] + z_bin[&one], 1000);
+
+        assert!(simulated_circuit
+            .measure_all_in_basis(&[PauliBasis::X, PauliBasis::Y], 10)
+            .is_err());
+    }
+
+    #[test]
+    fn probabilities_of_hadamard_circuit() {
+        let mut circuit = Circuit::new(1).unwrap();
+        circuit.add_gate(Gate::H, 0).unwrap();
+        let probabilities: Vec<f64> = circuit.simulate().probabilities();
+
+        assert_eq!(probabilities.len(), 2);
+        for probability in probabilities {
+            assert!((probability - 0.5f64).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn entropy_of_basis_and_equal_superpositions() {
+        let mut basis_circuit = Circuit::new(3).unwrap();
+        basis_circuit.add_gate(Gate::X, 1).unwrap();
+        assert!(basis_circuit.simulate().entropy().abs() < 1e-10);
+
+        let mut equal_circuit = Circuit::new(3).unwrap();
+        equal_circuit
+            .add_repeating_gate(Gate::H, &[0, 1, 2])
+            .unwrap();
+        assert!((equal_circuit.simulate().entropy() - 3f64).abs() < 1e-10);
+    }
+
+    #[test]
+    fn seeded_circuits_measure_identically() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_repeating_gate(Gate::H, &[0, 1, 2]).unwrap();
+
+        let mut first_circuit = circuit.clone_and_simulate();
+        first_circuit.set_seed(7);
+        let mut second_circuit = circuit.simulate();
+        second_circuit.set_seed(7);
+
+        assert_eq!(
+            first_circuit.measure_all(500).take(),
+            second_circuit.measure_all(500).take()
+        );
+    }
+
+    #[test]
+    fn expectation_z_of_single_qubit() {
+        let zero_circuit = Circuit::new(1).unwrap();
+        assert_eq!(zero_circuit.simulate().expectation_z(&[0]).unwrap(), 1f64);
+
+        let mut one_circuit = Circuit::new(1).unwrap();
+        one_circuit.add_gate(Gate::X, 0).unwrap();
+        assert_eq!(one_circuit.simulate().expectation_z(&[0]).unwrap(), -1f64);
+
+        let mut plus_circuit = Circuit::new(1).unwrap();
+        plus_circuit.add_gate(Gate::H, 0).unwrap();
+        let simulated_plus = plus_circuit.simulate();
+        assert!(simulated_plus.expectation_z(&[0]).unwrap().abs() < 1e-10);
+        assert!(simulated_plus.expectation_z(&[1]).is_err());
+    }
+
+    #[test]
+    fn measurement_report_of_deterministic_circuit() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_gate(Gate::X, 0).unwrap();
+
+        assert_eq!(
+            circuit.simulate().measurement_report(100),
+            vec![(
+                ProductState::new_unchecked(&[Qubit::One, Qubit::Zero]),
+                100usize,
+                1f64
+            )]
+        );
+    }
+
+    #[test]
+    fn measurement_report_is_sorted() {
+        fastrand::seed(0);
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit.add_repeating_gate(Gate::H, &[0, 1]).unwrap();
+
+        let report = circuit.simulate().measurement_report(500);
+        let indices: Vec<usize> = report
+            .iter()
+            .map(|(state, _, _)| state.comp_basis())
+            .collect();
+        assert_eq!(indices, vec![0, 1, 2, 3]);
+        assert_eq!(report.iter().map(|(_, count, _)| count).sum::<usize>(), 500);
+        assert!((report.iter().map(|(_, _, prob)| prob).sum::<f64>() - 1f64).abs() < 1e-10);
+    }
+
+    #[test]
+    fn samples_qubit_with_marginal() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit
+            .add_gate(Gate::H, 1)
+            .unwrap()
+            .add_gate(Gate::X, 2)
+            .unwrap();
+        let simulated = circuit.simulate();
+
+        let (_, probability_one) = simulated.sample_qubit(1).unwrap();
+        assert!((probability_one - 0.5f64).abs() < 1e-10);
+
+        assert_eq!(simulated.sample_qubit(0).unwrap(), (Qubit::Zero, 0f64));
+        let (observed, probability_one) = simulated.sample_qubit(2).unwrap();
+        assert_eq!(observed, Qubit::One);
+        assert!((probability_one - 1f64).abs() < 1e-10);
+    }
+
+    #[test]
+    #[should_panic]
+    fn catches_sampling_qubit_out_of_bounds() {
+        let circuit = Circuit::new(2).unwrap();
+        circuit.simulate().sample_qubit(2).unwrap();
+    }
+
+    #[test]
+    fn amplitudes_as_pairs_match_state() {
+        let mut circuit = Circuit::new(2).unwrap();
+        circuit
+            .add_gate(Gate::H, 0)
+            .unwrap()
+            .add_gate(Gate::Y, 1)
+            .unwrap();
+        let simulated = circuit.simulate();
+
+        let pairs: Vec<(f64, f64)> = simulated
+            .get_state()
+            .take()
+            .get_amplitudes()
+            .iter()
+            .map(|amp| (amp.re, amp.im))
+            .collect();
+        assert_eq!(simulated.amplitudes_as_pairs(), pairs);
+    }
+
+    #[test]
+    fn detects_entanglement_of_simulated_circuit() {
+        let mut circuit = Circuit::new(3).unwrap();
+        circuit.add_repeating_gate(Gate::H, &[0, 2]).unwrap();
+        assert!(!circuit.clone_and_simulate().is_entangled(1e-10));
+
+        circuit.add_gate(Gate::CNot(0), 1).unwrap();
+        assert!(circuit.simulate().is_entangled(1e-10));
+    }
+}
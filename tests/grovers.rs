@@ -157,15 +157,15 @@ fn x3sudoko() -> Result<(), Box<dyn Error>> {
 fn multicnot<const NUM_CONTROL: usize>(input_state: ProductState) -> Option<SuperPosition> {
     let mut copy_state = input_state;
     if copy_state.get_qubits() == [Qubit::One; NUM_CONTROL] {
-        copy_state.get_mut_qubits()[NUM_CONTROL - 1] = Qubit::Zero;
-        return Some(copy_state.into());
+        copy_state.set(NUM_CONTROL - 1, Qubit::Zero).ok()?;
+        Some(copy_state.into())
     } else if copy_state.get_qubits() == {
         let mut temp = [Qubit::One; NUM_CONTROL];
         temp[NUM_CONTROL - 1] = Qubit::Zero;
         temp
     } {
-        copy_state.get_mut_qubits()[NUM_CONTROL - 1] = Qubit::One;
-        return Some(copy_state.into());
+        copy_state.set(NUM_CONTROL - 1, Qubit::One).ok()?;
+        Some(copy_state.into())
     } else {
         None
     }
@@ -182,3 +182,34 @@ fn compare_complex_lists_and_register(correct_list: &[Complex64], register: &Sup
 fn equal_within_error(num: f64, compare_num: f64) -> bool {
     num < compare_num + ERROR_MARGIN && num > compare_num - ERROR_MARGIN
 }
+
+#[test]
+fn mcx_matches_multicnot() -> Result<(), Box<dyn Error>> {
+    for num_controls in [3, 4] {
+        let controls: Vec<usize> = (0..num_controls).collect();
+        let multicnot_gate: Gate = match num_controls {
+            3 => Gate::Custom(multicnot::<4>, controls.clone(), "X".to_string()),
+            _ => Gate::Custom(multicnot::<5>, controls.clone(), "X".to_string()),
+        };
+
+        let mut registers: Vec<SuperPosition> = Vec::new();
+        for gate in [Gate::MCX(controls.clone()), multicnot_gate] {
+            let mut circuit: Circuit = Circuit::new(num_controls + 1)?;
+            circuit
+                .add_repeating_gate(Gate::H, &controls)?
+                .add_gate(Gate::Ry(0.3f64), num_controls)?
+                .add_gate(gate, num_controls)?;
+            registers.push(circuit.simulate().take_state().take());
+        }
+
+        // The target flips only when all controls are |1>.
+        let flipped_index: usize = (1 << (num_controls + 1)) - 2;
+        assert!(
+            registers[0].get_amplitudes()[flipped_index].re
+                < registers[0].get_amplitudes()[flipped_index + 1].re
+        );
+        compare_complex_lists_and_register(registers[1].get_amplitudes(), &registers[0]);
+    }
+
+    Ok(())
+}